@@ -1,63 +1,277 @@
 // Importing necessary functions from the graph layout package and the D3.js library
-import init, { process_random, process_force_atlas2, process_circular, process_fruchterman_reingold, process_stress_majorization, process_multidimensional_scaling } from './pkg/graph_layout.js';
+import init, { process_random, process_force_atlas2, process_circular, process_fruchterman_reingold, process_stress_majorization, process_multidimensional_scaling, process_classical_mds, process_kamada_kawai, process_layered, process_tree, process_radial_tree, process_spectral, process_multilevel, process_remove_overlaps, compute_metrics, ForceAtlas2Settings, LayoutOptions, LayoutSession } from './pkg/graph_layout.js';
 import * as d3 from "https://cdn.jsdelivr.net/npm/d3@7/+esm";
 
 // Variable to hold the uploaded algorithm, if any
 let uploadedAlgorithm = null;
 
+// Nodes pinned by the user, node label -> node index and coordinates in the layout space (before scaling to the SVG)
+const pinnedNodes = new Map();
+
+// Currently running animation (layout session and pending animation frame), if any
+let animation = null;
+
+// Nodes of the current drawing, the iterative layouts can continue from their layout coordinates
+let drawnNodes = [];
+
+// Number of layout iterations computed between two redraws of an animation
+const ANIMATION_STEP = 10;
+
+// Radius of the drawn nodes in pixels
+let nodeRadius = 5;
+
 // Function to update the visibility of control elements based on the selected algorithm
 function updateControlVisibility(algorithm) {
-    const iterationsAlgorithms = ['process_force_atlas2', 'process_fruchterman_reingold', 'process_kamada_kawai', 'process_stress_majorization', 'process_multidimensional_scaling'];
-    const gravityAlgorithms = ['process_force_atlas2', 'process_fruchterman_reingold'];
+    const iterationsAlgorithms = ['process_force_atlas2', 'process_fruchterman_reingold', 'process_multilevel', 'process_kamada_kawai', 'process_stress_majorization', 'process_multidimensional_scaling'];
+    const gravityAlgorithms = ['process_force_atlas2', 'process_fruchterman_reingold', 'process_multilevel'];
     const scalingRatioAlgorithms = ['process_force_atlas2'];
+    const thetaAlgorithms = ['process_force_atlas2', 'process_fruchterman_reingold', 'process_multilevel'];
+    const springConstantAlgorithms = ['process_kamada_kawai'];
+    const pivotsAlgorithms = ['process_stress_majorization', 'process_multidimensional_scaling', 'process_classical_mds'];
+    const toleranceAlgorithms = ['process_stress_majorization', 'process_force_atlas2', 'process_fruchterman_reingold', 'process_multilevel'];
+    const coolingAlgorithms = ['process_force_atlas2', 'process_fruchterman_reingold', 'process_multilevel'];
+    const warmStartAlgorithms = ['process_force_atlas2', 'process_fruchterman_reingold', 'process_multilevel', 'process_kamada_kawai', 'process_stress_majorization', 'process_multidimensional_scaling'];
+    const layeredAlgorithms = ['process_layered'];
+    const spacingAlgorithms = ['process_layered', 'process_tree', 'process_radial_tree'];
+    const rootAlgorithms = ['process_tree', 'process_radial_tree'];
+    const normalizedAlgorithms = ['process_spectral'];
+    const multilevelAlgorithms = ['process_multilevel'];
+    const circularAlgorithms = ['process_circular'];
+    const forceAtlas2Algorithms = ['process_force_atlas2'];
 
     document.getElementById('iterations-group').style.display = iterationsAlgorithms.includes(algorithm) ? 'flex' : 'none';
     document.getElementById('gravity-group').style.display = gravityAlgorithms.includes(algorithm) ? 'flex' : 'none';
     document.getElementById('scaling-ratio-group').style.display = scalingRatioAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('theta-group').style.display = thetaAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('spring-constant-group').style.display = springConstantAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('pivots-group').style.display = pivotsAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('tolerance-group').style.display = toleranceAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('cooling-group').style.display = coolingAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('warm-start-group').style.display = warmStartAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('direction-group').style.display = layeredAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('node-spacing-group').style.display = spacingAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('layer-spacing-group').style.display = spacingAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('root-group').style.display = rootAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('normalized-group').style.display = normalizedAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('levels-group').style.display = multilevelAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('refinement-iterations-group').style.display = multilevelAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('ordering-group').style.display = circularAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    document.getElementById('rings-group').style.display = circularAlgorithms.includes(algorithm) ? 'flex' : 'none';
+    ['lin-log-group', 'dissuade-hubs-group', 'strong-gravity-group', 'edge-weight-influence-group', 'prevent-overlap-group', 'jitter-tolerance-group'].forEach(id => {
+        document.getElementById(id).style.display = forceAtlas2Algorithms.includes(algorithm) ? 'flex' : 'none';
+    });
 }
 
 // Function to run the selected graph processing algorithm with the provided parameters
-async function run(graphStr, algorithm, iterations, gravity, scalingRatio) {
+async function run(graphStr, algorithm, iterations, gravity, scalingRatio, theta, linLog, dissuadeHubs, strongGravity, edgeWeightInfluence, preventOverlap, jitterTolerance, springConstant, pivots, tolerance, cooling, direction, nodeSpacing, layerSpacing, root, normalized, levels, refinementIterations, ordering, rings, radius, removeOverlaps, margin, keepAspectRatio, clamp, warmStart, seed) {
     await init();
+    nodeRadius = radius;
 
     let processedGraph;
-
-    // Switch statement to handle different algorithms
-    switch (algorithm) {
-        case "process_random":
-            processedGraph = process_random(graphStr);
-            break;
-        case "process_force_atlas2":
-            processedGraph = process_force_atlas2(graphStr, iterations, gravity, scalingRatio);
-            break;
-        case "process_circular":
-            processedGraph = process_circular(graphStr);
-            break;
-        case "process_fruchterman_reingold":
-            processedGraph = process_fruchterman_reingold(graphStr, iterations, gravity);
-            break;
-        case "process_stress_majorization":
-            processedGraph = process_stress_majorization(graphStr, iterations);
-            break;
-        case "process_multidimensional_scaling":
-            processedGraph = process_multidimensional_scaling(graphStr, iterations);
-            break;
-        case "uploaded_algorithm":
-            if (uploadedAlgorithm) {
-                processedGraph = uploadedAlgorithm(graphStr); // Just pass graphStr
-            } else {
-                throw new Error("No uploaded algorithm available");
+    // What the iterative layouts report about their convergence, shown next to the metrics
+    let convergence = null;
+    const pinned = pinnedString();
+    const initial = warmStart ? initialString() : "";
+    // The layouts are fitted into the SVG, so their coordinates are pixels
+    const options = drawingArea(margin, keepAspectRatio, clamp);
+
+    try {
+        // Switch statement to handle different algorithms
+        switch (algorithm) {
+            case "process_random":
+                processedGraph = process_random(graphStr, pinned, options, seed);
+                break;
+            case "process_force_atlas2": {
+                const settings = forceAtlas2Settings({ gravity, scalingRatio, theta, linLog, dissuadeHubs, strongGravity, edgeWeightInfluence, preventOverlap, jitterTolerance });
+                try {
+                    ({ layout: processedGraph, convergence } = unpackResult(process_force_atlas2(graphStr, pinned, initial, options, settings, nodeSizesString(graphStr, options), iterations, cooling, tolerance, seed)));
+                } finally {
+                    settings.free();
+                }
+                break;
             }
-            break;
-        default:
-            throw new Error("Unknown algorithm");
+            case "process_circular":
+                processedGraph = process_circular(graphStr, pinned, options, ordering, rings);
+                break;
+            case "process_fruchterman_reingold":
+                ({ layout: processedGraph, convergence } = unpackResult(process_fruchterman_reingold(graphStr, pinned, initial, options, iterations, gravity, theta, cooling, tolerance, seed)));
+                break;
+            case "process_multilevel":
+                ({ layout: processedGraph, convergence } = unpackResult(process_multilevel(graphStr, pinned, initial, options, levels, iterations, refinementIterations, gravity, theta, cooling, tolerance, seed)));
+                break;
+            case "process_stress_majorization":
+                ({ layout: processedGraph, convergence } = unpackResult(process_stress_majorization(graphStr, pinned, initial, options, iterations, pivots, tolerance, seed)));
+                break;
+            case "process_multidimensional_scaling":
+                processedGraph = process_multidimensional_scaling(graphStr, pinned, initial, options, iterations, pivots, seed);
+                break;
+            case "process_classical_mds":
+                processedGraph = process_classical_mds(graphStr, pinned, options, pivots);
+                break;
+            case "process_spectral":
+                processedGraph = process_spectral(graphStr, pinned, options, normalized);
+                break;
+            case "process_kamada_kawai":
+                ({ layout: processedGraph, convergence } = unpackResult(process_kamada_kawai(graphStr, pinned, initial, options, iterations, springConstant, seed)));
+                break;
+            case "process_layered":
+                processedGraph = process_layered(graphStr, pinned, options, direction, nodeSpacing, layerSpacing);
+                break;
+            case "process_tree":
+                processedGraph = process_tree(graphStr, pinned, options, root, nodeSpacing, layerSpacing);
+                break;
+            case "process_radial_tree":
+                processedGraph = process_radial_tree(graphStr, pinned, options, root, nodeSpacing, layerSpacing);
+                break;
+            case "uploaded_algorithm":
+                if (uploadedAlgorithm) {
+                    processedGraph = uploadedAlgorithm(graphStr); // Just pass graphStr
+                } else {
+                    throw new Error("No uploaded algorithm available");
+                }
+                break;
+            default:
+                throw new Error("Unknown algorithm");
+        }
+    } finally {
+        options.free();
     }
 
     let { nodes, edges } = parseGraph(processedGraph);
 
-    // Scaling and updating the graph with processed data
-    scaleGraph(nodes);
+    // Only uploaded algorithms still need scaling, the others already fill the SVG
+    if (algorithm === "uploaded_algorithm") {
+        scaleGraph(nodes);
+    } else {
+        keepLayoutCoordinates(nodes);
+    }
+    if (removeOverlaps) {
+        separateNodes(nodes, processedGraph);
+    }
     updateGraph(nodes, edges);
+    showMetrics(nodes, processedGraph, convergence);
+}
+
+// Function to read the layout and the reported convergence out of a LayoutResult and free it
+function unpackResult(run) {
+    try {
+        return { layout: run.layout, convergence: JSON.parse(run.result) };
+    } finally {
+        run.free();
+    }
+}
+
+// Function to give every node of the graph a square of side 2 * nodeRadius in pixels, used by ForceAtlas2 to prevent overlap
+function nodeSizesString(graphStr, options) {
+    const labels = new Set(parseGraph(process_random(graphStr, "", options, 0)).nodes.map(node => node.label));
+    return [...labels].map(label => `${quoteLabel(label)}:${2 * nodeRadius},${2 * nodeRadius}`).join(';');
+}
+
+// Function to write the drawn nodes and the edges of the processed graph in the laid out format of the layout engine
+function screenLayoutString(nodes, processedGraph) {
+    const nodesPart = nodes.map(node => `${quoteLabel(node.label)}:${node.x},${node.y}`).join(';');
+    return `nodes: ${nodesPart};edges: ${processedGraph.split('edges:')[1]}`;
+}
+
+// Function to move the drawn nodes apart until their circles (and a small gap) do not overlap, pinned nodes stay in place
+function separateNodes(nodes, processedGraph) {
+    const side = 2 * nodeRadius + 2;
+    const sizes = nodes.map(node => `${quoteLabel(node.label)}:${side},${side}`).join(';');
+    const pinned = nodes.filter(node => pinnedNodes.has(node.label)).map(node => `${quoteLabel(node.label)}:${node.x},${node.y}`).join(';');
+    const separated = parseGraph(process_remove_overlaps(screenLayoutString(nodes, processedGraph), sizes, pinned)).nodes;
+    nodes.forEach((node, index) => {
+        node.x = separated[index].x;
+        node.y = separated[index].y;
+    });
+}
+
+// Function to display the quality metrics of the drawing, measured in screen coordinates with the drawn node radius
+function showMetrics(nodes, processedGraph, convergence) {
+    const metricsDiv = document.getElementById('metrics');
+    try {
+        const metrics = JSON.parse(compute_metrics(screenLayoutString(nodes, processedGraph), nodeRadius));
+        const angle = metrics.angular_resolution === null ? '-' : `${metrics.angular_resolution.toFixed(1)}°`;
+        metricsDiv.textContent = convergenceText(convergence) + `Crossings: ${metrics.edge_crossings} | Stress: ${metrics.normalized_stress.toFixed(4)}`
+            + ` | Edge length variance: ${metrics.edge_length_variance.toFixed(4)} | Angular resolution: ${angle}`
+            + ` | Overlaps: ${metrics.node_overlaps} | Neighborhood preservation: ${metrics.neighborhood_preservation.toFixed(3)}`;
+    } catch (error) {
+        // Uploaded algorithms may return layouts the engine cannot read back
+        console.error(error);
+        metricsDiv.textContent = '';
+    }
+}
+
+// Function to describe the final stress or energy of an iterative layout and the iterations it took, empty for the others
+function convergenceText(convergence) {
+    if (!convergence) {
+        return '';
+    }
+    const [measure, value] = 'stress' in convergence ? ['Layout stress', convergence.stress] : ['Energy', convergence.energy];
+    const converged = convergence.converged ? ' (converged)' : '';
+    return `${measure}: ${value.toPrecision(4)} | Iterations: ${convergence.iterations}${converged} | `;
+}
+
+// Function to encode the pinned nodes in the "id:x,y;id:x,y" format expected by the layout engine
+function pinnedString() {
+    return [...pinnedNodes].map(([label, position]) => `${quoteLabel(label)}:${position.x},${position.y}`).join(';');
+}
+
+// Function to write the layout coordinates of the drawn nodes as starting positions for the next layout
+function initialString() {
+    return drawnNodes.map(node => `${quoteLabel(node.label)}:${node.layoutX},${node.layoutY}`).join(';');
+}
+
+// Function to write a node label the way the layout engine reads it, labels with special characters are quoted
+function quoteLabel(label) {
+    if (/^[^\s,;:\->"]+$/.test(label)) {
+        return label;
+    }
+    return `"${label.replace(/\\/g, '\\\\').replace(/"/g, '\\"')}"`;
+}
+
+// Function to split a string at every separator that is not inside a quoted node label
+function splitUnquoted(text, separator) {
+    const parts = [];
+    let start = 0;
+    let inQuotes = false;
+    for (let i = 0; i < text.length; i++) {
+        if (inQuotes && text[i] === '\\') {
+            i++;
+        } else if (text[i] === '"') {
+            inQuotes = !inQuotes;
+        } else if (text[i] === separator && !inQuotes) {
+            parts.push(text.slice(start, i));
+            start = i + 1;
+        }
+    }
+    parts.push(text.slice(start));
+    return parts;
+}
+
+// Function to read a (possibly quoted) node label from the beginning of a string, returns the label and the rest of the string
+function readLabel(text) {
+    text = text.trimStart();
+    if (!text.startsWith('"')) {
+        const end = text.search(/[\s,;:\->"]/);
+        return end === -1 ? [text, ""] : [text.slice(0, end), text.slice(end)];
+    }
+    let label = "";
+    for (let i = 1; i < text.length; i++) {
+        if (text[i] === '\\') {
+            label += text[++i];
+        } else if (text[i] === '"') {
+            return [label, text.slice(i + 1)];
+        } else {
+            label += text[i];
+        }
+    }
+    throw new Error(`Unterminated node label: ${text}`);
+}
+
+// Function to report errors thrown by the layout engine, e.g. when the graph content is malformed
+function showError(error) {
+    console.error(error);
+    alert(`Could not lay out the graph: ${error.message}`);
 }
 
 // Function to parse the graph string into nodes and edges
@@ -70,19 +284,56 @@ function parseGraph(graphStr) {
         throw new Error("Invalid graph format.");
     }
 
-    const nodes = nodesPart.split(';').filter(Boolean).map((node, index) => {
-        const [x, y] = node.split(',').map(Number);
-        return { id: index, x, y };
+    // Nodes have the form "label:x,y", algorithms that do not know labels may write just "x,y" (the label is the index then)
+    const nodes = splitUnquoted(nodesPart, ';').filter(node => node.trim()).map((node, index) => {
+        const separator = node.lastIndexOf(':');
+        const label = separator === -1 ? String(index) : readLabel(node.slice(0, separator))[0];
+        const [x, y] = node.slice(separator + 1).split(',').map(Number);
+        return { id: index, label, x, y };
     });
-
-    const edges = edgesPart.split(',').filter(Boolean).map(edge => {
-        const [source, target] = edge.split('-').map(Number);
-        return { source, target };
+    const indices = new Map(nodes.map(node => [node.label, node.id]));
+
+    // Edges have the form "source-target" (undirected) or "source->target" (directed), optionally followed by ":weight"
+    const edges = splitUnquoted(edgesPart, ',').filter(edge => edge.trim()).map(edge => {
+        const [source, afterSource] = readLabel(edge);
+        const directed = afterSource.trimStart().startsWith('->');
+        const [target, afterTarget] = readLabel(afterSource.trimStart().slice(directed ? 2 : 1));
+        const weight = afterTarget.trimStart().startsWith(':') ? Number(afterTarget.trimStart().slice(1)) : 1;
+        return { source: indices.get(source), target: indices.get(target), directed, weight };
     });
 
     return { nodes, edges };
 }
 
+// Function to create the layout options of a drawing area covering the SVG container, to be freed by the caller
+function drawingArea(margin, keepAspectRatio, clamp) {
+    const svg = d3.select("svg");
+    return new LayoutOptions(+svg.attr("width"), +svg.attr("height"), margin, keepAspectRatio, clamp);
+}
+
+// Function to collect the ForceAtlas2 controls into the settings of the layout engine, which are set by name
+function forceAtlas2Settings({ gravity, scalingRatio, theta, linLog, dissuadeHubs, strongGravity, edgeWeightInfluence, preventOverlap, jitterTolerance }) {
+    const settings = new ForceAtlas2Settings();
+    settings.gravity = gravity;
+    settings.scaling_ratio = scalingRatio;
+    settings.theta = theta;
+    settings.lin_log = linLog;
+    settings.dissuade_hubs = dissuadeHubs;
+    settings.strong_gravity = strongGravity;
+    settings.edge_weight_influence = edgeWeightInfluence;
+    settings.prevent_overlap = preventOverlap;
+    settings.jitter_tolerance = jitterTolerance;
+    return settings;
+}
+
+// Function to keep the coordinates of nodes laid out in the SVG, they are needed to pin the node
+function keepLayoutCoordinates(nodes) {
+    nodes.forEach(node => {
+        node.layoutX = node.x;
+        node.layoutY = node.y;
+    });
+}
+
 // Function to scale the graph nodes to fit within the SVG container
 function scaleGraph(nodes) {
     const svg = d3.select("svg");
@@ -102,19 +353,49 @@ function scaleGraph(nodes) {
         .range([padding, height - padding]);
 
     nodes.forEach(node => {
+        // Layout coordinates are kept, they are needed to pin the node
+        node.layoutX = node.x;
+        node.layoutY = node.y;
         node.x = xScale(node.x);
         node.y = yScale(node.y);
     });
 }
 
+// Function to compute where an edge line ends, directed edges stop at the border of the target node so the arrowhead stays visible
+function edgeEnd(nodes, edge) {
+    const source = nodes[edge.source];
+    const target = nodes[edge.target];
+    const length = Math.hypot(target.x - source.x, target.y - source.y);
+    if (!edge.directed || length === 0) {
+        return { x: target.x, y: target.y };
+    }
+    return {
+        x: target.x - (target.x - source.x) / length * nodeRadius,
+        y: target.y - (target.y - source.y) / length * nodeRadius,
+    };
+}
+
 // Function to update the SVG with the graph nodes and edges
 function updateGraph(nodes, edges) {
+    drawnNodes = nodes;
     const svg = d3.select("svg");
     const width = +svg.attr("width");
     const height = +svg.attr("height");
 
     svg.selectAll("*").remove();
 
+    // Arrowhead drawn at the end of directed edges
+    svg.append("defs").append("marker")
+        .attr("id", "arrowhead")
+        .attr("viewBox", "0 -5 10 10")
+        .attr("refX", 10)
+        .attr("markerWidth", 6)
+        .attr("markerHeight", 6)
+        .attr("orient", "auto")
+        .append("path")
+        .attr("d", "M0,-5L10,0L0,5")
+        .attr("fill", "#999");
+
     // Draw grid lines
     const gridSize = 50;
     svg.append("g")
@@ -146,8 +427,9 @@ function updateGraph(nodes, edges) {
         .attr("stroke-width", 1)
         .attr("x1", d => nodes[d.source].x)
         .attr("y1", d => nodes[d.source].y)
-        .attr("x2", d => nodes[d.target].x)
-        .attr("y2", d => nodes[d.target].y);
+        .attr("x2", d => edgeEnd(nodes, d).x)
+        .attr("y2", d => edgeEnd(nodes, d).y)
+        .attr("marker-end", d => d.directed ? "url(#arrowhead)" : null);
 
     // Draw nodes
     const node = svg.append("g")
@@ -156,21 +438,31 @@ function updateGraph(nodes, edges) {
         .selectAll("circle")
         .data(nodes)
         .enter().append("circle")
-        .attr("r", 5)
+        .attr("r", nodeRadius)
         .attr("fill", "red")
         .attr("cx", d => d.x)
         .attr("cy", d => d.y)
-        .attr("class", "node");
+        .attr("class", "node")
+        .classed("fixed", d => pinnedNodes.has(d.label));
 
     // Add title to each node
     node.append("title")
-        .text(d => d.id);
+        .text(d => d.label);
 
-    // Handle node click event for fixing/unfixing nodes
+    // Handle node click event for fixing/unfixing nodes, fixed nodes keep their position in the next layouts
     node.on("click", function(event, d) {
-        d.fixed = !d.fixed;
+        d.fixed = !pinnedNodes.has(d.label);
         d3.select(this).classed("fixed", d.fixed);
-        if (!d.fixed) {
+        if (d.fixed) {
+            pinnedNodes.set(d.label, { index: d.id, x: d.layoutX, y: d.layoutY });
+            if (animation) {
+                animation.session.pin(d.id, d.layoutX, d.layoutY);
+            }
+        } else {
+            pinnedNodes.delete(d.label);
+            if (animation) {
+                animation.session.unpin(d.id);
+            }
             d.fx = null;
             d.fy = null;
         }
@@ -226,22 +518,181 @@ fetch(`http://localhost:8080/api/graph/${graph_id}`)
         const iterations = 1000;
         const gravity = 0.1;
         const scalingRatio = 10.0;
+        const theta = 0.5;
+        const linLog = false;
+        const dissuadeHubs = false;
+        const strongGravity = false;
+        const edgeWeightInfluence = 1.0;
+        const preventOverlap = false;
+        const jitterTolerance = 1.0;
+        const springConstant = 1.0;
+        const pivots = 0;
+        const tolerance = 1e-4;
+        const cooling = "adaptive";
+        const direction = "top_down";
+        const nodeSpacing = 10.0;
+        const layerSpacing = 20.0;
+        const root = "";
+        const normalized = false;
+        const levels = 10;
+        const refinementIterations = 50;
+        const ordering = "input";
+        const rings = 1;
+        const radius = 5;
+        const removeOverlaps = false;
+        const margin = 20;
+        const keepAspectRatio = true;
+        const clamp = false;
+        const warmStart = false;
+        const seed = 1;
         updateControlVisibility(algorithm);
-        run(data.content, algorithm, iterations, gravity, scalingRatio);
-    });
+        return run(data.content, algorithm, iterations, gravity, scalingRatio, theta, linLog, dissuadeHubs, strongGravity, edgeWeightInfluence, preventOverlap, jitterTolerance, springConstant, pivots, tolerance, cooling, direction, nodeSpacing, layerSpacing, root, normalized, levels, refinementIterations, ordering, rings, radius, removeOverlaps, margin, keepAspectRatio, clamp, warmStart, seed);
+    })
+    .catch(showError);
+
+// Function to read the layout parameters from the control inputs
+function readControls() {
+    // Theta of 0 is a valid value (exact repulsion), so it cannot fall back with ||
+    const thetaValue = parseFloat(document.getElementById("theta-input").value);
+    return {
+        algorithm: document.getElementById("algorithm-select").value,
+        iterations: parseInt(document.getElementById("iterations-input").value) || 1000,
+        gravity: parseFloat(document.getElementById("gravity-input").value) || 0.1,
+        scalingRatio: parseFloat(document.getElementById("scaling-ratio-input").value) || 10.0,
+        theta: isNaN(thetaValue) ? 0.5 : thetaValue,
+        // Options of ForceAtlas2 as in Gephi, an edge weight influence of 0 ignores the weights
+        linLog: document.getElementById("lin-log-input").checked,
+        dissuadeHubs: document.getElementById("dissuade-hubs-input").checked,
+        strongGravity: document.getElementById("strong-gravity-input").checked,
+        edgeWeightInfluence: Math.max(parseFloat(document.getElementById("edge-weight-influence-input").value) || 0, 0),
+        preventOverlap: document.getElementById("prevent-overlap-input").checked,
+        jitterTolerance: parseFloat(document.getElementById("jitter-tolerance-input").value) || 1.0,
+        springConstant: parseFloat(document.getElementById("spring-constant-input").value) || 1.0,
+        // Number of pivots of the distance based layouts, 0 uses the distances between all pairs of nodes
+        pivots: parseInt(document.getElementById("pivots-input").value) || 0,
+        // Stress majorization stops once the stress decreases by less than this fraction and the force-directed layouts
+        // once their energy falls below this fraction of its first value, 0 always runs all iterations
+        tolerance: Math.max(parseFloat(document.getElementById("tolerance-input").value) || 0, 0),
+        // How the temperature of Fruchterman-Reingold and the multilevel layout decreases over the iterations
+        cooling: document.getElementById("cooling-select").value,
+        // Direction of the edges of the layered layout
+        direction: document.getElementById("direction-select").value,
+        // Distances within and between the layers of the layered and the tree layouts
+        nodeSpacing: parseFloat(document.getElementById("node-spacing-input").value) || 10.0,
+        layerSpacing: parseFloat(document.getElementById("layer-spacing-input").value) || 20.0,
+        // Label of the root of the tree layouts, empty lets the layout pick the center of the tree
+        root: document.getElementById("root-input").value.trim(),
+        // Whether the spectral layout uses the normalized Laplacian
+        normalized: document.getElementById("normalized-input").checked,
+        // Maximum number of coarsening levels of the multilevel layout and iterations spent on each finer level
+        levels: parseInt(document.getElementById("levels-input").value) || 0,
+        refinementIterations: parseInt(document.getElementById("refinement-iterations-input").value) || 0,
+        // Order of the nodes around the circle and maximum number of concentric rings (keyed by degree) of the circular layout
+        ordering: document.getElementById("ordering-select").value,
+        rings: parseInt(document.getElementById("rings-input").value) || 1,
+        // Radius of the drawn nodes in pixels and whether they are moved apart until they do not overlap
+        radius: parseFloat(document.getElementById("radius-input").value) || 5,
+        removeOverlaps: document.getElementById("remove-overlaps-input").checked,
+        // Free space in pixels around the layout, whether it keeps its aspect ratio and whether force-directed layouts stay inside the SVG
+        margin: Math.max(parseFloat(document.getElementById("margin-input").value) || 0, 0),
+        keepAspectRatio: document.getElementById("keep-aspect-ratio-input").checked,
+        clamp: document.getElementById("clamp-input").checked,
+        // Whether the iterative layouts continue from the current drawing instead of random positions
+        warmStart: document.getElementById("warm-start-input").checked,
+        // Seed of the random number generator, the same seed always gives the same layout
+        seed: parseInt(document.getElementById("seed-input").value) || 0,
+    };
+}
+
+// Function to pass the current control values to a running layout session
+function applyControls(session, controls) {
+    session.set_gravity(controls.gravity);
+    session.set_scaling_ratio(controls.scalingRatio);
+    session.set_theta(controls.theta);
+    session.set_spring_constant(controls.springConstant);
+    session.set_lin_log(controls.linLog);
+    session.set_dissuade_hubs(controls.dissuadeHubs);
+    session.set_strong_gravity(controls.strongGravity);
+    session.set_edge_weight_influence(controls.edgeWeightInfluence);
+    session.set_prevent_overlap(controls.preventOverlap);
+    session.set_jitter_tolerance(controls.jitterTolerance);
+}
+
+// Function to stop the running animation and release its layout session
+function stopAnimation() {
+    if (animation) {
+        cancelAnimationFrame(animation.frame);
+        animation.session.free();
+        animation = null;
+    }
+}
+
+// Function to animate a force-directed layout, redrawing the graph every few iterations
+async function animate(graphStr, controls) {
+    await init();
+    stopAnimation();
+    nodeRadius = controls.radius;
+
+    const options = drawingArea(controls.margin, controls.keepAspectRatio, controls.clamp);
+    const initial = controls.warmStart ? initialString() : "";
+    const session = new LayoutSession(graphStr, initial, controls.algorithm.replace("process_", ""), options, controls.seed);
+    options.free();
+    applyControls(session, controls);
+    session.set_cooling(controls.cooling, controls.iterations, controls.tolerance);
+    for (let index = 0; index < session.positions().length / 2; index++) {
+        session.set_size(index, 2 * nodeRadius, 2 * nodeRadius);
+    }
+    pinnedNodes.forEach(position => session.pin(position.index, position.x, position.y));
+
+    const frame = () => {
+        session.step(Math.min(ANIMATION_STEP, controls.iterations - session.iteration()));
+        const { nodes, edges } = parseGraph(session.to_graph_string());
+        scaleGraph(nodes);
+        updateGraph(nodes, edges);
+
+        // A converged layout no longer moves, so the animation ends early
+        if (session.iteration() < controls.iterations && !session.converged()) {
+            animation.frame = requestAnimationFrame(frame);
+        } else {
+            stopAnimation();
+        }
+    };
+    animation = { session, frame: requestAnimationFrame(frame) };
+}
 
 // Event listener for the refresh button to re-run the graph processing with updated parameters
 document.getElementById("refresh-button").addEventListener("click", () => {
-    const algorithm = document.getElementById("algorithm-select").value;
-    const iterations = parseInt(document.getElementById("iterations-input").value) || 1000;
-    const gravity = parseFloat(document.getElementById("gravity-input").value) || 0.1;
-    const scalingRatio = parseFloat(document.getElementById("scaling-ratio-input").value) || 10.0;
+    const { algorithm, iterations, gravity, scalingRatio, theta, linLog, dissuadeHubs, strongGravity, edgeWeightInfluence, preventOverlap, jitterTolerance, springConstant, pivots, tolerance, cooling, direction, nodeSpacing, layerSpacing, root, normalized, levels, refinementIterations, ordering, rings, radius, removeOverlaps, margin, keepAspectRatio, clamp, warmStart, seed } = readControls();
+    stopAnimation();
     const graph_id = new URLSearchParams(window.location.search).get('id');
     fetch(`http://localhost:8080/api/graph/${graph_id}`)
         .then(response => response.json())
         .then(data => {
-            run(data.content, algorithm, iterations, gravity, scalingRatio);
-        });
+            return run(data.content, algorithm, iterations, gravity, scalingRatio, theta, linLog, dissuadeHubs, strongGravity, edgeWeightInfluence, preventOverlap, jitterTolerance, springConstant, pivots, tolerance, cooling, direction, nodeSpacing, layerSpacing, root, normalized, levels, refinementIterations, ordering, rings, radius, removeOverlaps, margin, keepAspectRatio, clamp, warmStart, seed);
+        })
+        .catch(showError);
+});
+
+// Event listener for the animate button to watch a force-directed layout converge
+document.getElementById("animate-button").addEventListener("click", () => {
+    const controls = readControls();
+    const graph_id = new URLSearchParams(window.location.search).get('id');
+    fetch(`http://localhost:8080/api/graph/${graph_id}`)
+        .then(response => response.json())
+        .then(data => animate(data.content, controls))
+        .catch(showError);
+});
+
+// Event listener for the stop button to interrupt a running animation
+document.getElementById("stop-button").addEventListener("click", stopAnimation);
+
+// Parameters changed during an animation are applied to the running layout immediately
+["gravity-input", "scaling-ratio-input", "theta-input", "spring-constant-input", "lin-log-input", "dissuade-hubs-input", "strong-gravity-input", "edge-weight-influence-input", "prevent-overlap-input", "jitter-tolerance-input"].forEach(id => {
+    document.getElementById(id).addEventListener("input", () => {
+        if (animation) {
+            applyControls(animation.session, readControls());
+        }
+    });
 });
 
 // Initialize control visibility based on the default selected algorithm
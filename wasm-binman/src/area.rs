@@ -0,0 +1,249 @@
+use crate::{Graph, Node, Point};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE DRAWING AREA THAT LAYOUTS ARE FITTED INTO
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Every layout places the nodes in a drawing area of `width` x `height`, by default the 0..100 square.
+The area less `margin` on every side is the inner area: random starting positions fill it, the circular layout and
+the packed components are centered in it, Fruchterman-Reingold takes its size as the area of the drawing and
+Kamada-Kawai sizes its edges so that the graph spans it.
+Afterwards the laid out graph is scaled and centered so that it fills the inner area, keeping its aspect ratio
+unless `keep_aspect_ratio` is false, in which case both axes are stretched. Graphs with pinned nodes are in the
+coordinates of the area already, since that is what the pins are given in: only the free nodes are fitted, their
+bounding box is shrunk as little as needed to fit into the inner area and moved into it as little as possible,
+so free nodes that already lie inside stay where they are.
+The layered and tree layouts, whose node and layer spacings are distances in the area, are not scaled either:
+they are only moved so that the drawing starts at the top left corner of the inner area, and may extend past it.
+With pins they are moved so that the pinned nodes come to their pins (on average, when there are several).
+With `clamp` the force-directed layouts keep the nodes inside the inner area while they run and the nodes that end up
+outside of it are moved to its border. Without it the nodes move freely and the final scaling brings them
+into the area, so large graphs are not squashed against the border.
+*/
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct LayoutOptions {
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+    pub(crate) margin: f64,
+    pub(crate) keep_aspect_ratio: bool,
+    pub(crate) clamp: bool,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> LayoutOptions {
+        LayoutOptions { width: 100.0, height: 100.0, margin: 0.0, keep_aspect_ratio: true, clamp: false }
+    }
+}
+
+#[wasm_bindgen]
+impl LayoutOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: f64, height: f64, margin: f64, keep_aspect_ratio: bool, clamp: bool) -> Result<LayoutOptions, JsValue> {
+        let options = LayoutOptions { width, height, margin, keep_aspect_ratio, clamp };
+        if !options.is_valid() {
+            return Err(js_sys::Error::new("width and height must be positive and the margin non-negative").into());
+        }
+        Ok(options)
+    }
+}
+
+impl LayoutOptions {
+    pub(crate) fn is_valid(&self) -> bool {
+        self.width > 0.0 && self.height > 0.0 && self.width.is_finite() && self.height.is_finite() && self.margin >= 0.0
+    }
+
+    // Minimum and maximum corner of the inner area, a margin of more than half the area leaves only its center
+    pub(crate) fn inner(&self) -> (Point, Point) {
+        let (left, top) = (self.margin.min(self.width / 2.0), self.margin.min(self.height / 2.0));
+        (Point { x: left, y: top }, Point { x: self.width - left, y: self.height - top })
+    }
+
+    pub(crate) fn center(&self) -> Point {
+        Point { x: self.width / 2.0, y: self.height / 2.0 }
+    }
+
+    // Nearest point of the inner area
+    pub(crate) fn clamp_point(&self, point: Point) -> Point {
+        let (min, max) = self.inner();
+        Point { x: point.x.clamp(min.x, max.x), y: point.y.clamp(min.y, max.y) }
+    }
+}
+
+// Smallest and largest coordinates of the given nodes
+fn bounds<'a>(nodes: impl Iterator<Item = &'a Node>) -> (Point, Point) {
+    let mut low = Point { x: f64::INFINITY, y: f64::INFINITY };
+    let mut high = Point { x: f64::NEG_INFINITY, y: f64::NEG_INFINITY };
+    for node in nodes {
+        low = Point { x: low.x.min(node.position.x), y: low.y.min(node.position.y) };
+        high = Point { x: high.x.max(node.position.x), y: high.y.max(node.position.y) };
+    }
+    (low, high)
+}
+
+fn has_pins(graph: &Graph) -> bool {
+    graph.nodes.iter().any(Node::is_pinned)
+}
+
+// Shrink and move the free nodes of a graph with pins into the inner area, see the comment at the top of the file
+fn fit_free_nodes(graph: &mut Graph, options: &LayoutOptions) {
+    if graph.nodes.iter().all(Node::is_pinned) {
+        return;
+    }
+    let (low, high) = bounds(graph.nodes.iter().filter(|node| !node.is_pinned()));
+    let (min, max) = options.inner();
+    let shrink = |extent: f64, available: f64| if extent > available { available / extent } else { 1.0 };
+    let mut scale_x = shrink(high.x - low.x, max.x - min.x);
+    let mut scale_y = shrink(high.y - low.y, max.y - min.y);
+    if options.keep_aspect_ratio {
+        scale_x = scale_x.min(scale_y);
+        scale_y = scale_x;
+    }
+
+    // Top left corner of the shrunk bounding box, moved inside the inner area
+    let corner = Point {
+        x: low.x.max(min.x).min(max.x - (high.x - low.x) * scale_x),
+        y: low.y.max(min.y).min(max.y - (high.y - low.y) * scale_y),
+    };
+    for node in graph.nodes.iter_mut().filter(|node| !node.is_pinned()) {
+        node.position.x = corner.x + (node.position.x - low.x) * scale_x;
+        node.position.y = corner.y + (node.position.y - low.y) * scale_y;
+    }
+}
+
+fn clamp_to_area(graph: &mut Graph, options: &LayoutOptions) {
+    if options.clamp {
+        for node in graph.nodes.iter_mut().filter(|node| !node.is_pinned()) {
+            node.position = options.clamp_point(node.position);
+        }
+    }
+}
+
+// Scale and center the laid out graph into the inner area and clamp it, see the comment at the top of the file
+pub(crate) fn fit_to_area(graph: &mut Graph, options: &LayoutOptions) {
+    if has_pins(graph) {
+        fit_free_nodes(graph, options);
+    } else if !graph.nodes.is_empty() {
+        let (low, high) = bounds(graph.nodes.iter());
+        let (min, max) = options.inner();
+        // An axis along which all nodes share a coordinate is not scaled but centered
+        let scale = |extent: f64, available: f64| if extent > 0.0 { available / extent } else { f64::INFINITY };
+        let mut scale_x = scale(high.x - low.x, max.x - min.x);
+        let mut scale_y = scale(high.y - low.y, max.y - min.y);
+        if options.keep_aspect_ratio {
+            scale_x = scale_x.min(scale_y);
+            scale_y = scale_x;
+        }
+        let finite = |scale: f64| if scale.is_finite() { scale } else { 0.0 };
+        let (scale_x, scale_y) = (finite(scale_x), finite(scale_y));
+
+        let (middle, center) = (Point { x: (low.x + high.x) / 2.0, y: (low.y + high.y) / 2.0 }, options.center());
+        for node in &mut graph.nodes {
+            node.position.x = center.x + (node.position.x - middle.x) * scale_x;
+            node.position.y = center.y + (node.position.y - middle.y) * scale_y;
+        }
+    }
+    clamp_to_area(graph, options);
+}
+
+// Move the laid out graph to the top left corner of the inner area without scaling it and clamp it, used by the layouts with spacings
+pub(crate) fn translate_to_area(graph: &mut Graph, options: &LayoutOptions) {
+    if has_pins(graph) {
+        // Mean offset of the pinned nodes from their pins, the pinned nodes are then put exactly on them
+        let pinned: Vec<(Point, Point)> = graph.nodes.iter().filter_map(|node| Some((node.position, node.pinned?))).collect();
+        let count = pinned.len() as f64;
+        let offset = pinned.iter().fold(Point { x: 0.0, y: 0.0 }, |sum, (position, pin)| Point {
+            x: sum.x + (pin.x - position.x) / count,
+            y: sum.y + (pin.y - position.y) / count,
+        });
+        for node in &mut graph.nodes {
+            let position = Point { x: node.position.x + offset.x, y: node.position.y + offset.y };
+            node.position = node.pinned.unwrap_or(position);
+        }
+    } else if !graph.nodes.is_empty() {
+        let (low, _) = bounds(graph.nodes.iter());
+        let (min, _) = options.inner();
+        for node in &mut graph.nodes {
+            node.position.x += min.x - low.x;
+            node.position.y += min.y - low.y;
+        }
+    }
+    clamp_to_area(graph, options);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph_at;
+
+    fn positions(graph: &Graph) -> Vec<(f64, f64)> {
+        graph.nodes.iter().map(|node| (node.position.x, node.position.y)).collect()
+    }
+
+    #[test]
+    fn fit_to_area_check_if_layout_fills_inner_area() {
+        let options = LayoutOptions { width: 400.0, height: 200.0, margin: 10.0, ..LayoutOptions::default() };
+        let mut kept = graph_at(&[(-1.0, -1.0), (1.0, 1.0), (0.0, 0.0)], &[]);
+        let mut stretched = graph_at(&[(-1.0, -1.0), (1.0, 1.0), (0.0, 0.0)], &[]);
+        let mut single = graph_at(&[(7.0, 3.0)], &[]);
+
+        fit_to_area(&mut kept, &options);
+        fit_to_area(&mut stretched, &LayoutOptions { keep_aspect_ratio: false, ..options });
+        fit_to_area(&mut single, &options);
+
+        assert_eq!(positions(&kept), vec![(110.0, 10.0), (290.0, 190.0), (200.0, 100.0)]);
+        assert_eq!(positions(&stretched), vec![(10.0, 10.0), (390.0, 190.0), (200.0, 100.0)]);
+        assert_eq!(positions(&single), vec![(200.0, 100.0)]);
+    }
+
+    #[test]
+    fn translate_to_area_check_if_spacing_is_kept() {
+        let options = LayoutOptions { width: 400.0, height: 200.0, margin: 10.0, ..LayoutOptions::default() };
+        let mut moved = graph_at(&[(-30.0, -5.0), (30.0, 15.0), (0.0, 500.0)], &[]);
+        let mut clamped = graph_at(&[(-30.0, -5.0), (30.0, 15.0), (0.0, 500.0)], &[]);
+
+        translate_to_area(&mut moved, &options);
+        translate_to_area(&mut clamped, &LayoutOptions { clamp: true, ..options });
+
+        assert_eq!(positions(&moved), vec![(10.0, 10.0), (70.0, 30.0), (40.0, 515.0)]);
+        assert_eq!(positions(&clamped), vec![(10.0, 10.0), (70.0, 30.0), (40.0, 190.0)]);
+    }
+
+    #[test]
+    fn fit_to_area_check_if_only_free_nodes_are_fitted_with_pins() {
+        let options = LayoutOptions { margin: 5.0, ..LayoutOptions::default() };
+        let mut outside = graph_at(&[(-20.0, 50.0), (120.0, 30.0), (200.0, 200.0)], &[]);
+        let mut inside = graph_at(&[(20.0, 50.0), (80.0, 30.0), (200.0, 200.0)], &[]);
+        let mut clamped = graph_at(&[(-20.0, 50.0), (120.0, 30.0), (200.0, 200.0)], &[]);
+        for graph in [&mut outside, &mut inside, &mut clamped] {
+            graph.nodes[2].pinned = Some(Point { x: 200.0, y: 200.0 });
+        }
+
+        fit_to_area(&mut outside, &options);
+        fit_to_area(&mut inside, &options);
+        fit_to_area(&mut clamped, &LayoutOptions { clamp: true, keep_aspect_ratio: false, ..options });
+
+        // 140 units wide shrink to the 90 of the inner area, the aspect ratio is kept
+        let shrunk = positions(&outside);
+        assert_eq!((shrunk[0].0, shrunk[1].0, shrunk[1].1), (5.0, 95.0, 30.0));
+        assert!((shrunk[0].1 - (30.0 + 20.0 * 90.0 / 140.0)).abs() < 1e-9);
+        assert_eq!(positions(&inside), vec![(20.0, 50.0), (80.0, 30.0), (200.0, 200.0)]);
+        assert_eq!(positions(&clamped), vec![(5.0, 50.0), (95.0, 30.0), (200.0, 200.0)]);
+        assert!(!LayoutOptions { width: 0.0, ..options }.is_valid() && !LayoutOptions { margin: -1.0, ..options }.is_valid());
+    }
+
+    #[test]
+    fn translate_to_area_check_if_pinned_nodes_come_to_their_pins() {
+        let mut tree = graph_at(&[(0.0, 0.0), (-10.0, 20.0), (10.0, 20.0)], &[]);
+        tree.nodes[1].pinned = Some(Point { x: 40.0, y: 60.0 });
+
+        translate_to_area(&mut tree, &LayoutOptions::default());
+
+        assert_eq!(positions(&tree), vec![(50.0, 40.0), (40.0, 60.0), (60.0, 60.0)]);
+    }
+}
@@ -0,0 +1,391 @@
+use crate::components::connected_components;
+use crate::Graph;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE NODE ORDER AND THE RINGS OF THE CIRCULAR LAYOUT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+A circular drawing is only as readable as the order of the nodes around the circle, in input order
+the edges usually become a hairball of chords. The orderings:
+- input: node i at position i
+- component: connected components one after another, each in breadth-first order
+- community: communities found by label propagation one after another, each in breadth-first order
+- crossing_reduction: greedy order by connectivity followed by circular sifting (Baur and Brandes),
+  every node in turn is moved to the place around the circle where its edges cross the fewest other edges
+Concentric rings are keyed by degree: the nodes of highest degree go on the inner ring and every ring lists its nodes
+in the chosen order. Edge directions, weights, self loops and parallel edges are ignored.
+*/
+
+// Label propagation stops after this many rounds even if some labels still change
+const LABEL_PROPAGATION_ROUNDS: usize = 20;
+
+// Circular sifting stops after this many rounds even if the last round still removed crossings
+const SIFTING_ROUNDS: usize = 3;
+
+// Order of the nodes around the circle
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CircularOrdering {
+    Input,
+    Component,
+    Community,
+    CrossingReduction,
+}
+
+impl CircularOrdering {
+    pub(crate) fn from_name(name: &str) -> Option<CircularOrdering> {
+        match name {
+            "input" => Some(CircularOrdering::Input),
+            "component" => Some(CircularOrdering::Component),
+            "community" => Some(CircularOrdering::Community),
+            "crossing_reduction" => Some(CircularOrdering::CrossingReduction),
+            _ => None,
+        }
+    }
+}
+
+// Distinct neighbors of every node in increasing order, self loops are dropped
+fn neighbor_lists(graph: &Graph) -> Vec<Vec<usize>> {
+    let mut neighbors = vec![Vec::new(); graph.nodes.len()];
+    for edge in graph.edges.iter().filter(|edge| edge.source != edge.target) {
+        neighbors[edge.source].push(edge.target);
+        neighbors[edge.target].push(edge.source);
+    }
+    for list in &mut neighbors {
+        list.sort_unstable();
+        list.dedup();
+    }
+    neighbors
+}
+
+/*
+Nodes of every group one group after another, groups are numbered from 0 and come in the order of their numbers.
+Within a group the nodes are listed in breadth-first order, so most edges inside the group join nearby nodes.
+*/
+fn grouped_order(neighbors: &[Vec<usize>], group: &[usize]) -> Vec<usize> {
+    let num_groups = group.iter().max().map_or(0, |max| max + 1);
+    let mut orders = vec![Vec::new(); num_groups];
+    let mut visited = vec![false; neighbors.len()];
+    for start in 0..neighbors.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let order = &mut orders[group[start]];
+        let mut next = order.len();
+        order.push(start);
+        while next < order.len() {
+            let node = order[next];
+            next += 1;
+            for &neighbor in &neighbors[node] {
+                if !visited[neighbor] && group[neighbor] == group[node] {
+                    visited[neighbor] = true;
+                    order.push(neighbor);
+                }
+            }
+        }
+    }
+    orders.concat()
+}
+
+/*
+Communities by label propagation: every node starts in its own community and, in index order, repeatedly joins
+the community most of its neighbors belong to (on ties it stays, or joins the community with the smallest label).
+Communities are numbered in the order of their smallest node.
+*/
+fn communities(neighbors: &[Vec<usize>]) -> Vec<usize> {
+    let num_nodes = neighbors.len();
+    let mut label: Vec<usize> = (0..num_nodes).collect();
+    let mut counts = vec![0; num_nodes];
+    for _ in 0..LABEL_PROPAGATION_ROUNDS {
+        let mut changed = false;
+        for node in 0..num_nodes {
+            for &neighbor in &neighbors[node] {
+                counts[label[neighbor]] += 1;
+            }
+            let most = neighbors[node].iter().map(|&neighbor| counts[label[neighbor]]).max().unwrap_or(0);
+            let best = if counts[label[node]] == most {
+                label[node]
+            } else {
+                neighbors[node].iter().map(|&neighbor| label[neighbor]).filter(|&candidate| counts[candidate] == most).min().unwrap_or(label[node])
+            };
+            for &neighbor in &neighbors[node] {
+                counts[label[neighbor]] = 0;
+            }
+            if best != label[node] {
+                label[node] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut number = vec![usize::MAX; num_nodes];
+    let mut next = 0;
+    label
+        .iter()
+        .map(|&community| {
+            if number[community] == usize::MAX {
+                number[community] = next;
+                next += 1;
+            }
+            number[community]
+        })
+        .collect()
+}
+
+/*
+Greedy order by connectivity: the next node is the one with the most neighbors already placed,
+ties go to the node with the fewest neighbors still to place (its edges are closed soonest).
+It goes to the front or the back of the order, whichever makes its edges cross fewer of the open edges,
+i.e. the edges from placed nodes to nodes still to place, which all end in the gap between back and front.
+*/
+fn connectivity_order(neighbors: &[Vec<usize>]) -> Vec<usize> {
+    let num_nodes = neighbors.len();
+    let mut placed = vec![false; num_nodes];
+    let mut placed_neighbors = vec![0; num_nodes];
+    let mut order: VecDeque<usize> = VecDeque::with_capacity(num_nodes);
+    for _ in 0..num_nodes {
+        let next = (0..num_nodes)
+            .filter(|&node| !placed[node])
+            .max_by_key(|&node| (placed_neighbors[node], Reverse(neighbors[node].len() - placed_neighbors[node]), Reverse(node)))
+            .expect("a node is left to place");
+
+        let is_neighbor = |node: usize| neighbors[next].binary_search(&node).is_ok();
+        let open: Vec<usize> = order
+            .iter()
+            .map(|&node| neighbors[node].len() - placed_neighbors[node] - usize::from(is_neighbor(node)))
+            .collect();
+        let total: usize = open.iter().sum();
+        let (mut before, mut front_crossings, mut back_crossings) = (0, 0, 0);
+        for (&node, &node_open) in order.iter().zip(&open) {
+            if is_neighbor(node) {
+                front_crossings += before;
+                back_crossings += total - before - node_open;
+            }
+            before += node_open;
+        }
+        if front_crossings < back_crossings {
+            order.push_front(next);
+        } else {
+            order.push_back(next);
+        }
+
+        placed[next] = true;
+        for &neighbor in &neighbors[next] {
+            placed_neighbors[neighbor] += 1;
+        }
+    }
+    order.into()
+}
+
+// Whether the chords a-b and c-d cross, `position` is the place of every node around the circle and the four nodes differ
+fn chords_cross(position: &[usize], a: usize, b: usize, c: usize, d: usize) -> bool {
+    let (low, high) = (position[a].min(position[b]), position[a].max(position[b]));
+    let inside = |node: usize| low < position[node] && position[node] < high;
+    inside(c) != inside(d)
+}
+
+/*
+Change in the number of crossings when the nodes u and v, next to each other on the circle, trade places.
+Only pairs of edges u-x and v-y with four different nodes are affected, and each of them flips between crossing and not.
+*/
+fn swap_change(neighbors: &[Vec<usize>], position: &[usize], u: usize, v: usize) -> i64 {
+    let mut change = 0;
+    for &x in neighbors[u].iter().filter(|&&x| x != v) {
+        for &y in neighbors[v].iter().filter(|&&y| y != u && y != x) {
+            change += if chords_cross(position, u, x, v, y) { -1 } else { 1 };
+        }
+    }
+    change
+}
+
+/*
+One round of circular sifting: every node is moved once around the circle by swapping it with its successor
+and put back at the place with the fewest crossings. Returns whether any crossing was removed.
+*/
+fn sift(neighbors: &[Vec<usize>], order: &mut [usize]) -> bool {
+    let num_nodes = order.len();
+    let mut position = vec![0; num_nodes];
+    for (place, &node) in order.iter().enumerate() {
+        position[node] = place;
+    }
+    let swap_with_successor = |order: &mut [usize], position: &mut [usize], node: usize| {
+        let (place, next) = (position[node], (position[node] + 1) % num_nodes);
+        let successor = order[next];
+        order.swap(place, next);
+        position[node] = next;
+        position[successor] = place;
+    };
+
+    let mut improved = false;
+    for node in 0..num_nodes {
+        let (mut change, mut best_change, mut best_steps) = (0, 0, 0);
+        for step in 1..num_nodes {
+            change += swap_change(neighbors, &position, node, order[(position[node] + 1) % num_nodes]);
+            swap_with_successor(order, &mut position, node);
+            if change < best_change {
+                best_change = change;
+                best_steps = step;
+            }
+        }
+        // After passing all other nodes the circle is back in its original cyclic order
+        for _ in 0..best_steps {
+            swap_with_successor(order, &mut position, node);
+        }
+        improved |= best_change < 0;
+    }
+    improved
+}
+
+// Order of all nodes around the circle
+pub(crate) fn circular_order(graph: &Graph, ordering: CircularOrdering) -> Vec<usize> {
+    let neighbors = neighbor_lists(graph);
+    match ordering {
+        CircularOrdering::Input => (0..graph.nodes.len()).collect(),
+        CircularOrdering::Component => {
+            let mut group = vec![0; graph.nodes.len()];
+            for (index, component) in connected_components(graph).iter().enumerate() {
+                for &node in component {
+                    group[node] = index;
+                }
+            }
+            grouped_order(&neighbors, &group)
+        }
+        CircularOrdering::Community => grouped_order(&neighbors, &communities(&neighbors)),
+        CircularOrdering::CrossingReduction => {
+            let mut order = connectivity_order(&neighbors);
+            if order.len() >= 4 {
+                for _ in 0..SIFTING_ROUNDS {
+                    if !sift(&neighbors, &mut order) {
+                        break;
+                    }
+                }
+            }
+            order
+        }
+    }
+}
+
+/*
+Ring of every node for at most `rings` concentric rings, 0 is the innermost. Rings hold more nodes the further out
+they are (ring r about r + 1 shares), so the nodes are about as far apart on every ring. The nodes of highest degree
+go inside, nodes of the same degree always share a ring, so fewer rings than asked for may be used.
+*/
+pub(crate) fn degree_rings(graph: &Graph, rings: usize) -> Vec<usize> {
+    let neighbors = neighbor_lists(graph);
+    let num_nodes = graph.nodes.len();
+    let rings = rings.clamp(1, num_nodes.max(1));
+    let mut by_degree: Vec<usize> = (0..num_nodes).collect();
+    by_degree.sort_by_key(|&node| (Reverse(neighbors[node].len()), node));
+
+    let shares = rings * (rings + 1) / 2;
+    let mut ring = vec![0; num_nodes];
+    let (mut start, mut used) = (0, 0);
+    for r in 0..rings {
+        let mut end = (num_nodes * (r + 1) * (r + 2) / 2 / shares).max(start);
+        // A boundary inside a group of equal degree moves to the start of the group, or to its end if that empties the ring
+        let same_degree = |end: usize| neighbors[by_degree[end]].len() == neighbors[by_degree[end - 1]].len();
+        let mut back = end;
+        while back > start && back < num_nodes && same_degree(back) {
+            back -= 1;
+        }
+        if back > start {
+            end = back;
+        } else {
+            while end > start && end < num_nodes && same_degree(end) {
+                end += 1;
+            }
+        }
+        if end > start {
+            for &node in &by_degree[start..end] {
+                ring[node] = used;
+            }
+            used += 1;
+            start = end;
+        }
+    }
+    ring
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+
+    fn crossings(graph: &Graph, order: &[usize]) -> usize {
+        let mut position = vec![0; order.len()];
+        for (place, &node) in order.iter().enumerate() {
+            position[node] = place;
+        }
+        let edges = &graph.edges;
+        let mut count = 0;
+        for (k, e) in edges.iter().enumerate() {
+            for f in &edges[k + 1..] {
+                let distinct = e.source != f.source && e.source != f.target && e.target != f.source && e.target != f.target;
+                if distinct && chords_cross(&position, e.source, e.target, f.source, f.target) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn circular_order_check_if_crossing_reduction_untangles_a_shuffled_cycle() {
+        // A cycle of 12 nodes numbered so that the input order draws a star polygon full of crossings
+        let cycle: Vec<(usize, usize)> = (0..12).map(|i| ((i * 5) % 12, ((i + 1) * 5) % 12)).collect();
+        let mut ladder: Vec<(usize, usize)> = (0..7).flat_map(|i| vec![(2 * i, 2 * i + 2), (2 * i + 1, 2 * i + 3), (2 * i, 2 * i + 1)]).collect();
+        ladder.push((14, 15));
+        let cycle = graph(12, &cycle);
+        let ladder = graph(16, &ladder);
+
+        let order = circular_order(&cycle, CircularOrdering::CrossingReduction);
+
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..12).collect::<Vec<usize>>());
+        assert!(crossings(&cycle, &(0..12).collect::<Vec<usize>>()) > 0);
+        assert_eq!(crossings(&cycle, &order), 0);
+        let input_crossings = crossings(&ladder, &(0..16).collect::<Vec<usize>>());
+        let reduced = crossings(&ladder, &circular_order(&ladder, CircularOrdering::CrossingReduction));
+        assert!(reduced < input_crossings, "{} >= {}", reduced, input_crossings);
+    }
+
+    #[test]
+    fn circular_order_check_if_groups_are_contiguous() {
+        // Two triangles joined by the edge 2-3 and a separate edge 6-7, nodes of the triangles interleaved
+        let graph = graph(8, &[(0, 4), (4, 2), (2, 0), (1, 3), (3, 5), (5, 1), (2, 3), (6, 7)]);
+
+        let by_component = circular_order(&graph, CircularOrdering::Component);
+        let by_community = circular_order(&graph, CircularOrdering::Community);
+
+        assert_eq!(by_component, vec![0, 2, 4, 3, 1, 5, 6, 7]);
+        let mut first: Vec<usize> = by_community[..3].to_vec();
+        first.sort_unstable();
+        let mut second: Vec<usize> = by_community[3..6].to_vec();
+        second.sort_unstable();
+        assert_eq!((first, second), (vec![0, 2, 4], vec![1, 3, 5]));
+        assert_eq!(&by_community[6..], &[6, 7]);
+        assert_eq!(circular_order(&graph, CircularOrdering::Input), (0..8).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn degree_rings_check_if_hubs_are_inside() {
+        // Star with center 0 whose leaves 1 to 3 have an extra leaf each
+        let star = graph(10, &[(0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (0, 6), (1, 7), (2, 8), (3, 9)]);
+        let cycle = graph(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+
+        assert_eq!(degree_rings(&star, 3), vec![0, 1, 1, 1, 2, 2, 2, 2, 2, 2]);
+        assert_eq!(degree_rings(&star, 1), vec![0; 10]);
+        // All nodes of a cycle have the same degree and share one ring
+        assert_eq!(degree_rings(&cycle, 3), vec![0; 5]);
+    }
+}
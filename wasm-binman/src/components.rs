@@ -0,0 +1,245 @@
+use crate::area::LayoutOptions;
+use crate::{Graph, Node, Point};
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR LAYING OUT DISCONNECTED GRAPHS ONE CONNECTED COMPONENT AT A TIME
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+
+// Side of the bounding box of a component with a single edge before packing, larger components grow with the root of their size
+const COMPONENT_UNIT: f64 = 10.0;
+
+// Free space left between two packed components, relative to COMPONENT_UNIT
+const COMPONENT_GAP: f64 = 1.0;
+
+/*
+Split the nodes of the graph into connected components, edge directions are ignored.
+Components are ordered by their smallest node index and list their nodes in increasing order.
+*/
+pub(crate) fn connected_components(graph: &Graph) -> Vec<Vec<usize>> {
+    let num_nodes = graph.nodes.len();
+    let mut neighbors = vec![Vec::new(); num_nodes];
+    for edge in &graph.edges {
+        neighbors[edge.source].push(edge.target);
+        neighbors[edge.target].push(edge.source);
+    }
+
+    let mut component_of = vec![usize::MAX; num_nodes];
+    let mut components = Vec::new();
+    for start in 0..num_nodes {
+        if component_of[start] != usize::MAX {
+            continue;
+        }
+        let mut component = vec![start];
+        component_of[start] = components.len();
+        let mut next = 0;
+        while next < component.len() {
+            let node = component[next];
+            next += 1;
+            for &neighbor in &neighbors[node] {
+                if component_of[neighbor] == usize::MAX {
+                    component_of[neighbor] = components.len();
+                    component.push(neighbor);
+                }
+            }
+        }
+        component.sort_unstable();
+        components.push(component);
+    }
+    components
+}
+
+// Copy the given nodes and the edges between them into a graph of their own, nodes are renumbered in the given order
+fn subgraph(graph: &Graph, nodes: &[usize]) -> Graph {
+    let mut index = vec![usize::MAX; graph.nodes.len()];
+    for (i, &node) in nodes.iter().enumerate() {
+        index[node] = i;
+    }
+    Graph {
+        nodes: nodes
+            .iter()
+            .map(|&node| {
+                let original = &graph.nodes[node];
+                let mut copy = Node::new(original.position);
+                copy.label = original.label.clone();
+                copy.pinned = original.pinned;
+                copy.initial = original.initial;
+                copy.size = original.size;
+                copy
+            })
+            .collect(),
+        edges: graph
+            .edges
+            .iter()
+            .filter(|edge| index[edge.source] != usize::MAX)
+            .map(|edge| {
+                let mut copy = edge.clone();
+                copy.source = index[edge.source];
+                copy.target = index[edge.target];
+                copy
+            })
+            .collect(),
+    }
+}
+
+/*
+Pack the laid out components into the inner drawing area.
+Every component is scaled so that the side of its bounding box grows with the square root of its number of nodes,
+which keeps the edge lengths of all components comparable. The boxes are then placed on shelves, tallest first
+(next fit decreasing height), with the width of a shelf chosen so that the packing has roughly the shape of the area.
+Finally the whole packing is scaled uniformly to the inner area and centered in it.
+*/
+pub(crate) fn pack_components(graph: &mut Graph, components: &[Vec<usize>], options: &LayoutOptions) {
+    let (area_min, area_max) = options.inner();
+    let (area_width, area_height) = (area_max.x - area_min.x, area_max.y - area_min.y);
+
+    // Bounding box of every component (minimum corner) and its scale
+    let mut boxes = Vec::with_capacity(components.len());
+    for component in components {
+        let mut min = Point { x: f64::INFINITY, y: f64::INFINITY };
+        let mut max = Point { x: f64::NEG_INFINITY, y: f64::NEG_INFINITY };
+        for &node in component {
+            let position = graph.nodes[node].position;
+            min.x = min.x.min(position.x);
+            min.y = min.y.min(position.y);
+            max.x = max.x.max(position.x);
+            max.y = max.y.max(position.y);
+        }
+        let extent = (max.x - min.x).max(max.y - min.y);
+        let scale = if extent > 0.0 { COMPONENT_UNIT * (component.len() as f64 - 1.0).sqrt() / extent } else { 1.0 };
+        boxes.push((min, (max.x - min.x) * scale, (max.y - min.y) * scale, scale));
+    }
+
+    let gap = COMPONENT_UNIT * COMPONENT_GAP;
+    let total_area: f64 = boxes.iter().map(|&(_, width, height, _)| (width + gap) * (height + gap)).sum();
+    let widest = boxes.iter().map(|&(_, width, _, _)| width).fold(0.0, f64::max);
+    let aspect_ratio = if area_width > 0.0 && area_height > 0.0 { area_width / area_height } else { 1.0 };
+    let shelf_width = (total_area * aspect_ratio).sqrt().max(widest);
+
+    let mut order: Vec<usize> = (0..components.len()).collect();
+    order.sort_by(|&a, &b| boxes[b].2.total_cmp(&boxes[a].2));
+
+    // Place the boxes shelf by shelf
+    let mut corners = vec![Point { x: 0.0, y: 0.0 }; components.len()];
+    let (mut x, mut y, mut shelf_height) = (0.0, 0.0, 0.0_f64);
+    let (mut total_width, mut total_height) = (0.0_f64, 0.0_f64);
+    for &i in &order {
+        let (_, width, height, _) = boxes[i];
+        if x > 0.0 && x + width > shelf_width {
+            x = 0.0;
+            y += shelf_height + gap;
+            shelf_height = 0.0;
+        }
+        corners[i] = Point { x, y };
+        x += width + gap;
+        shelf_height = shelf_height.max(height);
+        total_width = total_width.max(corners[i].x + width);
+        total_height = total_height.max(y + height);
+    }
+
+    // Fit the packing into the inner area, keeping its aspect ratio
+    let fit = |extent: f64, available: f64| if extent > 0.0 { available / extent } else { f64::INFINITY };
+    let output_scale = fit(total_width, area_width).min(fit(total_height, area_height));
+    let output_scale = if output_scale.is_finite() { output_scale } else { 1.0 };
+    let offset = Point {
+        x: area_min.x + (area_width - total_width * output_scale) / 2.0,
+        y: area_min.y + (area_height - total_height * output_scale) / 2.0,
+    };
+    for (component, (&(min, _, _, scale), corner)) in components.iter().zip(boxes.iter().zip(&corners)) {
+        for &node in component {
+            let position = &mut graph.nodes[node].position;
+            position.x = offset.x + (corner.x + (position.x - min.x) * scale) * output_scale;
+            position.y = offset.y + (corner.y + (position.y - min.y) * scale) * output_scale;
+        }
+    }
+}
+
+/*
+Run a layout on every connected component separately and pack the results, so components neither overlap
+nor get pushed against the border of the drawing by each other's repulsion.
+Graphs with pinned nodes are laid out as a whole, since the pins fix absolute coordinates that packing would move.
+*/
+pub(crate) fn layout_components<F: FnMut(&mut Graph)>(graph: &mut Graph, options: &LayoutOptions, mut layout: F) {
+    let components = connected_components(graph);
+    if components.len() <= 1 || graph.nodes.iter().any(|node| node.is_pinned()) {
+        layout(graph);
+        return;
+    }
+
+    for component in &components {
+        let mut part = subgraph(graph, component);
+        layout(&mut part);
+        for (&node, laid_out) in component.iter().zip(&part.nodes) {
+            graph.nodes[node].position = laid_out.position;
+        }
+    }
+    pack_components(graph, &components, options);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+    use crate::Edge;
+
+    // Bounding box (min x, min y, max x, max y) of a set of nodes
+    fn bounds(graph: &Graph, nodes: &[usize]) -> (f64, f64, f64, f64) {
+        nodes.iter().map(|&node| graph.nodes[node].position).fold(
+            (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            |(x0, y0, x1, y1), p| (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)),
+        )
+    }
+
+    #[test]
+    fn connected_components_check_if_components_are_found() {
+        let mut graph = graph(7, &[(0, 3), (3, 5), (1, 2), (6, 2)]);
+        graph.edges[3].directed = true;
+
+        let components = connected_components(&graph);
+
+        assert_eq!(components, vec![vec![0, 3, 5], vec![1, 2, 6], vec![4]]);
+    }
+
+    #[test]
+    fn subgraph_check_if_edges_are_renumbered() {
+        let mut graph = graph(5, &[(0, 4), (1, 2), (4, 3)]);
+        graph.nodes[4].label = "e".to_string();
+
+        let part = subgraph(&graph, &[0, 3, 4]);
+
+        assert_eq!(part.nodes[2].label, "e");
+        assert_eq!(part.edges, vec![Edge::new(0, 2), Edge::new(2, 1)]);
+    }
+
+    #[test]
+    fn pack_components_check_if_boxes_do_not_overlap() {
+        let mut graph = graph(12, &[(0, 1), (1, 2), (2, 3), (4, 5), (5, 6), (7, 8), (9, 10)]);
+        let components = connected_components(&graph);
+
+        pack_components(&mut graph, &components, &LayoutOptions::default());
+
+        let boxes: Vec<_> = components.iter().map(|component| bounds(&graph, component)).collect();
+        for (i, a) in boxes.iter().enumerate() {
+            assert!(a.0 >= -1e-9 && a.1 >= -1e-9 && a.2 <= 100.0 + 1e-9 && a.3 <= 100.0 + 1e-9, "{:?}", a);
+            for b in &boxes[i + 1..] {
+                let separated = a.2 < b.0 || b.2 < a.0 || a.3 < b.1 || b.3 < a.1;
+                assert!(separated, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn layout_components_check_if_each_component_is_laid_out_alone() {
+        let mut graph = graph(5, &[(0, 1), (2, 3), (3, 4)]);
+        let mut sizes = Vec::new();
+
+        layout_components(&mut graph, &LayoutOptions::default(), |part| sizes.push(part.nodes.len()));
+
+        assert_eq!(sizes, vec![2, 3]);
+        graph.nodes[0].pinned = Some(Point { x: 1.0, y: 1.0 });
+        sizes.clear();
+        layout_components(&mut graph, &LayoutOptions::default(), |part| sizes.push(part.nodes.len()));
+        assert_eq!(sizes, vec![5]);
+    }
+}
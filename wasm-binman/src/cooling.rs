@@ -0,0 +1,179 @@
+use crate::Convergence;
+use serde::{Deserialize, Serialize};
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE TEMPERATURE OF THE FORCE-DIRECTED LAYOUTS AND FOR STOPPING THEM ONCE THEY SETTLE
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Simulated annealing for force-directed layouts. The temperature is a factor between 0 and 1 of the largest step
+a node may take in an iteration, so nodes move freely at first and settle as the layout cools down:
+- linear: the temperature falls from 1 to 0 over the iterations, as in the paper by Fruchterman and Reingold
+- exponential: the temperature is multiplied by the same factor in every iteration and ends at FINAL_TEMPERATURE
+- adaptive: the step length of Hu (2005), the temperature falls by ADAPTIVE_FACTOR whenever the energy grows
+  and rises again after ADAPTIVE_PROGRESS iterations in a row that lowered it, never above 1
+A layout refining an existing drawing starts at a lower temperature, the schedules are then scaled down to it.
+The energy of an iteration is the sum of the squared forces on the nodes that may move, it vanishes once all forces
+are balanced. A layout has converged, and stops early, once its energy falls below `tolerance` times the energy
+of its first iteration.
+*/
+
+// Temperature of the last iteration of the exponential schedule
+const FINAL_TEMPERATURE: f64 = 1e-3;
+
+// Starting temperature of a layout that refines a given drawing (warm start) instead of starting over
+pub(crate) const WARM_START_TEMPERATURE: f64 = 0.1;
+
+// Cooling factor of the adaptive schedule and number of improving iterations before it heats up again
+const ADAPTIVE_FACTOR: f64 = 0.9;
+const ADAPTIVE_PROGRESS: usize = 5;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CoolingSchedule {
+    Linear,
+    Exponential,
+    Adaptive,
+}
+
+impl CoolingSchedule {
+    pub(crate) fn from_name(name: &str) -> Option<CoolingSchedule> {
+        match name {
+            "linear" => Some(CoolingSchedule::Linear),
+            "exponential" => Some(CoolingSchedule::Exponential),
+            "adaptive" => Some(CoolingSchedule::Adaptive),
+            _ => None,
+        }
+    }
+}
+
+/*
+Temperature and energies of a running layout, kept between iterations so that a layout run a few iterations at a time
+cools down exactly as one run all at once. `iterations` is the length of the linear and exponential schedules.
+*/
+#[derive(Debug, Clone)]
+pub(crate) struct Cooling {
+    schedule: CoolingSchedule,
+    iterations: usize,
+    tolerance: f64,
+    initial_temperature: f64,
+    iteration: usize,
+    temperature: f64,
+    progress: usize,
+    initial_energy: Option<f64>,
+    energy: f64,
+    converged: bool,
+}
+
+impl Cooling {
+    pub(crate) fn new(schedule: CoolingSchedule, iterations: usize, tolerance: f64) -> Cooling {
+        Cooling::with_temperature(schedule, iterations, tolerance, 1.0)
+    }
+
+    // Cooling that starts at `temperature` instead of 1 and never heats up above it
+    pub(crate) fn with_temperature(schedule: CoolingSchedule, iterations: usize, tolerance: f64, temperature: f64) -> Cooling {
+        Cooling {
+            schedule,
+            iterations,
+            tolerance,
+            initial_temperature: temperature,
+            iteration: 0,
+            temperature,
+            progress: 0,
+            initial_energy: None,
+            energy: 0.0,
+            converged: false,
+        }
+    }
+
+    // Fraction of the largest step allowed in the current iteration
+    pub(crate) fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    pub(crate) fn converged(&self) -> bool {
+        self.converged
+    }
+
+    // Record the energy of the iteration just run, check for convergence and cool down for the next one
+    pub(crate) fn cool(&mut self, energy: f64) {
+        let initial_energy = *self.initial_energy.get_or_insert(energy);
+        let previous_energy = if self.iteration == 0 { f64::INFINITY } else { self.energy };
+        self.iteration += 1;
+        self.energy = energy;
+        self.converged = energy <= self.tolerance * initial_energy;
+
+        let progress = self.iteration as f64 / self.iterations.max(1) as f64;
+        self.temperature = match self.schedule {
+            CoolingSchedule::Linear => self.initial_temperature * (1.0 - progress).max(0.0),
+            CoolingSchedule::Exponential => self.initial_temperature * FINAL_TEMPERATURE.powf(progress.min(1.0)),
+            CoolingSchedule::Adaptive if energy < previous_energy => {
+                self.progress += 1;
+                if self.progress >= ADAPTIVE_PROGRESS {
+                    self.progress = 0;
+                    (self.temperature / ADAPTIVE_FACTOR).min(self.initial_temperature)
+                } else {
+                    self.temperature
+                }
+            }
+            CoolingSchedule::Adaptive => {
+                self.progress = 0;
+                self.temperature * ADAPTIVE_FACTOR
+            }
+        };
+    }
+
+    pub(crate) fn convergence(&self) -> Convergence {
+        Convergence { value: self.energy, iterations: self.iteration, converged: self.converged }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temperatures(schedule: CoolingSchedule, energies: &[f64]) -> Vec<f64> {
+        let mut cooling = Cooling::new(schedule, 4, 0.0);
+        energies
+            .iter()
+            .map(|&energy| {
+                cooling.cool(energy);
+                cooling.temperature()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cooling_check_schedules() {
+        let energies = [8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 4.0];
+
+        assert_eq!(temperatures(CoolingSchedule::Linear, &energies[..4]), vec![0.75, 0.5, 0.25, 0.0]);
+        let exponential = temperatures(CoolingSchedule::Exponential, &energies[..4]);
+        assert!((exponential[1] - FINAL_TEMPERATURE.sqrt()).abs() < 1e-12 && (exponential[3] - FINAL_TEMPERATURE).abs() < 1e-12);
+        // Five improving iterations would heat up again but the temperature stays at most 1, growing energy cools down
+        assert_eq!(temperatures(CoolingSchedule::Adaptive, &energies), vec![1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.9]);
+        // A lower starting temperature scales the schedules down
+        let mut refining = Cooling::with_temperature(CoolingSchedule::Linear, 4, 0.0, 0.2);
+        assert_eq!(refining.temperature(), 0.2);
+        refining.cool(1.0);
+        assert!((refining.temperature() - 0.15).abs() < 1e-12);
+        assert_eq!(CoolingSchedule::from_name("adaptive"), Some(CoolingSchedule::Adaptive));
+        assert_eq!(CoolingSchedule::from_name("annealing"), None);
+    }
+
+    #[test]
+    fn cooling_check_if_convergence_is_relative_to_first_energy() {
+        let mut cooling = Cooling::new(CoolingSchedule::Adaptive, 100, 0.01);
+
+        cooling.cool(200.0);
+        cooling.cool(100.0);
+        assert!(!cooling.converged());
+        cooling.cool(1.5);
+        assert_eq!(cooling.convergence(), Convergence { value: 1.5, iterations: 3, converged: true });
+        assert_eq!(
+            cooling.convergence().combine(Convergence { value: 2.0, iterations: 7, converged: false }),
+            Convergence { value: 3.5, iterations: 7, converged: false }
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use crate::Graph;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR GRAPH-THEORETIC DISTANCES USED BY THE DISTANCE BASED LAYOUTS
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Distances are computed by a breadth-first search from every source when all edges have weight 1
+and by Dijkstra's algorithm otherwise, which takes O(n * m) (resp. O(n * m log n)) instead of the O(n^3) of Floyd-Warshall.
+For large graphs the pivot mode computes distances from k pivots only, every node then keeps
+its distances to the pivots and its neighbors, which needs O(k * n) time and memory.
+*/
+
+
+/*
+Adjacency lists of a graph in compressed form, the neighbors of node i are
+targets[offsets[i]..offsets[i + 1]] and the lengths of the edges leading to them weights[offsets[i]..offsets[i + 1]].
+*/
+#[derive(Debug)]
+pub(crate) struct Adjacency {
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    weights: Vec<f64>,
+    unweighted: bool,
+}
+
+impl Adjacency {
+    /*
+    Build the adjacency of a graph. The length of an edge is its weight.
+    With `follow_direction` a directed edge can only be traversed from its source to its target.
+    */
+    pub(crate) fn new(graph: &Graph, follow_direction: bool) -> Adjacency {
+        let num_nodes = graph.nodes.len();
+        let mut arcs = Vec::with_capacity(2 * graph.edges.len());
+        for edge in &graph.edges {
+            arcs.push((edge.source, edge.target, edge.weight));
+            if !(edge.directed && follow_direction) {
+                arcs.push((edge.target, edge.source, edge.weight));
+            }
+        }
+
+        let mut offsets = vec![0; num_nodes + 1];
+        for &(source, _, _) in &arcs {
+            offsets[source + 1] += 1;
+        }
+        for i in 0..num_nodes {
+            offsets[i + 1] += offsets[i];
+        }
+        let mut next = offsets.clone();
+        let mut targets = vec![0; arcs.len()];
+        let mut weights = vec![0.0; arcs.len()];
+        for &(source, target, weight) in &arcs {
+            targets[next[source]] = target;
+            weights[next[source]] = weight;
+            next[source] += 1;
+        }
+        let unweighted = weights.iter().all(|&weight| weight == 1.0);
+        Adjacency { offsets, targets, weights, unweighted }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    // Neighbors of a node together with the lengths of the edges leading to them
+    pub(crate) fn neighbors(&self, node: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.offsets[node]..self.offsets[node + 1];
+        self.targets[range.clone()].iter().copied().zip(self.weights[range].iter().copied())
+    }
+
+    // Distances from `source` to every node, unreachable nodes are left at infinity
+    pub(crate) fn distances_from(&self, source: usize) -> Vec<f64> {
+        if self.unweighted {
+            self.breadth_first_search(source)
+        } else {
+            self.dijkstra(source)
+        }
+    }
+
+    fn breadth_first_search(&self, source: usize) -> Vec<f64> {
+        let mut distances = vec![f64::INFINITY; self.len()];
+        let mut queue = VecDeque::new();
+        distances[source] = 0.0;
+        queue.push_back(source);
+        while let Some(node) = queue.pop_front() {
+            for (neighbor, _) in self.neighbors(node) {
+                if distances[neighbor].is_infinite() {
+                    distances[neighbor] = distances[node] + 1.0;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        distances
+    }
+
+    fn dijkstra(&self, source: usize) -> Vec<f64> {
+        let mut distances = vec![f64::INFINITY; self.len()];
+        let mut heap = BinaryHeap::new();
+        distances[source] = 0.0;
+        heap.push(Candidate { distance: 0.0, node: source });
+        while let Some(Candidate { distance, node }) = heap.pop() {
+            if distance > distances[node] {
+                continue;
+            }
+            for (neighbor, weight) in self.neighbors(node) {
+                let new_distance = distance + weight;
+                if new_distance < distances[neighbor] {
+                    distances[neighbor] = new_distance;
+                    heap.push(Candidate { distance: new_distance, node: neighbor });
+                }
+            }
+        }
+        distances
+    }
+}
+
+// Entry of Dijkstra's priority queue, ordered so that the max-heap pops the smallest distance first
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    distance: f64,
+    node: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other.distance.total_cmp(&self.distance).then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/*
+Compute graph-theoretic distances between every pair of nodes.
+The length of an edge is its weight, unreachable pairs are left at infinity.
+With `follow_direction` a directed edge can only be traversed from its source to its target,
+so distances[i][j] is the length of the shortest directed path from i to j.
+Shared by the distance based layouts: stress majorization, multidimensional scaling and Kamada-Kawai.
+A drawing has a single distance between two points, so these layouts ignore the direction of edges.
+*/
+pub(crate) fn shortest_path_distances(graph: &Graph, follow_direction: bool) -> Vec<Vec<f64>> {
+    let adjacency = Adjacency::new(graph, follow_direction);
+    (0..adjacency.len()).map(|source| adjacency.distances_from(source)).collect()
+}
+
+/*
+Choose up to `count` pivots spread over the graph (max-min selection): the first pivot is node 0,
+every further pivot is the node farthest from all pivots chosen so far.
+Returns the pivots and the distances from each of them to every node.
+*/
+pub(crate) fn pivot_distances(adjacency: &Adjacency, count: usize) -> (Vec<usize>, Vec<Vec<f64>>) {
+    let num_nodes = adjacency.len();
+    let mut pivots = Vec::new();
+    let mut distances = Vec::new();
+    let mut nearest = vec![f64::INFINITY; num_nodes];
+    let mut is_pivot = vec![false; num_nodes];
+    let mut next = 0;
+    while pivots.len() < count.min(num_nodes) {
+        let row = adjacency.distances_from(next);
+        for (nearest, &distance) in nearest.iter_mut().zip(&row) {
+            *nearest = nearest.min(distance);
+        }
+        pivots.push(next);
+        is_pivot[next] = true;
+        distances.push(row);
+
+        // Unreachable nodes count as the farthest ones, so every component gets a pivot
+        next = (0..num_nodes)
+            .filter(|&node| !is_pivot[node])
+            .max_by(|&a, &b| nearest[a].total_cmp(&nearest[b]).then_with(|| b.cmp(&a)))
+            .unwrap_or(0);
+    }
+    (pivots, distances)
+}
+
+/*
+Target distances used by the distance based layouts, terms[i] lists the nodes j with the graph-theoretic distance
+between i and j that the drawing should reproduce. Edge directions are ignored and unreachable pairs are left out.
+With `pivots` = 0 (or at least the number of nodes) every pair of nodes is included. Otherwise every node keeps
+only its distances to the pivots and to its neighbors, which approximates the full stress in O(k * n).
+*/
+pub(crate) fn distance_terms(graph: &Graph, pivots: usize) -> Vec<Vec<(usize, f64)>> {
+    let adjacency = Adjacency::new(graph, false);
+    let num_nodes = adjacency.len();
+    let finite = |(j, distance): (usize, f64)| if distance.is_finite() { Some((j, distance)) } else { None };
+
+    if pivots == 0 || pivots >= num_nodes {
+        return (0..num_nodes)
+            .map(|i| {
+                let row = adjacency.distances_from(i);
+                row.into_iter().enumerate().filter(|&(j, _)| j != i).filter_map(finite).collect()
+            })
+            .collect();
+    }
+
+    let (pivots, distances) = pivot_distances(&adjacency, pivots);
+    (0..num_nodes)
+        .map(|i| {
+            let mut terms: Vec<(usize, f64)> = pivots
+                .iter()
+                .zip(&distances)
+                .filter(|&(&pivot, _)| pivot != i)
+                .filter_map(|(&pivot, row)| finite((pivot, row[i])))
+                .collect();
+            for (neighbor, weight) in adjacency.neighbors(i) {
+                if neighbor != i && !pivots.contains(&neighbor) {
+                    terms.push((neighbor, weight));
+                }
+            }
+            terms
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_edges, NodeInterner};
+    use crate::tests::graph;
+    use crate::Edge;
+
+    #[test]
+    fn shortest_path_distances_check_path_graph() {
+        let graph = graph(4, &[(0, 1), (1, 2)]);
+
+        let distances = shortest_path_distances(&graph, false);
+
+        assert_eq!(distances[0][2], 2.0);
+        assert_eq!(distances[2][0], 2.0);
+        assert_eq!(distances[1][1], 0.0);
+        assert!(distances[0][3].is_infinite());
+    }
+
+    #[test]
+    fn shortest_path_distances_check_weighted_edges() {
+        let mut graph = graph(3, &[]);
+        graph.edges = parse_edges("0-1:2,1-2:0.5,0-2:4", &mut NodeInterner::default()).unwrap();
+
+        let distances = shortest_path_distances(&graph, false);
+        graph.edges.push(Edge::new(0, 2));
+        let shortcut = shortest_path_distances(&graph, false);
+
+        assert_eq!(distances[0][2], 2.5);
+        assert_eq!(distances[2][0], 2.5);
+        assert_eq!(shortcut[0][2], 1.0);
+    }
+
+    #[test]
+    fn shortest_path_distances_check_directed_edges() {
+        let mut graph = graph(3, &[]);
+        graph.edges = parse_edges("0->1,1->2", &mut NodeInterner::default()).unwrap();
+
+        let directed = shortest_path_distances(&graph, true);
+        let undirected = shortest_path_distances(&graph, false);
+
+        assert_eq!(directed[0][2], 2.0);
+        assert!(directed[2][0].is_infinite());
+        assert_eq!(undirected[2][0], 2.0);
+    }
+
+    #[test]
+    fn pivot_distances_check_if_pivots_are_spread() {
+        let path = graph(9, &(0..8).map(|i| (i, i + 1)).collect::<Vec<_>>());
+
+        let (pivots, distances) = pivot_distances(&Adjacency::new(&path, false), 3);
+
+        assert_eq!(pivots, vec![0, 8, 4]);
+        assert_eq!(distances[1][3], 5.0);
+    }
+
+    #[test]
+    fn distance_terms_check_exact_and_pivot_modes() {
+        let mut edges: Vec<(usize, usize)> = (0..19).map(|i| (i, i + 1)).collect();
+        edges.push((20, 21));
+        let graph = graph(22, &edges);
+
+        let exact = distance_terms(&graph, 0);
+        let sparse = distance_terms(&graph, 4);
+
+        assert_eq!(exact[0].len(), 19);
+        assert!(exact[0].contains(&(19, 19.0)));
+        assert!(sparse[10].len() <= 6);
+        assert!(sparse[10].contains(&(9, 1.0)) && sparse[10].contains(&(11, 1.0)));
+        assert!(sparse[10].contains(&(0, 10.0)));
+        assert!(sparse.iter().all(|terms| terms.iter().all(|&(_, distance)| distance.is_finite())));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::quadtree::QuadTree;
+use crate::area::LayoutOptions;
+use crate::cooling::Cooling;
+use crate::{Graph, Point};
+use wasm_bindgen::prelude::*;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE FORCEATLAS2 LAYOUT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Force-Atlas2 is a popular force-directed layout algorithm used in network visualization,
+particularly within the Gephi software.
+It operates by simulating a physical system where nodes repel each other,
+similar to charged particles, while edges act like springs that attract connected nodes.
+This dual-force dynamic helps in spreading out the nodes in a visually appealing and interpretable manner,
+reducing clutter and enhancing the clarity of the network structure.
+
+The forces follow Gephi (Jacomy et al. 2014):
+- every node has the mass degree + 1 and two nodes repel each other with scaling_ratio * m_i * m_j / d,
+  so hubs push harder than leaves
+- an edge attracts with w * d, or with w * ln(1 + d) in LinLog mode, which gives tighter clusters;
+  w is the edge weight to the power edge_weight_influence (0 ignores weights)
+- dissuade hubs divides the attraction of an edge by the mass of its source, so hubs move to the border
+- gravity pulls every node to the center with gravity * m, strong gravity with gravity * m * d
+- prevent overlap uses the nodes as circles (half the larger side of their size): touching circles repel
+  a hundred times harder, they do not attract and nodes move at most a short distance per iteration
+- the speed adapts: the swinging of a node (how much its force changed since the last iteration) slows it down,
+  and the global speed grows as long as the total swinging stays small compared to the total traction
+Gephi pulls toward the origin, here gravity pulls toward the center of the drawing area that the random
+initial layout fills, which only translates the result. The area also bounds the nodes if it clamps them.
+The step of every node, at the adaptive speed, is also scaled by the temperature of the cooling schedule
+(see cooling.rs), so the layout settles as it cools down and stops early once the energy of its forces has converged.
+*/
+
+// Speed efficiency never drops below this value
+const MIN_SPEED_EFFICIENCY: f64 = 0.05;
+
+// Upper bound of the jitter tolerance estimated from the traction
+const MAX_JITTER_TOLERANCE: f64 = 10.0;
+
+// The global speed grows by at most this fraction per iteration
+const MAX_SPEED_RISE: f64 = 0.5;
+
+// Factor of the repulsion between overlapping nodes when overlap is prevented
+const OVERLAP_REPULSION: f64 = 100.0;
+
+// Largest step of a node per iteration when overlap is prevented
+const MAX_OVERLAP_STEP: f64 = 10.0;
+
+/*
+Options of the layout, named as in Gephi. JavaScript creates them with the defaults of the viewer
+and sets the options it changes by name, e.g. `settings.lin_log = true`.
+*/
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceAtlas2Settings {
+    pub gravity: f64,
+    pub scaling_ratio: f64,
+    // Barnes-Hut approximation of the repulsion, 0 computes it exactly (see apply_repulsion in lib.rs)
+    pub theta: f64,
+    pub lin_log: bool,
+    pub dissuade_hubs: bool,
+    pub strong_gravity: bool,
+    pub edge_weight_influence: f64,
+    pub prevent_overlap: bool,
+    // How much swinging is tolerated before the speed goes down, Gephi's "Tolerance (speed)"
+    pub jitter_tolerance: f64,
+}
+
+impl Default for ForceAtlas2Settings {
+    fn default() -> ForceAtlas2Settings {
+        ForceAtlas2Settings {
+            gravity: 0.1,
+            scaling_ratio: 10.0,
+            theta: 0.5,
+            lin_log: false,
+            dissuade_hubs: false,
+            strong_gravity: false,
+            edge_weight_influence: 1.0,
+            prevent_overlap: false,
+            jitter_tolerance: 1.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ForceAtlas2Settings {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ForceAtlas2Settings {
+        ForceAtlas2Settings::default()
+    }
+}
+
+/*
+Speeds carried from one iteration to the next: the force on every node in the previous iteration,
+the global speed and its efficiency. Keeping it lets a layout continue exactly where it stopped.
+*/
+#[derive(Debug, Clone)]
+pub(crate) struct ForceAtlas2State {
+    previous_forces: Vec<Point>,
+    speed: f64,
+    speed_efficiency: f64,
+}
+
+impl ForceAtlas2State {
+    pub(crate) fn new(num_nodes: usize) -> ForceAtlas2State {
+        ForceAtlas2State { previous_forces: vec![Point { x: 0.0, y: 0.0 }; num_nodes], speed: 1.0, speed_efficiency: 1.0 }
+    }
+}
+
+/*
+Repulsion factor between two nodes, to be multiplied with the vector between them.
+`radii` are given when overlap is prevented, the distance then runs between the borders of the circles.
+*/
+fn repulsion_factor(scaling_ratio: f64, distance: f64, masses: f64, radii: Option<f64>) -> f64 {
+    match radii {
+        Some(radii) => {
+            let gap = distance - radii;
+            if gap > 0.0 {
+                scaling_ratio * masses / (gap * gap)
+            } else if gap < 0.0 {
+                OVERLAP_REPULSION * scaling_ratio * masses
+            } else {
+                0.0
+            }
+        }
+        None if distance > 0.0 => scaling_ratio * masses / (distance * distance),
+        None => 0.0,
+    }
+}
+
+fn apply_repulsion(graph: &Graph, settings: &ForceAtlas2Settings, masses: &[f64], radii: &[f64], forces: &mut [Point]) {
+    let positions: Vec<Point> = graph.nodes.iter().map(|node| node.position).collect();
+    let pair_radii = |i: usize, j: usize| if settings.prevent_overlap { Some(radii[i] + radii[j]) } else { None };
+    let mut push = |i: usize, other: Point, factor: f64| {
+        forces[i].x += (positions[i].x - other.x) * factor;
+        forces[i].y += (positions[i].y - other.y) * factor;
+    };
+
+    if settings.theta <= 0.0 {
+        for i in 0..positions.len() {
+            for j in (0..positions.len()).filter(|&j| j != i) {
+                let factor = repulsion_factor(settings.scaling_ratio, positions[i].distance(positions[j]), masses[i] * masses[j], pair_radii(i, j));
+                push(i, positions[j], factor);
+            }
+        }
+        return;
+    }
+
+    let tree = QuadTree::new(positions.clone(), masses.to_vec());
+    for i in 0..positions.len() {
+        tree.for_each_interaction(i, settings.theta, |other, mass, body| {
+            // Aggregated cells have no size, only single nodes can overlap
+            let radii = body.and_then(|j| pair_radii(i, j));
+            let factor = repulsion_factor(settings.scaling_ratio, positions[i].distance(other), masses[i] * mass, radii);
+            push(i, other, factor);
+        });
+    }
+}
+
+fn apply_gravity(graph: &Graph, settings: &ForceAtlas2Settings, center: Point, masses: &[f64], forces: &mut [Point]) {
+    for ((node, mass), force) in graph.nodes.iter().zip(masses).zip(forces.iter_mut()) {
+        let delta = Point { x: node.position.x - center.x, y: node.position.y - center.y };
+        let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
+        if distance > 0.0 {
+            let factor = if settings.strong_gravity { settings.gravity * mass } else { settings.gravity * mass / distance };
+            force.x -= delta.x * factor;
+            force.y -= delta.y * factor;
+        }
+    }
+}
+
+fn apply_attraction(graph: &Graph, settings: &ForceAtlas2Settings, masses: &[f64], radii: &[f64], forces: &mut [Point]) {
+    // Dissuading hubs divides by masses, the mean mass brings the attraction back to its usual strength
+    let compensation = if settings.dissuade_hubs && !masses.is_empty() { masses.iter().sum::<f64>() / masses.len() as f64 } else { 1.0 };
+    for edge in graph.edges.iter().filter(|edge| edge.source != edge.target) {
+        let (source, target) = (graph.nodes[edge.source].position, graph.nodes[edge.target].position);
+        let delta = Point { x: source.x - target.x, y: source.y - target.y };
+        let mut distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
+        if settings.prevent_overlap {
+            distance -= radii[edge.source] + radii[edge.target];
+        }
+        if distance <= 0.0 {
+            continue;
+        }
+        let mut factor = compensation * edge.weight.powf(settings.edge_weight_influence);
+        if settings.lin_log {
+            factor *= (1.0 + distance).ln() / distance;
+        }
+        if settings.dissuade_hubs {
+            factor /= masses[edge.source];
+        }
+        forces[edge.source].x -= delta.x * factor;
+        forces[edge.source].y -= delta.y * factor;
+        forces[edge.target].x += delta.x * factor;
+        forces[edge.target].y += delta.y * factor;
+    }
+}
+
+// Adapt the global speed to the total swinging and traction of the movable nodes
+fn adjust_speed(graph: &Graph, settings: &ForceAtlas2Settings, masses: &[f64], forces: &[Point], state: &mut ForceAtlas2State) {
+    let (mut swinging, mut traction) = (0.0, 0.0);
+    for (i, (force, previous)) in forces.iter().zip(&state.previous_forces).enumerate() {
+        if !graph.nodes[i].is_pinned() {
+            swinging += masses[i] * (force.x - previous.x).hypot(force.y - previous.y);
+            traction += masses[i] * 0.5 * (force.x + previous.x).hypot(force.y + previous.y);
+        }
+    }
+    if swinging <= 0.0 || traction <= 0.0 {
+        return;
+    }
+
+    let num_nodes = forces.len() as f64;
+    let estimated_jitter_tolerance = 0.05 * num_nodes.sqrt();
+    let min_jitter_tolerance = estimated_jitter_tolerance.sqrt();
+    let mut jitter_tolerance = settings.jitter_tolerance
+        * min_jitter_tolerance.max(MAX_JITTER_TOLERANCE.min(estimated_jitter_tolerance * traction / (num_nodes * num_nodes)));
+    // Protection against erratic behavior
+    if swinging / traction > 2.0 {
+        if state.speed_efficiency > MIN_SPEED_EFFICIENCY {
+            state.speed_efficiency *= 0.5;
+        }
+        jitter_tolerance = jitter_tolerance.max(settings.jitter_tolerance);
+    }
+    let target_speed = jitter_tolerance * state.speed_efficiency * traction / swinging;
+    if swinging > jitter_tolerance * traction {
+        if state.speed_efficiency > MIN_SPEED_EFFICIENCY {
+            state.speed_efficiency *= 0.7;
+        }
+    } else if state.speed < 1000.0 {
+        state.speed_efficiency *= 1.3;
+    }
+    state.speed += (target_speed - state.speed).min(MAX_SPEED_RISE * state.speed);
+}
+
+/*
+Run the given number of iterations from the current positions, continuing with the speeds in `state`,
+or fewer once `cooling` finds that the layout converged. The steps shrink with the temperature of `cooling`.
+Pinned nodes exert forces but stay where they are.
+*/
+pub(crate) fn force_atlas2<'a>(
+    graph: &'a mut Graph,
+    iterations: usize,
+    settings: &ForceAtlas2Settings,
+    options: &LayoutOptions,
+    state: &mut ForceAtlas2State,
+    cooling: &mut Cooling,
+) -> &'a Graph {
+    let num_nodes = graph.nodes.len();
+    let mut masses = vec![1.0; num_nodes];
+    for edge in graph.edges.iter().filter(|edge| edge.source != edge.target) {
+        masses[edge.source] += 1.0;
+        masses[edge.target] += 1.0;
+    }
+    let radii: Vec<f64> = graph.nodes.iter().map(|node| node.size.width.max(node.size.height) / 2.0).collect();
+
+    for _ in 0..iterations {
+        if cooling.converged() {
+            break;
+        }
+        let mut forces = vec![Point { x: 0.0, y: 0.0 }; num_nodes];
+        apply_repulsion(graph, settings, &masses, &radii, &mut forces);
+        apply_gravity(graph, settings, options.center(), &masses, &mut forces);
+        apply_attraction(graph, settings, &masses, &radii, &mut forces);
+        adjust_speed(graph, settings, &masses, &forces, state);
+
+        let mut energy = 0.0;
+        let temperature = cooling.temperature();
+        for (i, node) in graph.nodes.iter_mut().enumerate().filter(|(_, node)| !node.is_pinned()) {
+            let (force, previous) = (forces[i], state.previous_forces[i]);
+            energy += force.x * force.x + force.y * force.y;
+            let swinging = masses[i] * (force.x - previous.x).hypot(force.y - previous.y);
+            let mut factor = state.speed / (1.0 + (state.speed * swinging).sqrt());
+            if settings.prevent_overlap {
+                let length = force.x.hypot(force.y);
+                factor = if length > 0.0 { (0.1 * factor * length).min(MAX_OVERLAP_STEP) / length } else { 0.0 };
+            }
+            node.position.x += force.x * factor * temperature;
+            node.position.y += force.y * factor * temperature;
+            if options.clamp {
+                node.position = options.clamp_point(node.position);
+            }
+            node.disp = force;
+        }
+        state.previous_forces = forces;
+        cooling.cool(energy);
+    }
+    graph
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cooling::CoolingSchedule;
+    use crate::tests::graph;
+    use crate::{Rng, Size};
+
+    fn settings() -> ForceAtlas2Settings {
+        ForceAtlas2Settings {
+            gravity: 0.1,
+            scaling_ratio: 2.0,
+            theta: 0.0,
+            lin_log: false,
+            dissuade_hubs: false,
+            strong_gravity: false,
+            edge_weight_influence: 1.0,
+            prevent_overlap: false,
+            jitter_tolerance: 1.0,
+        }
+    }
+
+    // Stays hot and never converges early, so every test runs all of its iterations at the speed of Gephi
+    fn cooling() -> Cooling {
+        Cooling::new(CoolingSchedule::Linear, usize::MAX, 0.0)
+    }
+
+    // The shared graph with its nodes scattered over a square of side 100, nodes on one line would stay on it
+    fn scattered(num_nodes: usize, edges: &[(usize, usize)]) -> Graph {
+        let mut rng = Rng::new(4);
+        let mut graph = graph(num_nodes, edges);
+        for node in &mut graph.nodes {
+            node.position = Point { x: rng.next_f64() * 100.0, y: rng.next_f64() * 100.0 };
+        }
+        graph
+    }
+
+    fn distance(graph: &Graph, i: usize, j: usize) -> f64 {
+        graph.nodes[i].position.distance(graph.nodes[j].position)
+    }
+
+    // Two cliques of five nodes joined by the edge 4-5
+    fn two_cliques() -> Graph {
+        let mut edges = vec![(4, 5)];
+        for offset in [0, 5] {
+            for i in 0..5 {
+                for j in i + 1..5 {
+                    edges.push((offset + i, offset + j));
+                }
+            }
+        }
+        scattered(10, &edges)
+    }
+
+    // Mean distance within the cliques divided by the distance of their centers
+    fn cluster_ratio(graph: &Graph) -> f64 {
+        let center = |nodes: std::ops::Range<usize>| {
+            let sum = nodes.fold((0.0, 0.0), |(x, y), i| (x + graph.nodes[i].position.x, y + graph.nodes[i].position.y));
+            Point { x: sum.0 / 5.0, y: sum.1 / 5.0 }
+        };
+        let (a, b) = (center(0..5), center(5..10));
+        let within: f64 = (0..10).flat_map(|i| (i + 1..10).map(move |j| (i, j))).filter(|&(i, j)| (i < 5) == (j < 5)).map(|(i, j)| distance(graph, i, j)).sum::<f64>() / 20.0;
+        within / a.distance(b)
+    }
+
+    #[test]
+    fn force_atlas2_check_if_clusters_separate_and_lin_log_tightens_them() {
+        let mut linear = two_cliques();
+        let mut lin_log = two_cliques();
+
+        force_atlas2(&mut linear, 500, &settings(), &LayoutOptions::default(), &mut ForceAtlas2State::new(10), &mut cooling());
+        force_atlas2(&mut lin_log, 500, &ForceAtlas2Settings { lin_log: true, ..settings() }, &LayoutOptions::default(), &mut ForceAtlas2State::new(10), &mut cooling());
+
+        assert!(cluster_ratio(&linear) < 1.0, "{}", cluster_ratio(&linear));
+        assert!(cluster_ratio(&lin_log) < cluster_ratio(&linear), "{} >= {}", cluster_ratio(&lin_log), cluster_ratio(&linear));
+    }
+
+    #[test]
+    fn force_atlas2_check_if_steps_follow_the_temperature() {
+        let start = two_cliques();
+        let (mut hot, mut warm, mut cold) = (two_cliques(), two_cliques(), two_cliques());
+
+        force_atlas2(&mut hot, 1, &settings(), &LayoutOptions::default(), &mut ForceAtlas2State::new(10), &mut cooling());
+        force_atlas2(&mut warm, 1, &settings(), &LayoutOptions::default(), &mut ForceAtlas2State::new(10), &mut Cooling::with_temperature(CoolingSchedule::Linear, 10, 0.0, 0.5));
+        force_atlas2(&mut cold, 1, &settings(), &LayoutOptions::default(), &mut ForceAtlas2State::new(10), &mut Cooling::with_temperature(CoolingSchedule::Linear, 10, 0.0, 0.0));
+
+        for ((node, hot), (warm, cold)) in start.nodes.iter().zip(&hot.nodes).zip(warm.nodes.iter().zip(&cold.nodes)) {
+            assert!((2.0 * warm.position.distance(node.position) - hot.position.distance(node.position)).abs() < 1e-9);
+            assert_eq!(cold.position.distance(node.position), 0.0);
+        }
+    }
+
+    #[test]
+    fn force_atlas2_check_if_steps_continue_the_same_layout() {
+        let mut once = two_cliques();
+        let mut twice = two_cliques();
+        let barnes_hut = ForceAtlas2Settings { theta: 0.8, dissuade_hubs: true, ..settings() };
+
+        force_atlas2(&mut once, 30, &barnes_hut, &LayoutOptions::default(), &mut ForceAtlas2State::new(10), &mut cooling());
+        let mut state = ForceAtlas2State::new(10);
+        force_atlas2(&mut twice, 12, &barnes_hut, &LayoutOptions::default(), &mut state, &mut cooling());
+        force_atlas2(&mut twice, 18, &barnes_hut, &LayoutOptions::default(), &mut state, &mut cooling());
+
+        for (a, b) in once.nodes.iter().zip(&twice.nodes) {
+            assert_eq!((a.position.x, a.position.y), (b.position.x, b.position.y));
+        }
+        assert!(state.speed > 0.0 && state.speed_efficiency >= MIN_SPEED_EFFICIENCY * 0.5);
+    }
+
+    #[test]
+    fn force_atlas2_check_gravity_weights_and_overlap() {
+        // Two separate edges, the first one ten times heavier
+        let mut weighted = scattered(4, &[(0, 1), (2, 3)]);
+        weighted.edges[0].weight = 10.0;
+        let mut unweighted = scattered(4, &[(0, 1), (2, 3)]);
+        unweighted.edges[0].weight = 10.0;
+        let mut strong = scattered(4, &[(0, 1), (2, 3)]);
+        let mut star = scattered(8, &(1..8).map(|i| (0, i)).collect::<Vec<_>>());
+        for node in &mut star.nodes {
+            node.size = Size { width: 6.0, height: 6.0 };
+        }
+
+        force_atlas2(&mut weighted, 300, &settings(), &LayoutOptions::default(), &mut ForceAtlas2State::new(4), &mut cooling());
+        force_atlas2(&mut unweighted, 300, &ForceAtlas2Settings { edge_weight_influence: 0.0, ..settings() }, &LayoutOptions::default(), &mut ForceAtlas2State::new(4), &mut cooling());
+        force_atlas2(&mut strong, 300, &ForceAtlas2Settings { strong_gravity: true, ..settings() }, &LayoutOptions::default(), &mut ForceAtlas2State::new(4), &mut cooling());
+        force_atlas2(&mut star, 500, &ForceAtlas2Settings { prevent_overlap: true, ..settings() }, &LayoutOptions::default(), &mut ForceAtlas2State::new(8), &mut cooling());
+
+        assert!(distance(&weighted, 0, 1) < 0.5 * distance(&weighted, 2, 3));
+        assert!(distance(&unweighted, 0, 1) > 0.8 * distance(&unweighted, 2, 3));
+        // Strong gravity keeps both components close to the center
+        let center = LayoutOptions::default().center();
+        let spread = |graph: &Graph| graph.nodes.iter().map(|node| node.position.distance(center)).fold(0.0, f64::max);
+        assert!(spread(&strong) < spread(&unweighted), "{} >= {}", spread(&strong), spread(&unweighted));
+        for i in 0..8 {
+            for j in i + 1..8 {
+                assert!(distance(&star, i, j) > 6.0 * 0.95, "nodes {} and {} overlap", i, j);
+            }
+        }
+    }
+}
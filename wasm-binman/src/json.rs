@@ -0,0 +1,348 @@
+use crate::{Edge, Graph, Point, Size};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE JSON GRAPH INTERCHANGE FORMAT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Example of a document in version 1 of the format:
+{
+    "version": 1,
+    "nodes": [{ "id": 0, "attributes": { "color": "red" } }, { "id": "b" }],
+    "edges": [{ "source": 0, "target": "b", "weight": 2.5, "directed": true, "attributes": { "kind": "road" } }]
+}
+Edges without a weight have weight 1, weights must be positive. Edges are undirected unless "directed" is true.
+Nodes with "pinned": true keep their "x" and "y" coordinates during the layout. With the layout parameter
+"warm_start" the other nodes that have both start at their "x" and "y", e.g. to refine the output of a previous layout.
+Nodes may have a "width" and a "height" in layout coordinates, which overlap removal keeps apart.
+A layout fills in "x" and "y" of every node and adds "metadata" describing how the positions were computed.
+Iterative algorithms also report their result there, e.g. "result": { "stress": 0.28, "iterations": 12, "converged": true },
+the force-directed ones their final "energy" instead of the stress.
+Unknown attributes are passed through untouched.
+*/
+
+// Version of the format produced by this crate, documents with a different version are rejected
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+// Node identifier, either a number or a string, written back exactly as it was read
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub(crate) enum NodeId {
+    Index(u64),
+    Name(String),
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeId::Index(index) => write!(f, "{}", index),
+            NodeId::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct NodeRecord {
+    pub(crate) id: NodeId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) x: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) y: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) height: Option<f64>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub(crate) attributes: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct EdgeRecord {
+    pub(crate) source: NodeId,
+    pub(crate) target: NodeId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) weight: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) directed: bool,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub(crate) attributes: Map<String, Value>,
+}
+
+// Describes which algorithm produced the positions and with which parameters
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct LayoutMetadata {
+    pub(crate) algorithm: String,
+    pub(crate) parameters: Map<String, Value>,
+    // What the algorithm reports about the layout, e.g. the final stress and the number of iterations run
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub(crate) result: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct GraphDocument {
+    pub(crate) version: u32,
+    #[serde(default)]
+    pub(crate) nodes: Vec<NodeRecord>,
+    #[serde(default)]
+    pub(crate) edges: Vec<EdgeRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) metadata: Option<LayoutMetadata>,
+}
+
+// Reasons why a JSON document or a JSON layout request can be rejected
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonError {
+    InvalidJson(String),
+    UnsupportedVersion(u32),
+    DuplicateNode(NodeId),
+    UnknownNode(NodeId),
+    MissingCoordinates(NodeId),
+    InvalidSize(NodeId),
+    InvalidWeight(NodeId, NodeId),
+    UnknownAlgorithm(String),
+    UnknownRoot(String),
+    InvalidArea,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonError::InvalidJson(message) => write!(f, "invalid JSON graph: {}", message),
+            JsonError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported JSON graph version {} (expected {})",
+                version, FORMAT_VERSION
+            ),
+            JsonError::DuplicateNode(id) => write!(f, "node '{}' is defined more than once", id),
+            JsonError::UnknownNode(id) => write!(f, "edge refers to undefined node '{}'", id),
+            JsonError::InvalidWeight(source, target) => {
+                write!(f, "weight of edge '{}'-'{}' must be a positive number", source, target)
+            }
+            JsonError::MissingCoordinates(id) => write!(f, "pinned node '{}' must have both x and y", id),
+            JsonError::InvalidSize(id) => write!(f, "width and height of node '{}' must be non-negative numbers", id),
+            JsonError::UnknownAlgorithm(name) => write!(f, "unknown layout algorithm '{}'", name),
+            JsonError::UnknownRoot(id) => write!(f, "root node '{}' does not exist", id),
+            JsonError::InvalidArea => write!(f, "width and height must be positive and the margin non-negative"),
+        }
+    }
+}
+
+// JSON errors are thrown as JavaScript Error objects
+impl From<JsonError> for JsValue {
+    fn from(error: JsonError) -> JsValue {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+impl GraphDocument {
+    pub(crate) fn parse(graph_json: &str) -> Result<GraphDocument, JsonError> {
+        let document: GraphDocument =
+            serde_json::from_str(graph_json).map_err(|error| JsonError::InvalidJson(error.to_string()))?;
+        if document.version != FORMAT_VERSION {
+            return Err(JsonError::UnsupportedVersion(document.version));
+        }
+        Ok(document)
+    }
+
+    /*
+    Build a document from a graph in the legacy format.
+    Node labels become the ids, numeric labels are written as numbers and unlabeled nodes get their index.
+    */
+    pub(crate) fn from_graph(graph: &Graph) -> GraphDocument {
+        let ids: Vec<NodeId> = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| match node.label.parse::<u64>() {
+                Ok(index) if index.to_string() == node.label => NodeId::Index(index),
+                _ if node.label.is_empty() => NodeId::Index(i as u64),
+                _ => NodeId::Name(node.label.clone()),
+            })
+            .collect();
+        GraphDocument {
+            version: FORMAT_VERSION,
+            nodes: ids
+                .iter()
+                .map(|id| NodeRecord { id: id.clone(), x: None, y: None, pinned: false, width: None, height: None, attributes: Map::new() })
+                .collect(),
+            edges: graph
+                .edges
+                .iter()
+                .map(|edge| EdgeRecord {
+                    source: ids[edge.source].clone(),
+                    target: ids[edge.target].clone(),
+                    weight: if edge.weight == 1.0 { None } else { Some(edge.weight) },
+                    directed: edge.directed,
+                    attributes: Map::new(),
+                })
+                .collect(),
+            metadata: None,
+        }
+    }
+
+    // Translate node ids used by the edges into indices of the `nodes` array
+    pub(crate) fn resolve_edges(&self) -> Result<Vec<Edge>, JsonError> {
+        let mut indices = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if indices.insert(&node.id, i).is_some() {
+                return Err(JsonError::DuplicateNode(node.id.clone()));
+            }
+        }
+        let index = |id: &NodeId| indices.get(id).copied().ok_or_else(|| JsonError::UnknownNode(id.clone()));
+
+        self.edges
+            .iter()
+            .map(|edge| {
+                let weight = edge.weight.unwrap_or(1.0);
+                if !(weight.is_finite() && weight > 0.0) {
+                    return Err(JsonError::InvalidWeight(edge.source.clone(), edge.target.clone()));
+                }
+                Ok(Edge { source: index(&edge.source)?, target: index(&edge.target)?, weight, directed: edge.directed })
+            })
+            .collect()
+    }
+
+    // Fixed coordinates of the pinned nodes, by index in the `nodes` array
+    pub(crate) fn pins(&self) -> Result<Vec<(usize, Point)>, JsonError> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.pinned)
+            .map(|(i, node)| match (node.x, node.y) {
+                (Some(x), Some(y)) => Ok((i, Point { x, y })),
+                _ => Err(JsonError::MissingCoordinates(node.id.clone())),
+            })
+            .collect()
+    }
+
+    // Coordinates of the nodes that are not pinned but have both "x" and "y", by index in the `nodes` array
+    pub(crate) fn positions(&self) -> Vec<(usize, Point)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| !node.pinned)
+            .filter_map(|(i, node)| Some((i, Point { x: node.x?, y: node.y? })))
+            .collect()
+    }
+
+    // Width and height of every node, missing values are 0
+    pub(crate) fn sizes(&self) -> Result<Vec<Size>, JsonError> {
+        self.nodes
+            .iter()
+            .map(|node| {
+                let (width, height) = (node.width.unwrap_or(0.0), node.height.unwrap_or(0.0));
+                if width.is_finite() && width >= 0.0 && height.is_finite() && height >= 0.0 {
+                    Ok(Size { width, height })
+                } else {
+                    Err(JsonError::InvalidSize(node.id.clone()))
+                }
+            })
+            .collect()
+    }
+
+    // Copy node positions computed by a layout into the document and record how they were computed
+    pub(crate) fn set_layout(&mut self, graph: &Graph, algorithm: &str, parameters: Map<String, Value>, result: Map<String, Value>) {
+        for (record, node) in self.nodes.iter_mut().zip(graph.nodes.iter()) {
+            record.x = Some(node.position.x);
+            record.y = Some(node.position.y);
+        }
+        self.metadata = Some(LayoutMetadata { algorithm: algorithm.to_string(), parameters, result });
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("graph document is always serializable")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    #[test]
+    fn graph_document_check_if_ids_are_resolved() {
+        let document = GraphDocument::parse(
+            r#"{"version": 1, "nodes": [{"id": "a"}, {"id": 7}, {"id": "c"}], "edges": [{"source": "a", "target": 7}, {"source": 7, "target": "c"}]}"#,
+        )
+        .unwrap();
+
+        let edges = document.resolve_edges().unwrap();
+
+        assert_eq!(edges, vec![Edge::new(0, 1), Edge::new(1, 2)]);
+    }
+
+    #[test]
+    fn graph_document_check_if_errors_are_reported() {
+        let unknown = GraphDocument::parse(r#"{"version": 1, "nodes": [{"id": 0}], "edges": [{"source": 0, "target": 1}]}"#).unwrap();
+        let duplicate = GraphDocument::parse(r#"{"version": 1, "nodes": [{"id": "a"}, {"id": "a"}]}"#).unwrap();
+
+        assert_eq!(unknown.resolve_edges(), Err(JsonError::UnknownNode(NodeId::Index(1))));
+        assert_eq!(duplicate.resolve_edges(), Err(JsonError::DuplicateNode(NodeId::Name("a".to_string()))));
+        assert_eq!(GraphDocument::parse(r#"{"version": 2}"#), Err(JsonError::UnsupportedVersion(2)));
+        assert_eq!(
+            GraphDocument::parse(r#"{"version": 1, "nodes": [{"id": 0}, {"id": 1}], "edges": [{"source": 0, "target": 1, "weight": -1}]}"#)
+                .unwrap()
+                .resolve_edges(),
+            Err(JsonError::InvalidWeight(NodeId::Index(0), NodeId::Index(1)))
+        );
+        assert!(matches!(GraphDocument::parse("nodes: edges: 0-1"), Err(JsonError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn graph_document_check_pinned_nodes() {
+        let document = GraphDocument::parse(
+            r#"{"version": 1, "nodes": [{"id": "a"}, {"id": "b", "x": 3.0, "y": 4.0, "pinned": true}, {"id": "c", "x": 1.0}, {"id": "d", "x": 5.0, "y": 6.0}]}"#,
+        )
+        .unwrap();
+        let missing = GraphDocument::parse(r#"{"version": 1, "nodes": [{"id": "a", "x": 1.0, "pinned": true}]}"#).unwrap();
+
+        let pins = document.pins().unwrap();
+
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].0, 1);
+        assert_eq!((pins[0].1.x, pins[0].1.y), (3.0, 4.0));
+        assert_eq!(missing.pins().unwrap_err(), JsonError::MissingCoordinates(NodeId::Name("a".to_string())));
+        // Only unpinned nodes with both coordinates can start at them
+        let positions = document.positions();
+        assert_eq!(positions.iter().map(|&(i, p)| (i, p.x, p.y)).collect::<Vec<_>>(), vec![(3, 5.0, 6.0)]);
+    }
+
+    #[test]
+    fn graph_document_check_if_attributes_survive_round_trip() {
+        let graph_json = r#"{"version":1,"nodes":[{"id":"a","attributes":{"color":"red"}},{"id":"b"}],"edges":[{"source":"a","target":"b","weight":0.5,"directed":true,"attributes":{"latency":3}}]}"#;
+
+        let document = GraphDocument::parse(graph_json).unwrap();
+
+        assert_eq!(document.to_json(), graph_json);
+    }
+
+    #[test]
+    fn graph_document_check_if_labels_become_ids() {
+        let mut graph = Graph {
+            nodes: (0..3).map(|_| Node::new(Point { x: 0.0, y: 0.0 })).collect(),
+            edges: vec![Edge::new(0, 1), Edge::new(1, 2)],
+        };
+        graph.nodes[0].label = "alice".to_string();
+        graph.nodes[1].label = "42".to_string();
+
+        let document = GraphDocument::from_graph(&graph);
+
+        assert_eq!(
+            document.nodes.iter().map(|node| node.id.clone()).collect::<Vec<_>>(),
+            vec![NodeId::Name("alice".to_string()), NodeId::Index(42), NodeId::Index(2)]
+        );
+        assert_eq!(document.edges[0].target, NodeId::Index(42));
+    }
+}
@@ -0,0 +1,371 @@
+use crate::{Graph, Point};
+use serde::{Deserialize, Serialize};
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE LAYERED (SUGIYAMA) LAYOUT OF DIRECTED GRAPHS
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+The layered layout draws the edges of a graph pointing in one direction, which suits dependency graphs,
+workflows and hierarchies. It works in four phases:
+1. Cycle removal: edges closing a cycle (back edges of a depth-first search) are reversed for the layout.
+2. Layer assignment: every node goes to the layer after the last of its predecessors (longest path layering).
+   Edges spanning several layers are split by dummy nodes, so every edge connects two consecutive layers.
+3. Crossing reduction: the nodes of every layer are sorted by the barycenter of their neighbors in the layer
+   above (downward sweep) or below (upward sweep). The ordering with the fewest crossings is kept.
+4. Coordinate assignment: nodes move towards the mean position of their neighbors while keeping their order
+   and a minimum distance to each other.
+Undirected edges have no direction of their own, the cycle removal orients them away from where its search reached them first.
+*/
+
+// Number of barycenter sweeps of the crossing reduction
+const CROSSING_SWEEPS: usize = 24;
+
+// Number of passes of the coordinate assignment
+const COORDINATE_PASSES: usize = 8;
+
+// Direction in which the edges point in the drawing
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LayeredDirection {
+    TopDown,
+    LeftRight,
+}
+
+impl LayeredDirection {
+    pub(crate) fn from_name(name: &str) -> Option<LayeredDirection> {
+        match name {
+            "top_down" => Some(LayeredDirection::TopDown),
+            "left_right" => Some(LayeredDirection::LeftRight),
+            _ => None,
+        }
+    }
+}
+
+/*
+Orient the edges so that the graph has no directed cycle, self loops are dropped. A depth-first search (in node order)
+follows directed edges from their source to their target and undirected edges both ways, then every edge points from
+the endpoint the search finished last to the one it finished first. Directed edges thereby keep their direction
+unless they close a cycle (back edges, whose target is still on the search path, are reversed)
+and undirected edges point away from the node the search reached first.
+*/
+fn acyclic_edges(num_nodes: usize, edges: &[(usize, usize, bool)]) -> Vec<(usize, usize)> {
+    let mut neighbors = vec![Vec::new(); num_nodes];
+    for &(source, target, directed) in edges {
+        neighbors[source].push(target);
+        if !directed {
+            neighbors[target].push(source);
+        }
+    }
+
+    // finish[v] is the number of nodes the search finished before v
+    let mut finish = vec![0; num_nodes];
+    let mut finished = 0;
+    let mut visited = vec![false; num_nodes];
+    for start in 0..num_nodes {
+        if visited[start] {
+            continue;
+        }
+        let mut stack = vec![(start, 0)];
+        visited[start] = true;
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            if let Some(&neighbor) = neighbors[node].get(*next) {
+                *next += 1;
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    stack.push((neighbor, 0));
+                }
+            } else {
+                finish[node] = finished;
+                finished += 1;
+                stack.pop();
+            }
+        }
+    }
+
+    edges
+        .iter()
+        .filter(|&&(source, target, _)| source != target)
+        .map(|&(source, target, _)| if finish[source] > finish[target] { (source, target) } else { (target, source) })
+        .collect()
+}
+
+// Layer of every node: one more than the largest layer of its predecessors, nodes without predecessors are in layer 0
+fn assign_layers(num_nodes: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut successors = vec![Vec::new(); num_nodes];
+    let mut in_degree = vec![0; num_nodes];
+    for &(source, target) in edges {
+        successors[source].push(target);
+        in_degree[target] += 1;
+    }
+
+    let mut layers = vec![0; num_nodes];
+    let mut ready: Vec<usize> = (0..num_nodes).filter(|&node| in_degree[node] == 0).rev().collect();
+    while let Some(node) = ready.pop() {
+        for &successor in &successors[node] {
+            layers[successor] = layers[successor].max(layers[node] + 1);
+            in_degree[successor] -= 1;
+            if in_degree[successor] == 0 {
+                ready.push(successor);
+            }
+        }
+    }
+    layers
+}
+
+/*
+Proper layering of the graph: real nodes keep their indices, dummy nodes are appended after them.
+`above[v]` and `below[v]` are the neighbors of v in the previous and the next layer,
+`layers[l]` lists the nodes of layer l in their current order and `position[v]` is the index of v in its layer.
+*/
+#[derive(Debug)]
+struct Hierarchy {
+    layers: Vec<Vec<usize>>,
+    above: Vec<Vec<usize>>,
+    below: Vec<Vec<usize>>,
+    position: Vec<usize>,
+}
+
+impl Hierarchy {
+    fn new(num_nodes: usize, edges: &[(usize, usize)], layer_of: &[usize]) -> Hierarchy {
+        let mut layer_of = layer_of.to_vec();
+        let mut above = vec![Vec::new(); num_nodes];
+        let mut below = vec![Vec::new(); num_nodes];
+        for &(source, target) in edges {
+            // Split edges spanning several layers into a chain of dummy nodes
+            let mut previous = source;
+            for layer in layer_of[source] + 1..layer_of[target] {
+                let dummy = layer_of.len();
+                layer_of.push(layer);
+                above.push(vec![previous]);
+                below.push(Vec::new());
+                below[previous].push(dummy);
+                previous = dummy;
+            }
+            below[previous].push(target);
+            above[target].push(previous);
+        }
+
+        let num_layers = layer_of.iter().max().map_or(0, |max| max + 1);
+        let mut layers = vec![Vec::new(); num_layers];
+        let mut position = vec![0; layer_of.len()];
+        for (node, &layer) in layer_of.iter().enumerate() {
+            position[node] = layers[layer].len();
+            layers[layer].push(node);
+        }
+        Hierarchy { layers, above, below, position }
+    }
+
+    // Number of crossings between edges of consecutive layers
+    fn crossings(&self) -> usize {
+        let mut total = 0;
+        for layer in &self.layers {
+            let mut ends: Vec<(usize, usize)> = layer
+                .iter()
+                .flat_map(|&node| self.below[node].iter().map(move |&next| (node, next)))
+                .map(|(node, next)| (self.position[node], self.position[next]))
+                .collect();
+            ends.sort_unstable();
+            for (k, &(first_upper, first_lower)) in ends.iter().enumerate() {
+                total += ends[k + 1..]
+                    .iter()
+                    .filter(|&&(upper, lower)| upper > first_upper && lower < first_lower)
+                    .count();
+            }
+        }
+        total
+    }
+
+    // Sort a layer by the barycenter of the positions of the given neighbors, nodes without neighbors keep their place
+    fn sort_layer(&mut self, layer: usize, downward: bool) {
+        let mut keyed: Vec<(f64, usize)> = self.layers[layer]
+            .iter()
+            .map(|&node| {
+                let neighbors = if downward { &self.above[node] } else { &self.below[node] };
+                let key = if neighbors.is_empty() {
+                    self.position[node] as f64
+                } else {
+                    neighbors.iter().map(|&n| self.position[n] as f64).sum::<f64>() / neighbors.len() as f64
+                };
+                (key, node)
+            })
+            .collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (index, &(_, node)) in keyed.iter().enumerate() {
+            self.position[node] = index;
+            self.layers[layer][index] = node;
+        }
+    }
+
+    // Alternate downward and upward barycenter sweeps and keep the ordering with the fewest crossings
+    fn reduce_crossings(&mut self) {
+        let mut best = (self.crossings(), self.layers.clone());
+        for sweep in 0..CROSSING_SWEEPS {
+            if best.0 == 0 {
+                break;
+            }
+            if sweep % 2 == 0 {
+                for layer in 1..self.layers.len() {
+                    self.sort_layer(layer, true);
+                }
+            } else {
+                for layer in (0..self.layers.len().saturating_sub(1)).rev() {
+                    self.sort_layer(layer, false);
+                }
+            }
+            let crossings = self.crossings();
+            if crossings < best.0 {
+                best = (crossings, self.layers.clone());
+            }
+        }
+        self.layers = best.1;
+        for layer in &self.layers {
+            for (index, &node) in layer.iter().enumerate() {
+                self.position[node] = index;
+            }
+        }
+    }
+
+    /*
+    Coordinate of every node along its layer. Nodes are pulled towards the mean coordinate of their neighbors
+    in the previous (downward pass) or next layer (upward pass). Each layer is then placed twice, once pushing
+    overlapping nodes to the right and once to the left, and the two placements are averaged,
+    which keeps the order and at least `spacing` between consecutive nodes.
+    */
+    fn coordinates(&self, spacing: f64) -> Vec<f64> {
+        let mut x: Vec<f64> = self.position.iter().map(|&index| index as f64 * spacing).collect();
+        for pass in 0..COORDINATE_PASSES {
+            let downward = pass % 2 == 0;
+            let layer_order: Vec<usize> = if downward {
+                (0..self.layers.len()).collect()
+            } else {
+                (0..self.layers.len()).rev().collect()
+            };
+            for layer in layer_order {
+                let nodes = &self.layers[layer];
+                let desired: Vec<f64> = nodes
+                    .iter()
+                    .map(|&node| {
+                        let neighbors = if downward { &self.above[node] } else { &self.below[node] };
+                        if neighbors.is_empty() {
+                            x[node]
+                        } else {
+                            neighbors.iter().map(|&n| x[n]).sum::<f64>() / neighbors.len() as f64
+                        }
+                    })
+                    .collect();
+                let mut left = desired.clone();
+                for k in 1..left.len() {
+                    left[k] = left[k].max(left[k - 1] + spacing);
+                }
+                let mut right = desired;
+                for k in (0..right.len().saturating_sub(1)).rev() {
+                    right[k] = right[k].min(right[k + 1] - spacing);
+                }
+                for (k, &node) in nodes.iter().enumerate() {
+                    x[node] = (left[k] + right[k]) / 2.0;
+                }
+            }
+        }
+        let min = x.iter().copied().fold(f64::INFINITY, f64::min);
+        x.iter().map(|value| value - min).collect()
+    }
+}
+
+/*
+Compute the layered layout. `node_spacing` is the minimum distance between two nodes of a layer
+and `layer_spacing` the distance between consecutive layers. Pinned nodes are laid out like the others,
+so the spacing holds around them, translate_to_area then moves the whole layout onto the pins.
+*/
+pub(crate) fn layered_layout(graph: &mut Graph, direction: LayeredDirection, node_spacing: f64, layer_spacing: f64) -> &Graph {
+    let num_nodes = graph.nodes.len();
+    let edges: Vec<(usize, usize, bool)> = graph.edges.iter().map(|edge| (edge.source, edge.target, edge.directed)).collect();
+    let edges = acyclic_edges(num_nodes, &edges);
+    let layer_of = assign_layers(num_nodes, &edges);
+
+    let mut hierarchy = Hierarchy::new(num_nodes, &edges, &layer_of);
+    hierarchy.reduce_crossings();
+    let coordinates = hierarchy.coordinates(node_spacing);
+
+    for (i, node) in graph.nodes.iter_mut().enumerate() {
+        let along = coordinates[i];
+        let across = layer_of[i] as f64 * layer_spacing;
+        let position = match direction {
+            LayeredDirection::TopDown => Point { x: along, y: across },
+            LayeredDirection::LeftRight => Point { x: across, y: along },
+        };
+        node.position = position;
+    }
+    graph
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+
+    #[test]
+    fn acyclic_edges_check_if_cycles_are_broken() {
+        let edges = acyclic_edges(4, &[(0, 1, true), (1, 2, true), (2, 0, true), (2, 3, true), (3, 3, true)]);
+
+        assert_eq!(edges, vec![(0, 1), (1, 2), (0, 2), (2, 3)]);
+        assert_eq!(assign_layers(4, &edges), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn acyclic_edges_check_if_only_directed_edges_keep_their_direction() {
+        // The directed edges keep their direction, the undirected ones point away from node 1 where the search reached them
+        let edges = acyclic_edges(4, &[(1, 0, true), (2, 1, false), (3, 2, false), (2, 3, true)]);
+
+        assert_eq!(edges, vec![(1, 0), (1, 2), (2, 3), (2, 3)]);
+        assert_eq!(assign_layers(4, &edges), vec![1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn hierarchy_check_if_long_edges_get_dummy_nodes() {
+        let edges = [(0, 1), (1, 2), (0, 2), (0, 3)];
+        let layer_of = assign_layers(4, &edges);
+
+        let hierarchy = Hierarchy::new(4, &edges, &layer_of);
+
+        assert_eq!(layer_of, vec![0, 1, 2, 1]);
+        assert_eq!(hierarchy.layers, vec![vec![0], vec![1, 3, 4], vec![2]]);
+        assert_eq!(hierarchy.above[2], vec![1, 4]);
+    }
+
+    #[test]
+    fn reduce_crossings_check_if_crossing_is_removed() {
+        let edges = [(0, 3), (1, 2), (0, 4), (1, 5)];
+        let layer_of = assign_layers(6, &edges);
+        let mut hierarchy = Hierarchy::new(6, &edges, &layer_of);
+        assert_eq!(hierarchy.crossings(), 2);
+
+        hierarchy.reduce_crossings();
+
+        assert_eq!(hierarchy.crossings(), 0);
+    }
+
+    #[test]
+    fn layered_layout_check_direction_and_spacing() {
+        let mut top_down = graph(5, &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)]);
+        let mut left_right = graph(5, &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)]);
+        for edge in top_down.edges.iter_mut().chain(&mut left_right.edges) {
+            edge.directed = true;
+        }
+
+        layered_layout(&mut top_down, LayeredDirection::TopDown, 10.0, 30.0);
+        layered_layout(&mut left_right, LayeredDirection::LeftRight, 10.0, 30.0);
+
+        let y: Vec<f64> = top_down.nodes.iter().map(|node| node.position.y).collect();
+        assert_eq!(y, vec![0.0, 30.0, 30.0, 60.0, 90.0]);
+        assert!((top_down.nodes[1].position.x - top_down.nodes[2].position.x).abs() >= 10.0 - 1e-9);
+        // The diamond is symmetric, so its tip lies halfway between the two nodes of the middle layer
+        let middle = (top_down.nodes[1].position.x + top_down.nodes[2].position.x) / 2.0;
+        assert!((top_down.nodes[0].position.x - middle).abs() < 1e-9);
+        for (a, b) in top_down.nodes.iter().zip(&left_right.nodes) {
+            assert_eq!((a.position.x, a.position.y), (b.position.y, b.position.x));
+        }
+    }
+}
@@ -1,7 +1,43 @@
 use wasm_bindgen::prelude::*;
-use js_sys::Math;
 use std::f64::consts::PI;
 
+mod area;
+mod circular;
+mod components;
+mod cooling;
+mod distance;
+mod force_atlas2;
+mod json;
+mod layered;
+mod mds;
+mod metrics;
+mod multilevel;
+mod overlap;
+mod parser;
+mod quadtree;
+mod spectral;
+mod stress;
+mod tree;
+use area::{fit_to_area, translate_to_area, LayoutOptions};
+use circular::{circular_order, degree_rings, CircularOrdering};
+use components::layout_components;
+use cooling::{Cooling, CoolingSchedule, WARM_START_TEMPERATURE};
+use distance::{distance_terms, shortest_path_distances};
+use force_atlas2::{force_atlas2, ForceAtlas2Settings, ForceAtlas2State};
+use json::{GraphDocument, JsonError};
+use layered::{layered_layout, LayeredDirection};
+use mds::{align_to_anchors, classical_mds};
+use metrics::layout_metrics;
+use multilevel::{coarsen, prolong, REFINEMENT_TEMPERATURE};
+use overlap::remove_overlaps;
+use parser::{parse_edges, parse_layout, parse_pins, parse_positions, parse_sizes, quote_label, NodeInterner, ParseError};
+use quadtree::QuadTree;
+use spectral::spectral_positions;
+use stress::StressModel;
+use tree::{tree_layout, TreeStyle};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
 
 // CODE IN THIS FILE IS RESPONSIBLE FOR IMPLEMENTING THE GRAPH LAYOUT ALGORITHMS
 // CODE IS COMPILED TO WEBASSEMBLY AND GETS EXECUTED IN THE BROWSER
@@ -15,18 +51,113 @@ struct Point {
     y: f64,
 }
 
-// Define Node structure
+impl Point {
+    // Euclidean distance to another point
+    fn distance(self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/*
+Define Convergence structure: the final value of the function an iterative layout minimizes
+(the stress of the distance based layouts, the energy of the force-directed ones) and the number of iterations it took
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Convergence {
+    value: f64,
+    iterations: usize,
+    converged: bool,
+}
+
+impl Convergence {
+    // Combine the results of layouts of separate components: the values add up, the slowest component counts
+    fn combine(self, other: Convergence) -> Convergence {
+        Convergence {
+            value: self.value + other.value,
+            iterations: self.iterations.max(other.iterations),
+            converged: self.converged && other.converged,
+        }
+    }
+}
+
+// Define Size structure (extent of a drawn node in layout coordinates)
+#[derive(Clone, Copy, Debug)]
+struct Size {
+    width: f64,
+    height: f64,
+}
+
+/*
+Define Node structure (a pinned node stays at its fixed coordinates in every layout).
+A node with an initial position, e.g. from a previous run, starts there instead of at a random position.
+Once every node that may move has one, the iterative layouts continue from the given drawing (warm start),
+otherwise they initialize as usual and only the placed nodes start at their positions.
+The label is the id the node had in the input, nodes without a label are written out by their index.
+The size is the box the node is drawn in, centered at its position. Nodes of size 0 are drawn as points,
+only overlap removal looks at it.
+The mass is the number of input nodes a node stands for, more than 1 only for the merged nodes of the multilevel layout.
+*/
 #[derive(Debug)]
 struct Node {
+    label: String,
     position: Point,
     disp: Point,
+    pinned: Option<Point>,
+    initial: Option<Point>,
+    size: Size,
+    mass: f64,
+}
+
+impl Node {
+    fn new(position: Point) -> Node {
+        Node {
+            label: String::new(),
+            position,
+            disp: Point { x: 0.0, y: 0.0 },
+            pinned: None,
+            initial: None,
+            size: Size { width: 0.0, height: 0.0 },
+            mass: 1.0,
+        }
+    }
+
+    // Label as written in the text format
+    fn display_label(&self, index: usize) -> String {
+        if self.label.is_empty() {
+            index.to_string()
+        } else {
+            quote_label(&self.label)
+        }
+    }
+
+    fn is_pinned(&self) -> bool {
+        self.pinned.is_some()
+    }
+}
+
+// Whether every node that is not pinned has an initial position, the layouts then refine the given drawing instead of starting over
+fn is_warm_start(graph: &Graph) -> bool {
+    graph.nodes.iter().any(|node| node.initial.is_some()) && graph.nodes.iter().all(|node| node.is_pinned() || node.initial.is_some())
 }
 
-// Define Edge structure
+/*
+Define Edge structure.
+The weight is read as the length of the edge by the distance based layouts (e.g. a latency)
+and as the strength of the attraction by the force-directed ones and the spectral layout (e.g. a similarity).
+A directed edge goes from source to target, an undirected one connects both ways.
+*/
 #[derive(Debug,Clone, PartialEq)]
 struct Edge {
     source: usize,
     target: usize,
+    weight: f64,
+    directed: bool,
+}
+
+impl Edge {
+    fn new(source: usize, target: usize) -> Edge {
+        Edge { source, target, weight: 1.0, directed: false }
+    }
 }
 
 // Define Graph structure
@@ -36,56 +167,135 @@ struct Graph {
     edges: Vec<Edge>,
 }
 
-// Initialize a new Graph
-fn new_graph(num_nodes: usize, edges: Vec<Edge>) -> Graph {
-    let nodes = (0..num_nodes)
-        .map(|_| Node {
-            position: Point {
-                x: Math::random() * 100.0,
-                y: Math::random() * 100.0,
-            },
-            disp: Point { x: 0.0, y: 0.0 },
-        })
-        .collect();
-    Graph { nodes, edges }
+/*
+Small seedable pseudo random number generator (SplitMix64).
+Every source of randomness in the layouts goes through it, so the same seed always produces the same layout,
+and the layouts do not depend on the JavaScript host.
+*/
+#[derive(Debug, Clone)]
+struct Rng {
+    state: u64,
 }
 
-// Create Graph from a string
-fn from_string(graph_str: &str) -> Graph {
-    let edges: Vec<Edge> = graph_str
-        .split(',')
-        .map(|s| {
-            let nodes: Vec<usize> = s.split('-')
-                .map(|n| n.parse().unwrap())
-                .collect();
-            Edge { source: nodes[0], target: nodes[1] }
-        })
-        .collect();
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniformly distributed number in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// Uniformly distributed point in the inner drawing area
+fn random_point(options: &LayoutOptions, rng: &mut Rng) -> Point {
+    let (min, max) = options.inner();
+    Point {
+        x: min.x + rng.next_f64() * (max.x - min.x),
+        y: min.y + rng.next_f64() * (max.y - min.y),
+    }
+}
 
-    let num_nodes = edges.iter()
-        .flat_map(|e| vec![e.source, e.target])
-        .max()
-        .map_or(0, |max_node| max_node + 1);
+// Initialize a new Graph, nodes start at random positions in the drawing area
+fn new_graph(num_nodes: usize, edges: Vec<Edge>, options: &LayoutOptions, rng: &mut Rng) -> Graph {
+    let nodes = (0..num_nodes).map(|_| Node::new(random_point(options, rng))).collect();
+    Graph { nodes, edges }
+}
+
+// Pin nodes at the given coordinates, every node moves to its fixed position right away
+fn pin_nodes(graph: &mut Graph, pins: &[(usize, Point)]) {
+    for &(index, position) in pins {
+        graph.nodes[index].pinned = Some(position);
+        graph.nodes[index].position = position;
+    }
+}
 
-    new_graph(num_nodes, edges)
+// Give nodes initial positions and move them there, pinned nodes stay at their fixed coordinates
+fn place_nodes(graph: &mut Graph, positions: &[(usize, Point)]) {
+    for &(index, position) in positions {
+        let node = &mut graph.nodes[index];
+        node.initial = Some(position);
+        node.position = node.pinned.unwrap_or(position);
+    }
 }
 
 /*
-Force-Atlas2 is a popular force-directed layout algorithm used in network visualization,
-particularly within the Gephi software.
-It operates by simulating a physical system where nodes repel each other,
-similar to charged particles, while edges act like springs that attract connected nodes.
-This dual-force dynamic helps in spreading out the nodes in a visually appealing and interpretable manner,
-reducing clutter and enhancing the clarity of the network structure.
+Place all nodes that are neither pinned nor have an initial position at random positions.
+Random numbers are drawn for the other nodes as well, so pinning or placing a node does not move the others.
 */
-fn force_atlas2(graph: &mut Graph, iterations: usize, gravity: f64, scaling_ratio: f64) -> &Graph {
-    for _ in 0..iterations {
-        // Reset displacement
-        for node in &mut graph.nodes {
-            node.disp = Point { x: 0.0, y: 0.0 };
-        }
+fn randomize_positions(graph: &mut Graph, options: &LayoutOptions, rng: &mut Rng) {
+    for node in &mut graph.nodes {
+        let position = random_point(options, rng);
+        node.position = node.pinned.or(node.initial).unwrap_or(position);
+    }
+}
 
-        // Calculate repulsive forces
+// Create Graph from a string, nodes are numbered in order of first appearance and keep their ids as labels
+fn from_string(graph_str: &str, pinned_str: &str, options: &LayoutOptions, rng: &mut Rng) -> Result<Graph, ParseError> {
+    from_string_with_initial(graph_str, pinned_str, "", options, rng)
+}
+
+// Like from_string, the nodes listed in `initial_str` (see parse_positions) start at the given positions
+fn from_string_with_initial(graph_str: &str, pinned_str: &str, initial_str: &str, options: &LayoutOptions, rng: &mut Rng) -> Result<Graph, ParseError> {
+    Ok(from_string_interned(graph_str, pinned_str, initial_str, options, rng)?.0)
+}
+
+// Like from_string_with_initial, also returns the interner of the node ids to resolve the ids of further strings (e.g. parse_sizes)
+fn from_string_interned(graph_str: &str, pinned_str: &str, initial_str: &str, options: &LayoutOptions, rng: &mut Rng) -> Result<(Graph, NodeInterner), ParseError> {
+    let mut interner = NodeInterner::default();
+    let edges = parse_edges(graph_str, &mut interner)?;
+    let pins = parse_pins(pinned_str, &interner)?;
+    let initial = parse_positions(initial_str, &interner)?;
+
+    let mut graph = new_graph(interner.len(), edges, options, rng);
+    for (node, label) in graph.nodes.iter_mut().zip(interner.labels()) {
+        node.label = label.clone();
+    }
+    pin_nodes(&mut graph, &pins);
+    place_nodes(&mut graph, &initial);
+    Ok((graph, interner))
+}
+
+// Create Graph from a laid out graph as returned by the process_* functions, nodes keep their positions and labels
+fn from_layout_string(layout_str: &str) -> Result<Graph, ParseError> {
+    Ok(from_layout_string_interned(layout_str)?.0)
+}
+
+// Like from_layout_string, also returns the interner of the node ids to resolve the ids of further strings (e.g. parse_sizes)
+fn from_layout_string_interned(layout_str: &str) -> Result<(Graph, NodeInterner), ParseError> {
+    let mut interner = NodeInterner::default();
+    let (positions, edges) = parse_layout(layout_str, &mut interner)?;
+    let nodes = positions
+        .into_iter()
+        .zip(interner.labels())
+        .map(|(position, label)| {
+            let mut node = Node::new(position);
+            node.label = label.clone();
+            node
+        })
+        .collect();
+    Ok((Graph { nodes, edges }, interner))
+}
+
+/*
+Accumulate repulsive displacement between every pair of nodes.
+`repulsive_force` maps the distance between two nodes to the magnitude of the force pushing them apart,
+the force a node exerts is multiplied by its mass.
+With theta > 0 the Barnes-Hut approximation is used: distant groups of nodes are replaced by their center of mass,
+which makes an iteration O(n log n) instead of O(n^2). Theta = 0 keeps the exact pairwise computation,
+which is accurate and fast enough for small graphs. Typical values of theta are between 0.5 and 1.2.
+*/
+fn apply_repulsion<F: Fn(f64) -> f64>(graph: &mut Graph, theta: f64, repulsive_force: F) {
+    if theta <= 0.0 {
         for i in 0..graph.nodes.len() {
             for j in 0..graph.nodes.len() {
                 if i != j {
@@ -95,80 +305,88 @@ fn force_atlas2(graph: &mut Graph, iterations: usize, gravity: f64, scaling_rati
                     };
                     let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
                     if distance > 0.0 {
-                        let repulsive_force = scaling_ratio / distance;
-                        graph.nodes[i].disp.x += delta.x / distance * repulsive_force;
-                        graph.nodes[i].disp.y += delta.y / distance * repulsive_force;
+                        let force = repulsive_force(distance) * graph.nodes[j].mass;
+                        graph.nodes[i].disp.x += delta.x / distance * force;
+                        graph.nodes[i].disp.y += delta.y / distance * force;
                     }
                 }
             }
         }
+        return;
+    }
 
-        // Calculate attractive forces
-        for edge in &graph.edges {
-            let delta = Point {
-                x: graph.nodes[edge.source].position.x - graph.nodes[edge.target].position.x,
-                y: graph.nodes[edge.source].position.y - graph.nodes[edge.target].position.y,
-            };
+    let positions: Vec<Point> = graph.nodes.iter().map(|node| node.position).collect();
+    let masses: Vec<f64> = graph.nodes.iter().map(|node| node.mass).collect();
+    let tree = QuadTree::new(positions, masses);
+    for (i, node) in graph.nodes.iter_mut().enumerate() {
+        let position = node.position;
+        tree.for_each_interaction(i, theta, |other, mass, _| {
+            let delta = Point { x: position.x - other.x, y: position.y - other.y };
             let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
             if distance > 0.0 {
-                let attractive_force = distance * distance / scaling_ratio;
-                graph.nodes[edge.source].disp.x -= delta.x / distance * attractive_force;
-                graph.nodes[edge.source].disp.y -= delta.y / distance * attractive_force;
-                graph.nodes[edge.target].disp.x += delta.x / distance * attractive_force;
-                graph.nodes[edge.target].disp.y += delta.y / distance * attractive_force;
-            }
-        }
-
-        // Apply gravity
-        for node in &mut graph.nodes {
-            let distance_to_center = (node.position.x * node.position.x + node.position.y * node.position.y).sqrt();
-            node.disp.x -= node.position.x * gravity / distance_to_center;
-            node.disp.y -= node.position.y * gravity / distance_to_center;
-        }
-
-        // Update positions
-        for node in &mut graph.nodes {
-            let disp_length = (node.disp.x * node.disp.x + node.disp.y * node.disp.y).sqrt();
-            if disp_length > 0.0 {
-                node.position.x += node.disp.x / disp_length * disp_length.min(1.0);
-                node.position.y += node.disp.y / disp_length * disp_length.min(1.0);
+                let force = repulsive_force(distance) * mass;
+                node.disp.x += delta.x / distance * force;
+                node.disp.y += delta.y / distance * force;
             }
-
-            // Prevent nodes from moving too far away
-            node.position.x = node.position.x.max(0.0).min(100.0);
-            node.position.y = node.position.y.max(0.0).min(100.0);
-        }
+        });
     }
-    graph
 }
 
 /*
 A circular layout algorithm is a technique used in graph drawing where nodes are positioned on a circle,
 distributing them evenly to optimize the visual representation of the graph.
+The nodes follow the given ordering around the circle and are spread over at most `rings` concentric rings
+keyed by degree (see circular.rs), the outermost ring touches the border of the inner drawing area.
+Without `keep_aspect_ratio` the rings are ellipses filling the whole inner area.
 */
-fn circular_layout(graph: &mut Graph) -> &Graph {
-    let num_nodes = graph.nodes.len();
-    let radius = 50.0;
-    for (i, node) in graph.nodes.iter_mut().enumerate() {
-        let angle = (i as f64 / num_nodes as f64) * 2.0 * PI;
-        node.position = Point {
-            x: 50.0 + radius * angle.cos(),
-            y: 50.0 + radius * angle.sin(),
-        };
+fn circular_layout<'a>(graph: &'a mut Graph, ordering: CircularOrdering, rings: usize, options: &LayoutOptions) -> &'a Graph {
+    let order = circular_order(graph, ordering);
+    let ring_of = degree_rings(graph, rings);
+    let num_rings = ring_of.iter().max().map_or(1, |max| max + 1);
+    let (min, max) = options.inner();
+    let (center, mut radius_x, mut radius_y) = (options.center(), (max.x - min.x) / 2.0, (max.y - min.y) / 2.0);
+    if options.keep_aspect_ratio {
+        radius_x = radius_x.min(radius_y);
+        radius_y = radius_x;
+    }
+    for ring in 0..num_rings {
+        let members: Vec<usize> = order.iter().copied().filter(|&node| ring_of[node] == ring).collect();
+        let scale = (ring + 1) as f64 / num_rings as f64;
+        for (i, &member) in members.iter().enumerate() {
+            let node = &mut graph.nodes[member];
+            if node.is_pinned() {
+                continue;
+            }
+            let angle = (i as f64 / members.len() as f64) * 2.0 * PI;
+            node.position = Point {
+                x: center.x + scale * radius_x * angle.cos(),
+                y: center.y + scale * radius_y * angle.sin(),
+            };
+        }
     }
     graph
 }
 
+// The component ordering puts all components on one circle, every other ordering gives each component its own circle
+fn circular_components(graph: &mut Graph, ordering: CircularOrdering, rings: usize, options: &LayoutOptions) {
+    if ordering == CircularOrdering::Component {
+        circular_layout(graph, ordering, rings, options);
+    } else {
+        layout_components(graph, options, |component| {
+            circular_layout(component, ordering, rings, options);
+        });
+    }
+}
+
 /*
 Random layout is a simple graph layout algorithm that randomly positions nodes within a given area.
 Implemented for comparison purposes and as a starting point for more advanced layout algorithms.
 */
-fn random_layout(graph: &mut Graph) -> &Graph {
+// Random positions in the drawing area, initial positions are ignored since there is nothing to refine
+fn random_layout<'a>(graph: &'a mut Graph, options: &LayoutOptions, rng: &mut Rng) -> &'a Graph {
     for node in &mut graph.nodes {
-        node.position = Point {
-            x: Math::random() * 100.0,
-            y: Math::random() * 100.0,
-        };
+        let position = random_point(options, rng);
+        node.position = node.pinned.unwrap_or(position);
     }
     graph
 }
@@ -179,33 +397,35 @@ It treats nodes as repelling particles and edges as attractive springs,
 aiming to position nodes in such a way that all forces are balanced.
 This results in an aesthetically pleasing layout where connected nodes are drawn closer together,
 while unrelated nodes are spaced further apart.
+The ideal edge length follows from the size of the inner drawing area and gravity pulls toward its center.
+A node moves at most the ideal edge length times the temperature of `cooling` per iteration (see cooling.rs),
+the layout stops early once `cooling` finds that it converged. A warm started layout only starts at
+WARM_START_TEMPERATURE (see fruchterman_reingold_cooling), so it refines the given drawing instead of shaking it up.
 */
-fn fruchterman_reingold(graph: &mut Graph, iterations: usize, area: f64, gravity: f64) -> &Graph {
+// Cooling of a Fruchterman-Reingold run on `graph`, from the full temperature unless the graph is warm started
+fn fruchterman_reingold_cooling(graph: &Graph, schedule: CoolingSchedule, iterations: usize, tolerance: f64) -> Cooling {
+    let temperature = if is_warm_start(graph) { WARM_START_TEMPERATURE } else { 1.0 };
+    Cooling::with_temperature(schedule, iterations, tolerance, temperature)
+}
+
+fn fruchterman_reingold<'a>(graph: &'a mut Graph, iterations: usize, options: &LayoutOptions, gravity: f64, theta: f64, cooling: &mut Cooling) -> &'a Graph {
+    let (min, max) = options.inner();
+    let area = (max.x - min.x) * (max.y - min.y);
     let k = (area / graph.nodes.len() as f64).sqrt();
+    let center = options.center();
 
     for _ in 0..iterations {
+        if cooling.converged() {
+            break;
+        }
+
         // Reset displacement
         for node in &mut graph.nodes {
             node.disp = Point { x: 0.0, y: 0.0 };
         }
 
         // Calculate repulsive forces
-        for i in 0..graph.nodes.len() {
-            for j in 0..graph.nodes.len() {
-                if i != j {
-                    let delta = Point {
-                        x: graph.nodes[i].position.x - graph.nodes[j].position.x,
-                        y: graph.nodes[i].position.y - graph.nodes[j].position.y,
-                    };
-                    let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
-                    if distance > 0.0 {
-                        let repulsive_force = k * k / distance;
-                        graph.nodes[i].disp.x += delta.x / distance * repulsive_force;
-                        graph.nodes[i].disp.y += delta.y / distance * repulsive_force;
-                    }
-                }
-            }
-        }
+        apply_repulsion(graph, theta, |distance| k * k / distance);
 
         // Calculate attractive forces
         for edge in &graph.edges {
@@ -215,7 +435,7 @@ fn fruchterman_reingold(graph: &mut Graph, iterations: usize, area: f64, gravity
             };
             let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
             if distance > 0.0 {
-                let attractive_force = distance * distance / k;
+                let attractive_force = edge.weight * distance * distance / k;
                 graph.nodes[edge.source].disp.x -= delta.x / distance * attractive_force;
                 graph.nodes[edge.source].disp.y -= delta.y / distance * attractive_force;
                 graph.nodes[edge.target].disp.x += delta.x / distance * attractive_force;
@@ -225,91 +445,203 @@ fn fruchterman_reingold(graph: &mut Graph, iterations: usize, area: f64, gravity
 
         // Apply gravity
         for node in &mut graph.nodes {
-            let distance_to_center = (node.position.x * node.position.x + node.position.y * node.position.y).sqrt();
-            node.disp.x -= node.position.x * gravity * distance_to_center / k;
-            node.disp.y -= node.position.y * gravity * distance_to_center / k;
+            let delta = Point { x: node.position.x - center.x, y: node.position.y - center.y };
+            let distance_to_center = (delta.x * delta.x + delta.y * delta.y).sqrt();
+            node.disp.x -= delta.x * gravity * distance_to_center / k;
+            node.disp.y -= delta.y * gravity * distance_to_center / k;
         }
 
         // Update positions
-        for node in &mut graph.nodes {
+        let max_step = k * cooling.temperature();
+        let mut energy = 0.0;
+        for node in graph.nodes.iter_mut().filter(|node| !node.is_pinned()) {
             let disp_length = (node.disp.x * node.disp.x + node.disp.y * node.disp.y).sqrt();
+            energy += disp_length * disp_length;
             if disp_length > 0.0 {
-                node.position.x += node.disp.x / disp_length * disp_length.min(k);
-                node.position.y += node.disp.y / disp_length * disp_length.min(k);
+                node.position.x += node.disp.x / disp_length * disp_length.min(max_step);
+                node.position.y += node.disp.y / disp_length * disp_length.min(max_step);
             }
 
-            // Prevent nodes from moving too far away
-            node.position.x = node.position.x.max(0.0).min(100.0);
-            node.position.y = node.position.y.max(0.0).min(100.0);
+            // Prevent nodes from leaving the drawing area
+            if options.clamp {
+                node.position = options.clamp_point(node.position);
+            }
         }
+        cooling.cool(energy);
     }
     graph
 }
 
 /*
-The Stress Majorization Algorithm is a prominent layout algorithm used in graph drawing and network visualization.
-It aims to position the nodes of a graph in such a way that
-the geometric distances between nodes in the drawing are as close as possible
-to their theoretical distances in the graph.
+Multilevel Fruchterman-Reingold, see multilevel.rs. The graph is coarsened at most `levels` times,
+the coarsest graph gets `iterations` iterations and every finer level `refinement_iterations`.
+The coarsest level cools down from the full temperature, the finer ones only from REFINEMENT_TEMPERATURE. Returns the energy of the finest level
+and the number of iterations run on all levels together.
+A warm started graph is not coarsened, since the coarse levels would throw its positions away,
+only the graph itself is refined with `refinement_iterations` from WARM_START_TEMPERATURE. Otherwise the nodes with an initial position
+start the finest level there instead of at the position of their coarse node.
 */
-fn stress_majorization(graph: &mut Graph, iterations: usize) -> &Graph {
-    let mut distances = vec![vec![f64::INFINITY; graph.nodes.len()]; graph.nodes.len()];
-
-    // Compute shortest path distances (Floyd-Warshall Algorithm)
-    for i in 0..graph.nodes.len() {
-        distances[i][i] = 0.0;
+#[allow(clippy::too_many_arguments)]
+fn multilevel_layout(
+    graph: &mut Graph,
+    levels: usize,
+    iterations: usize,
+    refinement_iterations: usize,
+    gravity: f64,
+    theta: f64,
+    schedule: CoolingSchedule,
+    tolerance: f64,
+    options: &LayoutOptions,
+    rng: &mut Rng,
+) -> Convergence {
+    if is_warm_start(graph) {
+        let mut cooling = Cooling::with_temperature(schedule, refinement_iterations, tolerance, WARM_START_TEMPERATURE);
+        fruchterman_reingold(graph, refinement_iterations, options, gravity, theta, &mut cooling);
+        return cooling.convergence();
     }
-    for edge in &graph.edges {
-        distances[edge.source][edge.target] = 1.0;
-        distances[edge.target][edge.source] = 1.0;
-    }
-    for k in 0..graph.nodes.len() {
-        for i in 0..graph.nodes.len() {
-            for j in 0..graph.nodes.len() {
-                let new_distance = distances[i][k] + distances[k][j];
-                if new_distance < distances[i][j] {
-                    distances[i][j] = new_distance;
-                }
-            }
+    let (min, max) = options.inner();
+    let area = (max.x - min.x) * (max.y - min.y);
+    let mut hierarchy: Vec<(Graph, Vec<usize>)> = Vec::new();
+    while hierarchy.len() < levels {
+        let finer = hierarchy.last().map_or(&*graph, |(coarse, _)| coarse);
+        match coarsen(finer, rng) {
+            Some(level) => hierarchy.push(level),
+            None => break,
         }
     }
 
-    // Initialize positions randomly
-    for node in &mut graph.nodes {
-        node.position = Point {
-            x: Math::random() * 100.0,
-            y: Math::random() * 100.0,
+    let coarsest = hierarchy.last_mut().map_or(&mut *graph, |(coarse, _)| coarse);
+    let mut cooling = Cooling::new(schedule, iterations, tolerance);
+    fruchterman_reingold(coarsest, iterations, options, gravity, theta, &mut cooling);
+    let mut convergence = cooling.convergence();
+    while let Some((coarse, parent)) = hierarchy.pop() {
+        let finer = hierarchy.last_mut().map_or(&mut *graph, |(finer, _)| finer);
+        // Merged nodes start a small fraction of the ideal edge length apart
+        let jitter = 0.1 * (area / finer.nodes.len() as f64).sqrt();
+        prolong(finer, &coarse, &parent, jitter, rng);
+        let mut cooling = Cooling::with_temperature(schedule, refinement_iterations, tolerance, REFINEMENT_TEMPERATURE);
+        fruchterman_reingold(finer, refinement_iterations, options, gravity, theta, &mut cooling);
+        convergence = Convergence { iterations: convergence.iterations + cooling.convergence().iterations, ..cooling.convergence() };
+    }
+    convergence
+}
+
+/*
+Put a layout computed in its own units (e.g. from eigenvectors) among the pinned nodes, whose pins are coordinates
+in the drawing area: the layout is scaled until it spans the inner area and moved, with several pins also rotated
+and scaled, so that the pinned nodes come as close as possible to their pins (see align_to_anchors in mds.rs).
+The pinned nodes are then put on their pins. Without pins the positions are taken as they are.
+*/
+fn place_among_pins(graph: &mut Graph, mut positions: Vec<Point>, options: &LayoutOptions) {
+    let pins: Vec<(usize, Point)> = graph.nodes.iter().enumerate().filter_map(|(i, node)| Some((i, node.pinned?))).collect();
+    if !pins.is_empty() {
+        let extent = |coordinate: fn(&Point) -> f64| {
+            let values = positions.iter().map(coordinate);
+            values.clone().fold(f64::NEG_INFINITY, f64::max) - values.fold(f64::INFINITY, f64::min)
         };
+        let (min, max) = options.inner();
+        let span = extent(|p| p.x).max(extent(|p| p.y));
+        let scale = if span > 0.0 { (max.x - min.x).min(max.y - min.y) / span } else { 1.0 };
+        for position in &mut positions {
+            *position = Point { x: position.x * scale, y: position.y * scale };
+        }
+        align_to_anchors(&mut positions, &pins);
+    }
+    for (node, position) in graph.nodes.iter_mut().zip(positions) {
+        node.position = node.pinned.unwrap_or(position);
     }
+}
 
-    // Stress majorization iterations
-    for _ in 0..iterations {
-        for i in 0..graph.nodes.len() {
-            let mut new_position = Point { x: 0.0, y: 0.0 };
-            let mut weight_sum = 0.0;
+/*
+Classical (Torgerson) multidimensional scaling, see mds.rs.
+The layout is computed in one go from the eigenvectors of the double centered squared distance matrix,
+so it needs no iterations and no random numbers. Pinned nodes stay at their fixed coordinates,
+the layout of the others is placed among them (see place_among_pins).
+*/
+fn classical_multidimensional_scaling<'a>(graph: &'a mut Graph, pivots: usize, options: &LayoutOptions) -> &'a Graph {
+    if let Some(positions) = classical_mds(graph, pivots) {
+        place_among_pins(graph, positions, options);
+    }
+    graph
+}
 
-            for j in 0..graph.nodes.len() {
-                if i != j {
-                    let delta = Point {
-                        x: graph.nodes[i].position.x - graph.nodes[j].position.x,
-                        y: graph.nodes[i].position.y - graph.nodes[j].position.y,
-                    };
-                    let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
-                    let ideal_distance = distances[i][j];
-                    if distance > 0.0 && ideal_distance < f64::INFINITY {
-                        let weight = 1.0 / (ideal_distance * ideal_distance);
-                        new_position.x += weight * (graph.nodes[j].position.x + delta.x * ideal_distance / distance);
-                        new_position.y += weight * (graph.nodes[j].position.y + delta.y * ideal_distance / distance);
-                        weight_sum += weight;
-                    }
-                }
-            }
+/*
+Spectral layout, see spectral.rs. Like classical MDS it is computed in one go from eigenvectors,
+here those of the (normalized) Laplacian for its smallest non-zero eigenvalues. Pinned nodes stay at their fixed coordinates,
+the layout of the others is placed among them (see place_among_pins).
+*/
+fn spectral_layout<'a>(graph: &'a mut Graph, normalized: bool, options: &LayoutOptions) -> &'a Graph {
+    if let Some(positions) = spectral_positions(graph, normalized) {
+        place_among_pins(graph, positions, options);
+    }
+    graph
+}
+
+// Starting positions of the iterative distance based layouts
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Initialization {
+    // Deterministic classical MDS layout, random positions when it is degenerate (e.g. a graph without edges)
+    ClassicalMds,
+    Random,
+}
+
+/*
+Length in the drawing area of a graph distance of 1 for the distance based layouts. Pins are coordinates in the area,
+so with pins the target distances are scaled like those of Kamada-Kawai, until the longest one spans the inner area.
+Without pins the layouts work in graph distances and are fitted into the area afterwards, the scale is then 1.
+*/
+fn pinned_distance_scale(graph: &Graph, max_distance: f64, options: &LayoutOptions) -> f64 {
+    if max_distance <= 0.0 || !graph.nodes.iter().any(Node::is_pinned) {
+        return 1.0;
+    }
+    let (min, max) = options.inner();
+    (max.x - min.x).min(max.y - min.y) / max_distance
+}
 
-            graph.nodes[i].position.x = new_position.x / weight_sum;
-            graph.nodes[i].position.y = new_position.y / weight_sum;
+/*
+The Stress Majorization Algorithm is a prominent layout algorithm used in graph drawing and network visualization.
+It aims to position the nodes of a graph in such a way that
+the geometric distances between nodes in the drawing are as close as possible
+to their theoretical distances in the graph.
+With `pivots` > 0 only the distances to that many pivots and to the neighbors of a node are used (see distance.rs),
+which makes the layout usable for graphs with tens of thousands of nodes.
+By default the layout starts from the classical MDS layout, which makes it independent of the seed.
+A warm start skips the initialization. Nodes with an initial position in a graph that is only partly placed
+start there, the MDS layout of the others is aligned to them and to the pinned nodes (see mds.rs).
+With pins the target distances are scaled to the drawing area (see pinned_distance_scale), so the free nodes
+spread over the area around the pinned ones instead of bunching up next to them.
+The stress is minimized by SMACOF (see stress.rs), which stops early once the stress decreases by less than
+`tolerance` relative to its previous value. Returns the final stress and the number of iterations run.
+*/
+fn stress_majorization(graph: &mut Graph, iterations: usize, pivots: usize, tolerance: f64, initialization: Initialization, options: &LayoutOptions, rng: &mut Rng) -> Convergence {
+    let model = StressModel::new(graph, pivots);
+    let scale = pinned_distance_scale(graph, model.max_distance(), options);
+    let model = model.scaled(scale);
+
+    // Initialize positions
+    randomize_positions(graph, options, rng);
+    if initialization == Initialization::ClassicalMds && !is_warm_start(graph) {
+        if let Some(mut positions) = classical_mds(graph, pivots) {
+            for position in &mut positions {
+                *position = Point { x: position.x * scale, y: position.y * scale };
+            }
+            let anchors: Vec<(usize, Point)> = graph.nodes.iter().enumerate().filter_map(|(i, node)| Some((i, node.pinned.or(node.initial)?))).collect();
+            align_to_anchors(&mut positions, &anchors);
+            for (node, position) in graph.nodes.iter_mut().zip(positions) {
+                node.position = node.pinned.or(node.initial).unwrap_or(position);
+            }
         }
     }
-    graph
+
+    // SMACOF iterations, pinned nodes keep their positions
+    let mut positions: Vec<Point> = graph.nodes.iter().map(|node| node.position).collect();
+    let fixed: Vec<bool> = graph.nodes.iter().map(Node::is_pinned).collect();
+    let convergence = model.smacof(&mut positions, &fixed, iterations, tolerance);
+    for (node, position) in graph.nodes.iter_mut().zip(positions) {
+        node.position = position;
+    }
+    convergence
 }
 
 /*
@@ -320,52 +652,31 @@ MDS is often used in exploratory data analysis and data visualization to uncover
 he algorithm works by minimizing a stress function, which measures the disparity between the distances in the high-dimensional
 space and the lower-dimensional representation, ensuring that the resulting layout preserves the original structure of the data.
 */
-fn multidimensional_scaling(graph: &mut Graph, iterations: usize) -> &Graph {
-    let mut distances = vec![vec![f64::INFINITY; graph.nodes.len()]; graph.nodes.len()];
-
-    // Compute shortest path distances (Floyd-Warshall Algorithm)
-    for i in 0..graph.nodes.len() {
-        distances[i][i] = 0.0;
-    }
-    for edge in &graph.edges {
-        distances[edge.source][edge.target] = 1.0;
-        distances[edge.target][edge.source] = 1.0;
-    }
-    for k in 0..graph.nodes.len() {
-        for i in 0..graph.nodes.len() {
-            for j in 0..graph.nodes.len() {
-                let new_distance = distances[i][k] + distances[k][j];
-                if new_distance < distances[i][j] {
-                    distances[i][j] = new_distance;
-                }
-            }
-        }
-    }
+fn multidimensional_scaling<'a>(graph: &'a mut Graph, iterations: usize, pivots: usize, options: &LayoutOptions, rng: &mut Rng) -> &'a Graph {
+    // With pins the ideal distances are distances in the drawing area, see pinned_distance_scale
+    let terms = distance_terms(graph, pivots);
+    let max_distance = terms.iter().flatten().map(|&(_, distance)| distance).fold(0.0, f64::max);
+    let scale = pinned_distance_scale(graph, max_distance, options);
 
-    // Initialize positions randomly
-    for node in &mut graph.nodes {
-        node.position = Point {
-            x: Math::random() * 100.0,
-            y: Math::random() * 100.0,
-        };
-    }
+    // Initialize positions randomly, nodes with an initial position start there
+    randomize_positions(graph, options, rng);
 
     // MDS iterations
     for _ in 0..iterations {
-        for i in 0..graph.nodes.len() {
-            for j in 0..graph.nodes.len() {
-                if i != j {
-                    let delta = Point {
-                        x: graph.nodes[i].position.x - graph.nodes[j].position.x,
-                        y: graph.nodes[i].position.y - graph.nodes[j].position.y,
-                    };
-                    let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
-                    let ideal_distance = distances[i][j];
-                    if distance > 0.0 && ideal_distance < f64::INFINITY {
-                        let weight = 1.0 / (ideal_distance * ideal_distance);
-                        graph.nodes[i].position.x += weight * (graph.nodes[j].position.x - graph.nodes[i].position.x) / distance * (distance - ideal_distance);
-                        graph.nodes[i].position.y += weight * (graph.nodes[j].position.y - graph.nodes[i].position.y) / distance * (distance - ideal_distance);
-                    }
+        for (i, node_terms) in terms.iter().enumerate() {
+            if graph.nodes[i].is_pinned() {
+                continue;
+            }
+            for &(j, ideal_distance) in node_terms {
+                let delta = Point {
+                    x: graph.nodes[i].position.x - graph.nodes[j].position.x,
+                    y: graph.nodes[i].position.y - graph.nodes[j].position.y,
+                };
+                let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
+                if distance > 0.0 {
+                    let (weight, target) = (1.0 / (ideal_distance * ideal_distance), scale * ideal_distance);
+                    graph.nodes[i].position.x += weight * (graph.nodes[j].position.x - graph.nodes[i].position.x) / distance * (distance - target);
+                    graph.nodes[i].position.y += weight * (graph.nodes[j].position.y - graph.nodes[i].position.y) / distance * (distance - target);
                 }
             }
         }
@@ -374,70 +685,973 @@ fn multidimensional_scaling(graph: &mut Graph, iterations: usize) -> &Graph {
 }
 
 
-// Convert Graph to a string
-fn graph_to_string(graph: &Graph) -> String {
-    let mut graph_str = String::new();
-    graph_str.push_str("nodes: ");
-    for node in &graph.nodes {
-        graph_str.push_str(&format!("{},{};", node.position.x, node.position.y));
-    }
-    graph_str.push_str("edges: ");
-    for edge in &graph.edges {
-        graph_str.push_str(&format!("{}-{},", edge.source, edge.target));
-    }
-    graph_str
+/*
+The Kamada-Kawai algorithm models the graph as a system of springs connecting every pair of nodes.
+The natural length of a spring is proportional to the shortest path distance between its nodes
+and its stiffness is `spring_constant` divided by the square of that distance.
+The layout minimizes the total spring energy by repeatedly moving the node with the largest energy gradient
+with a Newton-Raphson step, keeping all other nodes in place. The spring constant does not move the minimum
+nor the steps, it scales the energy and the gradients, so a stiffer layout counts as converged later.
+Each iteration moves a single node, the loop stops early once every gradient is negligible.
+Pairs of nodes in different connected components are treated as if they were at the largest finite distance.
+*/
+fn kamada_kawai(graph: &mut Graph, iterations: usize, spring_constant: f64, options: &LayoutOptions) -> Convergence {
+    let distances = kamada_kawai_distances(graph);
+    kamada_kawai_with_distances(graph, &distances, iterations, spring_constant, options)
 }
 
-#[wasm_bindgen]
-extern "C" {
-    fn alert(nodes: &str);
+// Shortest path distances between all pairs of nodes, those in different components at the largest finite distance
+fn kamada_kawai_distances(graph: &Graph) -> Vec<Vec<f64>> {
+    let mut distances = shortest_path_distances(graph, false);
+    let max_distance = distances
+        .iter()
+        .flatten()
+        .filter(|d| d.is_finite())
+        .fold(0.0_f64, |max, &d| max.max(d))
+        .max(1.0);
+    for d in distances.iter_mut().flatten() {
+        if d.is_infinite() {
+            *d = max_distance;
+        }
+    }
+    distances
 }
 
-// WASM Bindgen to expose the individual functions to JavaScript
-
-#[wasm_bindgen]
-pub fn process_random(graph_str: &str) -> String {
-    let mut graph = from_string(graph_str);
-    random_layout(&mut graph);
-    graph_to_string(&graph)
-}
+// Kamada-Kawai with the distances of kamada_kawai_distances, which a LayoutSession computes once for all of its steps
+fn kamada_kawai_with_distances(graph: &mut Graph, distances: &[Vec<f64>], iterations: usize, spring_constant: f64, options: &LayoutOptions) -> Convergence {
+    let num_nodes = graph.nodes.len();
+    if num_nodes < 2 {
+        return Convergence { value: 0.0, iterations: 0, converged: true };
+    }
+    let max_distance = distances.iter().flatten().fold(0.0_f64, |max, &d| max.max(d)).max(1.0);
+
+    // Desirable length of a single edge so that the graph spans the inner drawing area
+    let (min, max) = options.inner();
+    let edge_length = (max.x - min.x).min(max.y - min.y) / max_distance;
+    let lengths: Vec<Vec<f64>> = distances
+        .iter()
+        .map(|row| row.iter().map(|d| edge_length * d).collect())
+        .collect();
+    let strengths: Vec<Vec<f64>> = distances
+        .iter()
+        .map(|row| row.iter().map(|d| if *d > 0.0 { spring_constant / (d * d) } else { 0.0 }).collect())
+        .collect();
 
-#[wasm_bindgen]
-pub fn process_force_atlas2(graph_str: &str, iterations: usize, gravity: f64, scaling_ratio: f64) -> String {
-    let mut graph = from_string(graph_str);
-    force_atlas2(&mut graph, iterations, gravity, scaling_ratio);
-    graph_to_string(&graph)
-}
+    // Partial derivative of the energy of node `m` caused by the spring to node `i`
+    let spring_gradient = |graph: &Graph, m: usize, i: usize| -> Point {
+        let dx = graph.nodes[m].position.x - graph.nodes[i].position.x;
+        let dy = graph.nodes[m].position.y - graph.nodes[i].position.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if m == i || distance == 0.0 {
+            return Point { x: 0.0, y: 0.0 };
+        }
+        Point {
+            x: strengths[m][i] * (dx - lengths[m][i] * dx / distance),
+            y: strengths[m][i] * (dy - lengths[m][i] * dy / distance),
+        }
+    };
 
-#[wasm_bindgen]
-pub fn process_circular(graph_str: &str) -> String {
-    let mut graph = from_string(graph_str);
-    circular_layout(&mut graph);
-    graph_to_string(&graph)
-}
+    let mut gradients = vec![Point { x: 0.0, y: 0.0 }; num_nodes];
+    for (m, gradient) in gradients.iter_mut().enumerate() {
+        for i in 0..num_nodes {
+            let g = spring_gradient(graph, m, i);
+            gradient.x += g.x;
+            gradient.y += g.y;
+        }
+    }
 
-#[wasm_bindgen]
-pub fn process_fruchterman_reingold(graph_str: &str, iterations: usize, gravity: f64) -> String {
-    let mut graph = from_string(graph_str);
-    fruchterman_reingold(&mut graph, iterations, 10000.0, gravity); // Adjust area parameter as needed
-    graph_to_string(&graph)
-}
+    let (mut run, mut converged) = (0, false);
+    for _ in 0..iterations {
+        // Pick the free node with the largest energy gradient
+        let (m, delta) = gradients
+            .iter()
+            .map(|g| (g.x * g.x + g.y * g.y).sqrt())
+            .enumerate()
+            .filter(|&(i, _)| !graph.nodes[i].is_pinned())
+            .fold((0, 0.0), |best, (i, delta)| if delta > best.1 { (i, delta) } else { best });
+        if delta < 1e-6 {
+            converged = true;
+            break;
+        }
+        run += 1;
+
+        // Second derivatives of the energy of node m
+        let (mut dxx, mut dxy, mut dyy) = (0.0, 0.0, 0.0);
+        for i in 0..num_nodes {
+            let dx = graph.nodes[m].position.x - graph.nodes[i].position.x;
+            let dy = graph.nodes[m].position.y - graph.nodes[i].position.y;
+            let distance = (dx * dx + dy * dy).sqrt();
+            if i == m || distance == 0.0 {
+                continue;
+            }
+            let cube = distance * distance * distance;
+            dxx += strengths[m][i] * (1.0 - lengths[m][i] * dy * dy / cube);
+            dxy += strengths[m][i] * lengths[m][i] * dx * dy / cube;
+            dyy += strengths[m][i] * (1.0 - lengths[m][i] * dx * dx / cube);
+        }
+        let determinant = dxx * dyy - dxy * dxy;
+        if determinant.abs() < 1e-12 {
+            break;
+        }
+        let step = Point {
+            x: (-gradients[m].x * dyy + gradients[m].y * dxy) / determinant,
+            y: (gradients[m].x * dxy - gradients[m].y * dxx) / determinant,
+        };
+
+        // Move node m and update the gradients it influences
+        for (i, gradient) in gradients.iter_mut().enumerate() {
+            let g = spring_gradient(graph, i, m);
+            gradient.x -= g.x;
+            gradient.y -= g.y;
+        }
+        graph.nodes[m].position.x += step.x;
+        graph.nodes[m].position.y += step.y;
+        gradients[m] = Point { x: 0.0, y: 0.0 };
+        for i in 0..num_nodes {
+            let g = spring_gradient(graph, i, m);
+            gradients[i].x += g.x;
+            gradients[i].y += g.y;
+            let g = spring_gradient(graph, m, i);
+            gradients[m].x += g.x;
+            gradients[m].y += g.y;
+        }
+    }
+
+    // Energy of the springs, each pair of nodes counted once
+    let mut energy = 0.0;
+    for i in 0..num_nodes {
+        for j in i + 1..num_nodes {
+            let stretch = graph.nodes[i].position.distance(graph.nodes[j].position) - lengths[i][j];
+            energy += strengths[i][j] * stretch * stretch / 2.0;
+        }
+    }
+    Convergence { value: energy, iterations: run, converged }
+}
+
+
+/*
+Parameters of the layout algorithms accepted by the JSON entry points, missing values fall back to the defaults of the viewer.
+Unknown keys are rejected, so a misspelled parameter is an error rather than silently replaced by its default.
+*/
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+struct LayoutParameters {
+    iterations: usize,
+    gravity: f64,
+    scaling_ratio: f64,
+    theta: f64,
+    spring_constant: f64,
+    // Options of ForceAtlas2, see force_atlas2.rs
+    lin_log: bool,
+    dissuade_hubs: bool,
+    strong_gravity: bool,
+    edge_weight_influence: f64,
+    prevent_overlap: bool,
+    jitter_tolerance: f64,
+    // Number of pivots of the distance based layouts, 0 uses the distances between all pairs of nodes
+    pivots: usize,
+    // Stress majorization stops once the stress decreases by less than this fraction in an iteration,
+    // the force-directed layouts once their energy falls below this fraction of its first value (see cooling.rs)
+    tolerance: f64,
+    // Temperature of Fruchterman-Reingold and the multilevel layout over the iterations
+    cooling: CoolingSchedule,
+    // Starting positions of stress majorization
+    initialization: Initialization,
+    // Direction of the edges of the layered layout
+    direction: LayeredDirection,
+    // Distances between nodes of a layer and between layers of the layered and the tree layouts
+    node_spacing: f64,
+    layer_spacing: f64,
+    // Id of the root of the tree layouts, empty roots every component at its center
+    root: String,
+    // Whether the spectral layout uses the normalized Laplacian
+    normalized: bool,
+    // Maximum number of coarsening levels of the multilevel layout and iterations spent on each finer level
+    levels: usize,
+    refinement_iterations: usize,
+    // Order of the nodes around the circle and number of concentric rings of the circular layout
+    ordering: CircularOrdering,
+    rings: usize,
+    // Whether the nodes are moved apart after the layout until their boxes (width and height in the document) do not overlap
+    remove_overlaps: bool,
+    // Whether nodes with "x" and "y" in the document start there, so the iterative layouts refine a previous drawing
+    warm_start: bool,
+    // Drawing area the layout is fitted into, see LayoutOptions in area.rs
+    // (not flattened from a LayoutOptions, since serde cannot deny unknown fields next to a flattened struct)
+    width: f64,
+    height: f64,
+    margin: f64,
+    keep_aspect_ratio: bool,
+    clamp: bool,
+    seed: u32,
+}
+
+impl Default for LayoutParameters {
+    fn default() -> LayoutParameters {
+        let area = LayoutOptions::default();
+        LayoutParameters {
+            iterations: 1000,
+            gravity: 0.1,
+            scaling_ratio: 10.0,
+            theta: 0.5,
+            spring_constant: 1.0,
+            lin_log: false,
+            dissuade_hubs: false,
+            strong_gravity: false,
+            edge_weight_influence: 1.0,
+            prevent_overlap: false,
+            jitter_tolerance: 1.0,
+            pivots: 0,
+            tolerance: 1e-4,
+            cooling: CoolingSchedule::Adaptive,
+            initialization: Initialization::ClassicalMds,
+            direction: LayeredDirection::TopDown,
+            node_spacing: 10.0,
+            layer_spacing: 20.0,
+            root: String::new(),
+            normalized: false,
+            levels: 10,
+            refinement_iterations: 50,
+            ordering: CircularOrdering::Input,
+            rings: 1,
+            remove_overlaps: false,
+            warm_start: false,
+            width: area.width,
+            height: area.height,
+            margin: area.margin,
+            keep_aspect_ratio: area.keep_aspect_ratio,
+            clamp: area.clamp,
+            seed: 1,
+        }
+    }
+}
+
+impl LayoutParameters {
+    // Default parameters that lay out into the given drawing area
+    fn with_options(options: &LayoutOptions) -> LayoutParameters {
+        LayoutParameters {
+            width: options.width,
+            height: options.height,
+            margin: options.margin,
+            keep_aspect_ratio: options.keep_aspect_ratio,
+            clamp: options.clamp,
+            ..LayoutParameters::default()
+        }
+    }
+
+    fn options(&self) -> LayoutOptions {
+        LayoutOptions { width: self.width, height: self.height, margin: self.margin, keep_aspect_ratio: self.keep_aspect_ratio, clamp: self.clamp }
+    }
+
+    fn force_atlas2_settings(&self) -> ForceAtlas2Settings {
+        ForceAtlas2Settings {
+            gravity: self.gravity,
+            scaling_ratio: self.scaling_ratio,
+            theta: self.theta,
+            lin_log: self.lin_log,
+            dissuade_hubs: self.dissuade_hubs,
+            strong_gravity: self.strong_gravity,
+            edge_weight_influence: self.edge_weight_influence,
+            prevent_overlap: self.prevent_overlap,
+            jitter_tolerance: self.jitter_tolerance,
+        }
+    }
+}
+
+// Root of the tree layouts given by its label: Some(None) lets the layout choose for an empty label, None means there is no such node
+fn tree_root(graph: &Graph, root: &str) -> Option<Option<usize>> {
+    if root.is_empty() {
+        return Some(None);
+    }
+    graph.nodes.iter().position(|node| node.label == root).map(Some)
+}
+
+// Parameters a layout actually used and what it reports about its result (e.g. the final stress), if anything
+type LayoutOutcome = (Map<String, Value>, Map<String, Value>);
+
+// `measure` names the minimized value in the result, "stress" or "energy"
+fn report_convergence(result: &mut Map<String, Value>, measure: &str, convergence: Convergence) {
+    result.insert(measure.to_string(), json!(convergence.value));
+    result.insert("iterations".to_string(), json!(convergence.iterations));
+    result.insert("converged".to_string(), json!(convergence.converged));
+}
+
+// Run the algorithm with the given name and fit the result into the drawing area
+fn run_layout(graph: &mut Graph, algorithm: &str, parameters: &LayoutParameters, rng: &mut Rng) -> Result<LayoutOutcome, JsonError> {
+    let p = parameters;
+    let options = &p.options();
+    let mut result = Map::new();
+    let used = match algorithm {
+        "random" => {
+            random_layout(graph, options, rng);
+            json!({})
+        }
+        "circular" => {
+            circular_components(graph, p.ordering, p.rings, options);
+            json!({ "ordering": p.ordering, "rings": p.rings })
+        }
+        "force_atlas2" => {
+            let settings = p.force_atlas2_settings();
+            let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+            layout_components(graph, options, |component| {
+                let mut state = ForceAtlas2State::new(component.nodes.len());
+                let mut cooling = Cooling::new(p.cooling, p.iterations, p.tolerance);
+                force_atlas2(component, p.iterations, &settings, options, &mut state, &mut cooling);
+                convergence = convergence.combine(cooling.convergence());
+            });
+            report_convergence(&mut result, "energy", convergence);
+            json!({
+                "iterations": p.iterations,
+                "gravity": p.gravity,
+                "scaling_ratio": p.scaling_ratio,
+                "theta": p.theta,
+                "lin_log": p.lin_log,
+                "dissuade_hubs": p.dissuade_hubs,
+                "strong_gravity": p.strong_gravity,
+                "edge_weight_influence": p.edge_weight_influence,
+                "prevent_overlap": p.prevent_overlap,
+                "jitter_tolerance": p.jitter_tolerance,
+                "cooling": p.cooling,
+                "tolerance": p.tolerance
+            })
+        }
+        "fruchterman_reingold" => {
+            let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+            layout_components(graph, options, |component| {
+                let mut cooling = fruchterman_reingold_cooling(component, p.cooling, p.iterations, p.tolerance);
+                fruchterman_reingold(component, p.iterations, options, p.gravity, p.theta, &mut cooling);
+                convergence = convergence.combine(cooling.convergence());
+            });
+            report_convergence(&mut result, "energy", convergence);
+            json!({ "iterations": p.iterations, "gravity": p.gravity, "theta": p.theta, "cooling": p.cooling, "tolerance": p.tolerance })
+        }
+        "multilevel" => {
+            let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+            layout_components(graph, options, |component| {
+                let component_convergence =
+                    multilevel_layout(component, p.levels, p.iterations, p.refinement_iterations, p.gravity, p.theta, p.cooling, p.tolerance, options, rng);
+                convergence = convergence.combine(component_convergence);
+            });
+            report_convergence(&mut result, "energy", convergence);
+            json!({
+                "levels": p.levels,
+                "iterations": p.iterations,
+                "refinement_iterations": p.refinement_iterations,
+                "gravity": p.gravity,
+                "theta": p.theta,
+                "cooling": p.cooling,
+                "tolerance": p.tolerance
+            })
+        }
+        "stress_majorization" => {
+            let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+            layout_components(graph, options, |component| {
+                let component_convergence = stress_majorization(component, p.iterations, p.pivots, p.tolerance, p.initialization, options, rng);
+                convergence = convergence.combine(component_convergence);
+            });
+            report_convergence(&mut result, "stress", convergence);
+            json!({ "iterations": p.iterations, "pivots": p.pivots, "tolerance": p.tolerance, "initialization": p.initialization })
+        }
+        "classical_mds" => {
+            layout_components(graph, options, |component| {
+                classical_multidimensional_scaling(component, p.pivots, options);
+            });
+            json!({ "pivots": p.pivots })
+        }
+        "multidimensional_scaling" => {
+            layout_components(graph, options, |component| {
+                multidimensional_scaling(component, p.iterations, p.pivots, options, rng);
+            });
+            json!({ "iterations": p.iterations, "pivots": p.pivots })
+        }
+        "kamada_kawai" => {
+            let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+            layout_components(graph, options, |component| {
+                convergence = convergence.combine(kamada_kawai(component, p.iterations, p.spring_constant, options));
+            });
+            report_convergence(&mut result, "energy", convergence);
+            json!({ "iterations": p.iterations, "spring_constant": p.spring_constant })
+        }
+        "layered" => {
+            layered_layout(graph, p.direction, p.node_spacing, p.layer_spacing);
+            json!({ "direction": p.direction, "node_spacing": p.node_spacing, "layer_spacing": p.layer_spacing })
+        }
+        "spectral" => {
+            layout_components(graph, options, |component| {
+                spectral_layout(component, p.normalized, options);
+            });
+            json!({ "normalized": p.normalized })
+        }
+        "tree" | "radial_tree" => {
+            let style = if algorithm == "tree" { TreeStyle::Tidy } else { TreeStyle::Radial };
+            let root = tree_root(graph, &p.root).ok_or_else(|| JsonError::UnknownRoot(p.root.clone()))?;
+            tree_layout(graph, style, root, p.node_spacing, p.layer_spacing);
+            json!({ "root": p.root, "node_spacing": p.node_spacing, "layer_spacing": p.layer_spacing })
+        }
+        _ => return Err(JsonError::UnknownAlgorithm(algorithm.to_string())),
+    };
+    if matches!(algorithm, "layered" | "tree" | "radial_tree") {
+        translate_to_area(graph, options);
+    } else {
+        fit_to_area(graph, options);
+    }
+    match used {
+        Value::Object(map) => Ok((map, result)),
+        _ => unreachable!("layout parameters are always a JSON object"),
+    }
+}
+
+// Convert Graph to a string, nodes are written as "label:x,y;" and edges refer to nodes by label
+fn graph_to_string(graph: &Graph) -> String {
+    let labels: Vec<String> = graph.nodes.iter().enumerate().map(|(i, node)| node.display_label(i)).collect();
+    let mut graph_str = String::new();
+    graph_str.push_str("nodes: ");
+    for (node, label) in graph.nodes.iter().zip(&labels) {
+        graph_str.push_str(&format!("{}:{},{};", label, node.position.x, node.position.y));
+    }
+    graph_str.push_str("edges: ");
+    for edge in &graph.edges {
+        let separator = if edge.directed { "->" } else { "-" };
+        let (source, target) = (&labels[edge.source], &labels[edge.target]);
+        if edge.weight == 1.0 {
+            graph_str.push_str(&format!("{}{}{},", source, separator, target));
+        } else {
+            graph_str.push_str(&format!("{}{}{}:{},", source, separator, target, edge.weight));
+        }
+    }
+    graph_str
+}
+
+#[wasm_bindgen]
+extern "C" {
+    fn alert(nodes: &str);
+}
+
+// WASM Bindgen to expose the individual functions to JavaScript
+// Every layout is fitted into the drawing area given by `options` (see area.rs), pins are coordinates in that area
+// The layered and tree layouts keep their spacings and are only moved to the top left corner of the area
+// The iterative layouts continue from the positions in `initial_str`, given as "id:x,y;..." or as the output of a previous run
+
+/*
+Layout returned by the iterative process_* functions together with what it reports about its convergence:
+`layout` is the laid out graph in the same format as the other process_* functions and `result` a JSON object
+such as {"stress": 0.28, "iterations": 12, "converged": true}, the "result" of process_json for the same layout.
+*/
+#[wasm_bindgen]
+pub struct LayoutResult {
+    layout: String,
+    result: String,
+}
+
+#[wasm_bindgen]
+impl LayoutResult {
+    #[wasm_bindgen(getter)]
+    pub fn layout(&self) -> String {
+        self.layout.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn result(&self) -> String {
+        self.result.clone()
+    }
+}
+
+impl LayoutResult {
+    fn new(graph: &Graph, measure: &str, convergence: Convergence) -> LayoutResult {
+        let mut result = Map::new();
+        report_convergence(&mut result, measure, convergence);
+        LayoutResult { layout: graph_to_string(graph), result: Value::Object(result).to_string() }
+    }
+}
+
+#[wasm_bindgen]
+pub fn process_random(graph_str: &str, pinned_str: &str, options: &LayoutOptions, seed: u32) -> Result<String, JsValue> {
+    let mut rng = Rng::new(seed as u64);
+    let mut graph = from_string(graph_str, pinned_str, options, &mut rng)?;
+    random_layout(&mut graph, options, &mut rng);
+    fit_to_area(&mut graph, options);
+    Ok(graph_to_string(&graph))
+}
+
+/*
+`settings` are the options of Gephi (see force_atlas2.rs). `sizes_str` gives the width and height of nodes as
+"id:width,height;...", which only matter with `prevent_overlap`, nodes without one are points.
+`cooling` and `tolerance` are those of process_fruchterman_reingold.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn process_force_atlas2(
+    graph_str: &str,
+    pinned_str: &str,
+    initial_str: &str,
+    options: &LayoutOptions,
+    settings: &ForceAtlas2Settings,
+    sizes_str: &str,
+    iterations: usize,
+    cooling: &str,
+    tolerance: f64,
+    seed: u32,
+) -> Result<LayoutResult, JsValue> {
+    let schedule = cooling_schedule(cooling)?;
+    let mut rng = Rng::new(seed as u64);
+    let (mut graph, interner) = from_string_interned(graph_str, pinned_str, initial_str, options, &mut rng)?;
+    for (index, size) in parse_sizes(sizes_str, &interner)? {
+        graph.nodes[index].size = size;
+    }
+    let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+    layout_components(&mut graph, options, |component| {
+        let mut state = ForceAtlas2State::new(component.nodes.len());
+        let mut cooling = Cooling::new(schedule, iterations, tolerance);
+        force_atlas2(component, iterations, settings, options, &mut state, &mut cooling);
+        convergence = convergence.combine(cooling.convergence());
+    });
+    fit_to_area(&mut graph, options);
+    Ok(LayoutResult::new(&graph, "energy", convergence))
+}
+
+// `ordering` is "input", "component", "community" or "crossing_reduction", `rings` is the maximum number of concentric rings
+#[wasm_bindgen]
+pub fn process_circular(graph_str: &str, pinned_str: &str, options: &LayoutOptions, ordering: &str, rings: usize) -> Result<String, JsValue> {
+    let ordering = CircularOrdering::from_name(ordering).ok_or_else(|| {
+        JsValue::from(js_sys::Error::new(&format!("unknown circular ordering '{}'", ordering)))
+    })?;
+    let mut graph = from_string(graph_str, pinned_str, options, &mut Rng::new(0))?;
+    circular_components(&mut graph, ordering, rings, options);
+    fit_to_area(&mut graph, options);
+    Ok(graph_to_string(&graph))
+}
+
+fn cooling_schedule(name: &str) -> Result<CoolingSchedule, JsValue> {
+    CoolingSchedule::from_name(name).ok_or_else(|| JsValue::from(js_sys::Error::new(&format!("unknown cooling schedule '{}'", name))))
+}
+
+// `cooling` is "linear", "exponential" or "adaptive", the layout stops early once its energy falls below `tolerance` times its first value
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn process_fruchterman_reingold(
+    graph_str: &str,
+    pinned_str: &str,
+    initial_str: &str,
+    options: &LayoutOptions,
+    iterations: usize,
+    gravity: f64,
+    theta: f64,
+    cooling: &str,
+    tolerance: f64,
+    seed: u32,
+) -> Result<LayoutResult, JsValue> {
+    let schedule = cooling_schedule(cooling)?;
+    let mut rng = Rng::new(seed as u64);
+    let mut graph = from_string_with_initial(graph_str, pinned_str, initial_str, options, &mut rng)?;
+    let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+    layout_components(&mut graph, options, |component| {
+        let mut cooling = fruchterman_reingold_cooling(component, schedule, iterations, tolerance);
+        fruchterman_reingold(component, iterations, options, gravity, theta, &mut cooling);
+        convergence = convergence.combine(cooling.convergence());
+    });
+    fit_to_area(&mut graph, options);
+    Ok(LayoutResult::new(&graph, "energy", convergence))
+}
+
+// `iterations` are spent on the coarsest graph and `refinement_iterations` on each of the at most `levels` finer ones,
+// `cooling` and `tolerance` are those of process_fruchterman_reingold
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn process_multilevel(
+    graph_str: &str,
+    pinned_str: &str,
+    initial_str: &str,
+    options: &LayoutOptions,
+    levels: usize,
+    iterations: usize,
+    refinement_iterations: usize,
+    gravity: f64,
+    theta: f64,
+    cooling: &str,
+    tolerance: f64,
+    seed: u32,
+) -> Result<LayoutResult, JsValue> {
+    let schedule = cooling_schedule(cooling)?;
+    let mut rng = Rng::new(seed as u64);
+    let mut graph = from_string_with_initial(graph_str, pinned_str, initial_str, options, &mut rng)?;
+    let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+    layout_components(&mut graph, options, |component| {
+        let component_convergence =
+            multilevel_layout(component, levels, iterations, refinement_iterations, gravity, theta, schedule, tolerance, options, &mut rng);
+        convergence = convergence.combine(component_convergence);
+    });
+    fit_to_area(&mut graph, options);
+    Ok(LayoutResult::new(&graph, "energy", convergence))
+}
+
+// The layout stops once the stress decreases by less than `tolerance` relative to its previous value
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn process_stress_majorization(graph_str: &str, pinned_str: &str, initial_str: &str, options: &LayoutOptions, iterations: usize, pivots: usize, tolerance: f64, seed: u32) -> Result<LayoutResult, JsValue> {
+    let mut rng = Rng::new(seed as u64);
+    let mut graph = from_string_with_initial(graph_str, pinned_str, initial_str, options, &mut rng)?;
+    let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+    layout_components(&mut graph, options, |component| {
+        let component_convergence = stress_majorization(component, iterations, pivots, tolerance, Initialization::ClassicalMds, options, &mut rng);
+        convergence = convergence.combine(component_convergence);
+    });
+    fit_to_area(&mut graph, options);
+    Ok(LayoutResult::new(&graph, "stress", convergence))
+}
+
+#[wasm_bindgen]
+pub fn process_classical_mds(graph_str: &str, pinned_str: &str, options: &LayoutOptions, pivots: usize) -> Result<String, JsValue> {
+    let mut graph = from_string(graph_str, pinned_str, options, &mut Rng::new(0))?;
+    layout_components(&mut graph, options, |component| {
+        classical_multidimensional_scaling(component, pivots, options);
+    });
+    fit_to_area(&mut graph, options);
+    Ok(graph_to_string(&graph))
+}
+
+#[wasm_bindgen]
+pub fn process_spectral(graph_str: &str, pinned_str: &str, options: &LayoutOptions, normalized: bool) -> Result<String, JsValue> {
+    let mut graph = from_string(graph_str, pinned_str, options, &mut Rng::new(0))?;
+    layout_components(&mut graph, options, |component| {
+        spectral_layout(component, normalized, options);
+    });
+    fit_to_area(&mut graph, options);
+    Ok(graph_to_string(&graph))
+}
+
+#[wasm_bindgen]
+pub fn process_multidimensional_scaling(graph_str: &str, pinned_str: &str, initial_str: &str, options: &LayoutOptions, iterations: usize, pivots: usize, seed: u32) -> Result<String, JsValue> {
+    let mut rng = Rng::new(seed as u64);
+    let mut graph = from_string_with_initial(graph_str, pinned_str, initial_str, options, &mut rng)?;
+    layout_components(&mut graph, options, |component| {
+        multidimensional_scaling(component, iterations, pivots, options, &mut rng);
+    });
+    fit_to_area(&mut graph, options);
+    Ok(graph_to_string(&graph))
+}
+
+// `spring_constant` scales the stiffness of every spring and so the reported energy (see kamada_kawai)
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn process_kamada_kawai(graph_str: &str, pinned_str: &str, initial_str: &str, options: &LayoutOptions, iterations: usize, spring_constant: f64, seed: u32) -> Result<LayoutResult, JsValue> {
+    let mut rng = Rng::new(seed as u64);
+    let mut graph = from_string_with_initial(graph_str, pinned_str, initial_str, options, &mut rng)?;
+    let mut convergence = Convergence { value: 0.0, iterations: 0, converged: true };
+    layout_components(&mut graph, options, |component| {
+        convergence = convergence.combine(kamada_kawai(component, iterations, spring_constant, options));
+    });
+    fit_to_area(&mut graph, options);
+    Ok(LayoutResult::new(&graph, "energy", convergence))
+}
+
+// `direction` is "top_down" or "left_right"
+#[wasm_bindgen]
+pub fn process_layered(graph_str: &str, pinned_str: &str, options: &LayoutOptions, direction: &str, node_spacing: f64, layer_spacing: f64) -> Result<String, JsValue> {
+    let direction = LayeredDirection::from_name(direction).ok_or_else(|| {
+        JsValue::from(js_sys::Error::new(&format!("unknown layered layout direction '{}'", direction)))
+    })?;
+    let mut graph = from_string(graph_str, pinned_str, options, &mut Rng::new(0))?;
+    layered_layout(&mut graph, direction, node_spacing, layer_spacing);
+    translate_to_area(&mut graph, options);
+    Ok(graph_to_string(&graph))
+}
+
+// Shared by process_tree and process_radial_tree, an empty `root` roots every component at its center
+fn process_tree_style(graph_str: &str, pinned_str: &str, options: &LayoutOptions, style: TreeStyle, root: &str, node_spacing: f64, layer_spacing: f64) -> Result<String, JsValue> {
+    let mut graph = from_string(graph_str, pinned_str, options, &mut Rng::new(0))?;
+    let root = tree_root(&graph, root)
+        .ok_or_else(|| JsValue::from(js_sys::Error::new(&format!("unknown root node '{}'", root))))?;
+    tree_layout(&mut graph, style, root, node_spacing, layer_spacing);
+    translate_to_area(&mut graph, options);
+    Ok(graph_to_string(&graph))
+}
+
+#[wasm_bindgen]
+pub fn process_tree(graph_str: &str, pinned_str: &str, options: &LayoutOptions, root: &str, node_spacing: f64, layer_spacing: f64) -> Result<String, JsValue> {
+    process_tree_style(graph_str, pinned_str, options, TreeStyle::Tidy, root, node_spacing, layer_spacing)
+}
+
+#[wasm_bindgen]
+pub fn process_radial_tree(graph_str: &str, pinned_str: &str, options: &LayoutOptions, root: &str, node_spacing: f64, layer_spacing: f64) -> Result<String, JsValue> {
+    process_tree_style(graph_str, pinned_str, options, TreeStyle::Radial, root, node_spacing, layer_spacing)
+}
+
+
+// An empty string gives the default parameters
+fn parse_parameters(parameters_json: &str) -> Result<LayoutParameters, JsonError> {
+    if parameters_json.trim().is_empty() {
+        return Ok(LayoutParameters::default());
+    }
+    serde_json::from_str(parameters_json).map_err(|error| JsonError::InvalidJson(error.to_string()))
+}
+
+/*
+JSON counterparts of the process_* functions.
+`graph_json` is a document in the JSON interchange format (see json.rs), `algorithm` is the name of the layout
+(e.g. "force_atlas2") and `parameters_json` an optional JSON object with its parameters,
+including the drawing area as "width", "height", "margin", "keep_aspect_ratio" and "clamp", unknown keys are an error.
+The same document is returned with node positions and layout metadata filled in.
+*/
+#[wasm_bindgen]
+pub fn process_json(graph_json: &str, algorithm: &str, parameters_json: &str) -> Result<String, JsValue> {
+    let mut document = GraphDocument::parse(graph_json)?;
+    let parameters = parse_parameters(parameters_json)?;
+    let options = parameters.options();
+    if !options.is_valid() {
+        return Err(JsonError::InvalidArea.into());
+    }
+
+    let edges = document.resolve_edges()?;
+    let mut rng = Rng::new(parameters.seed as u64);
+    let mut graph = new_graph(document.nodes.len(), edges, &options, &mut rng);
+    for ((node, record), size) in graph.nodes.iter_mut().zip(&document.nodes).zip(document.sizes()?) {
+        node.label = record.id.to_string();
+        node.size = size;
+    }
+    pin_nodes(&mut graph, &document.pins()?);
+    if parameters.warm_start {
+        place_nodes(&mut graph, &document.positions());
+    }
+    let (mut used, mut result) = run_layout(&mut graph, algorithm, &parameters, &mut rng)?;
+    if parameters.remove_overlaps {
+        result.insert("overlaps".to_string(), json!(remove_overlaps(&mut graph)));
+        used.insert("remove_overlaps".to_string(), json!(true));
+    }
+    if parameters.warm_start {
+        used.insert("warm_start".to_string(), json!(true));
+    }
+    if let Value::Object(area) = json!(options) {
+        used.extend(area);
+    }
+    used.insert("seed".to_string(), json!(parameters.seed));
+    document.set_layout(&graph, algorithm, used, result);
+    Ok(document.to_json())
+}
+
+/*
+Move the nodes of a laid out graph as returned by the process_* functions apart until they do not overlap (see overlap.rs).
+`sizes_str` gives the width and height of nodes as "id:width,height;...", nodes without one are points.
+Pinned nodes, given as "id:x,y;..." in the coordinates of the layout, keep their positions.
+*/
+#[wasm_bindgen]
+pub fn process_remove_overlaps(layout_str: &str, sizes_str: &str, pinned_str: &str) -> Result<String, JsValue> {
+    let (mut graph, interner) = from_layout_string_interned(layout_str)?;
+    for (index, size) in parse_sizes(sizes_str, &interner)? {
+        graph.nodes[index].size = size;
+    }
+    let pins = parse_pins(pinned_str, &interner)?;
+    pin_nodes(&mut graph, &pins);
+    remove_overlaps(&mut graph);
+    Ok(graph_to_string(&graph))
+}
 
+/*
+Quality metrics (see metrics.rs) of a laid out graph as returned by the process_* functions, as a JSON object
+such as {"edge_crossings": 0, "normalized_stress": 0.04, ...}. `node_radius` is in the coordinates of the layout.
+*/
 #[wasm_bindgen]
-pub fn process_stress_majorization(graph_str: &str, iterations: usize) -> String {
-    let mut graph = from_string(graph_str);
-    stress_majorization(&mut graph, iterations);
-    graph_to_string(&graph)
+pub fn compute_metrics(layout_str: &str, node_radius: f64) -> Result<String, JsValue> {
+    let graph = from_layout_string(layout_str)?;
+    Ok(serde_json::to_string(&layout_metrics(&graph, node_radius)).expect("layout metrics are always serializable"))
 }
 
+// Convert a graph in the legacy edge list format into the JSON interchange format
 #[wasm_bindgen]
-pub fn process_multidimensional_scaling(graph_str: &str, iterations: usize) -> String {
-    let mut graph = from_string(graph_str);
-    multidimensional_scaling(&mut graph, iterations);
-    graph_to_string(&graph)
+pub fn graph_string_to_json(graph_str: &str) -> Result<String, JsValue> {
+    let graph = from_string(graph_str, "", &LayoutOptions::default(), &mut Rng::new(0))?;
+    Ok(GraphDocument::from_graph(&graph).to_json())
 }
 
 
+// Force-directed algorithms that can be run a few iterations at a time
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ForceAlgorithm {
+    ForceAtlas2,
+    FruchtermanReingold,
+    KamadaKawai,
+}
+
+impl ForceAlgorithm {
+    fn from_name(name: &str) -> Option<ForceAlgorithm> {
+        match name {
+            "force_atlas2" => Some(ForceAlgorithm::ForceAtlas2),
+            "fruchterman_reingold" => Some(ForceAlgorithm::FruchtermanReingold),
+            "kamada_kawai" => Some(ForceAlgorithm::KamadaKawai),
+            _ => None,
+        }
+    }
+}
+
+/*
+Layout session owning a parsed graph, used by the viewer to animate a layout.
+Instead of running all iterations at once, JavaScript calls `step` repeatedly and redraws the current positions,
+which lets the user watch the layout converge, tweak parameters while it runs or stop it at any moment.
+The nodes start in the drawing area of the session and the layouts use it as they do in the process_* functions,
+but the positions are not fitted into it after every step, which would change the forces of the next one.
+Fruchterman-Reingold and ForceAtlas2 cool down over the steps (see cooling.rs) and stop once they converged.
+*/
+#[wasm_bindgen]
+pub struct LayoutSession {
+    graph: Graph,
+    algorithm: ForceAlgorithm,
+    parameters: LayoutParameters,
+    // Speeds of ForceAtlas2 carried from one step to the next
+    force_atlas2_state: ForceAtlas2State,
+    // Temperature and energy carried from one step to the next
+    cooling: Cooling,
+    // Shortest path distances of Kamada-Kawai, the edges do not change between steps
+    distances: Vec<Vec<f64>>,
+    seed: u32,
+    iteration: usize,
+}
+
+#[wasm_bindgen]
+impl LayoutSession {
+    // `initial_str` gives starting positions as in the process_* functions, e.g. to animate from the current drawing
+    #[wasm_bindgen(constructor)]
+    pub fn new(graph_str: &str, initial_str: &str, algorithm: &str, options: &LayoutOptions, seed: u32) -> Result<LayoutSession, JsValue> {
+        let algorithm = ForceAlgorithm::from_name(algorithm).ok_or_else(|| {
+            JsValue::from(js_sys::Error::new(&format!("layout algorithm '{}' cannot be run step by step", algorithm)))
+        })?;
+        let graph = from_string_with_initial(graph_str, "", initial_str, options, &mut Rng::new(seed as u64))?;
+        let force_atlas2_state = ForceAtlas2State::new(graph.nodes.len());
+        let parameters = LayoutParameters::with_options(options);
+        let cooling = fruchterman_reingold_cooling(&graph, parameters.cooling, parameters.iterations, parameters.tolerance);
+        let distances = if algorithm == ForceAlgorithm::KamadaKawai { kamada_kawai_distances(&graph) } else { Vec::new() };
+        Ok(LayoutSession { graph, algorithm, parameters, force_atlas2_state, cooling, distances, seed, iteration: 0 })
+    }
+
+    // Run the given number of iterations starting from the current positions, or fewer once the layout converged
+    pub fn step(&mut self, iterations: usize) {
+        let p = &self.parameters;
+        let run = match self.algorithm {
+            ForceAlgorithm::ForceAtlas2 => {
+                let before = self.cooling.convergence().iterations;
+                force_atlas2(&mut self.graph, iterations, &p.force_atlas2_settings(), &p.options(), &mut self.force_atlas2_state, &mut self.cooling);
+                self.cooling.convergence().iterations - before
+            }
+            ForceAlgorithm::FruchtermanReingold => {
+                let before = self.cooling.convergence().iterations;
+                fruchterman_reingold(&mut self.graph, iterations, &p.options(), p.gravity, p.theta, &mut self.cooling);
+                self.cooling.convergence().iterations - before
+            }
+            ForceAlgorithm::KamadaKawai => {
+                kamada_kawai_with_distances(&mut self.graph, &self.distances, iterations, p.spring_constant, &p.options()).iterations
+            }
+        };
+        self.iteration += run;
+    }
+
+    // Put the nodes back at their starting positions, initial or random ones, pinned nodes stay where they are
+    pub fn reset(&mut self) {
+        randomize_positions(&mut self.graph, &self.parameters.options(), &mut Rng::new(self.seed as u64));
+        for node in &mut self.graph.nodes {
+            node.disp = Point { x: 0.0, y: 0.0 };
+        }
+        self.force_atlas2_state = ForceAtlas2State::new(self.graph.nodes.len());
+        self.cooling = fruchterman_reingold_cooling(&self.graph, self.parameters.cooling, self.parameters.iterations, self.parameters.tolerance);
+        self.iteration = 0;
+    }
+
+    // Fix the node at the given coordinates for the rest of the session
+    pub fn pin(&mut self, index: usize, x: f64, y: f64) -> Result<(), JsValue> {
+        if index >= self.graph.nodes.len() {
+            return Err(js_sys::Error::new(&format!("node {} does not exist in the graph", index)).into());
+        }
+        pin_nodes(&mut self.graph, &[(index, Point { x, y })]);
+        Ok(())
+    }
+
+    // Width and height of a node, used by ForceAtlas2 when overlap is prevented
+    pub fn set_size(&mut self, index: usize, width: f64, height: f64) -> Result<(), JsValue> {
+        if !(width >= 0.0 && height >= 0.0) {
+            return Err(js_sys::Error::new("width and height must be non-negative numbers").into());
+        }
+        let node = self.graph.nodes.get_mut(index).ok_or_else(|| js_sys::Error::new(&format!("node {} does not exist in the graph", index)))?;
+        node.size = Size { width, height };
+        Ok(())
+    }
+
+    // Let a pinned node move again
+    pub fn unpin(&mut self, index: usize) {
+        if let Some(node) = self.graph.nodes.get_mut(index) {
+            node.pinned = None;
+        }
+    }
+
+    // Number of iterations run since the session was created or reset
+    pub fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    // Whether the energy of the layout fell below the tolerance, further steps then leave the positions as they are
+    pub fn converged(&self) -> bool {
+        self.cooling.converged()
+    }
+
+    /*
+    Cooling schedule ("linear", "exponential" or "adaptive") over the given number of iterations and tolerance
+    of the convergence check. The layout heats up again and cools down from its current positions.
+    */
+    pub fn set_cooling(&mut self, schedule: &str, iterations: usize, tolerance: f64) -> Result<(), JsValue> {
+        self.parameters.cooling = cooling_schedule(schedule)?;
+        self.parameters.iterations = iterations;
+        self.parameters.tolerance = tolerance;
+        self.cooling = Cooling::new(self.parameters.cooling, iterations, tolerance);
+        Ok(())
+    }
+
+    // Current positions as a flat array [x0, y0, x1, y1, ...]
+    pub fn positions(&self) -> Vec<f64> {
+        self.graph.nodes.iter().flat_map(|node| vec![node.position.x, node.position.y]).collect()
+    }
+
+    // Current state of the graph in the same format as returned by the process_* functions
+    pub fn to_graph_string(&self) -> String {
+        graph_to_string(&self.graph)
+    }
+
+    pub fn set_gravity(&mut self, gravity: f64) {
+        self.parameters.gravity = gravity;
+    }
+
+    pub fn set_scaling_ratio(&mut self, scaling_ratio: f64) {
+        self.parameters.scaling_ratio = scaling_ratio;
+    }
+
+    pub fn set_theta(&mut self, theta: f64) {
+        self.parameters.theta = theta;
+    }
+
+    pub fn set_spring_constant(&mut self, spring_constant: f64) {
+        self.parameters.spring_constant = spring_constant;
+    }
+
+    pub fn set_lin_log(&mut self, lin_log: bool) {
+        self.parameters.lin_log = lin_log;
+    }
+
+    pub fn set_dissuade_hubs(&mut self, dissuade_hubs: bool) {
+        self.parameters.dissuade_hubs = dissuade_hubs;
+    }
+
+    pub fn set_strong_gravity(&mut self, strong_gravity: bool) {
+        self.parameters.strong_gravity = strong_gravity;
+    }
+
+    pub fn set_edge_weight_influence(&mut self, edge_weight_influence: f64) {
+        self.parameters.edge_weight_influence = edge_weight_influence;
+    }
+
+    pub fn set_prevent_overlap(&mut self, prevent_overlap: bool) {
+        self.parameters.prevent_overlap = prevent_overlap;
+    }
+
+    pub fn set_jitter_tolerance(&mut self, jitter_tolerance: f64) {
+        self.parameters.jitter_tolerance = jitter_tolerance;
+    }
+}
+
 
 
 #[cfg(test)]
@@ -446,8 +1660,26 @@ pub mod tests {
 
     use wasm_bindgen_test::*;
 
+    // Graph shared by the tests of all modules: node i starts at (i, 0), the edges are undirected with weight 1
+    pub(crate) fn graph(num_nodes: usize, edges: &[(usize, usize)]) -> Graph {
+        Graph {
+            nodes: (0..num_nodes).map(|i| Node::new(Point { x: i as f64, y: 0.0 })).collect(),
+            edges: edges.iter().map(|&(source, target)| Edge::new(source, target)).collect(),
+        }
+    }
+
+    // Like graph, with the nodes at the given positions
+    pub(crate) fn graph_at(positions: &[(f64, f64)], edges: &[(usize, usize)]) -> Graph {
+        let mut graph = graph(positions.len(), edges);
+        for (node, &(x, y)) in graph.nodes.iter_mut().zip(positions) {
+            node.position = Point { x, y };
+        }
+        graph
+    }
+
 
     #[wasm_bindgen_test]
+    #[allow(clippy::eq_op)]
     fn pass() {
         assert_eq!(1, 1);
     }
@@ -458,10 +1690,15 @@ pub mod tests {
         let graph_str = "0-1,1-2,3-4,2-3,2-4,5-9,1-5,2-6"; 
         let iterations = 10;
         let gravity = 1.0;
-        let result = process_fruchterman_reingold(graph_str, iterations, gravity);
+        let run = process_fruchterman_reingold(graph_str, "", "", &LayoutOptions::default(), iterations, gravity, 0.0, "linear", 1e-4, 1).unwrap();
+        let result = run.layout;
         let start = result.find("edges: ").unwrap_or(0);
         let expected_result = "edges: ".to_owned() + graph_str + ","; 
         assert_eq!(&result[start..], expected_result);
+        let convergence: Value = serde_json::from_str(&run.result).unwrap();
+        assert!(convergence["energy"].as_f64().unwrap() > 0.0, "{}", convergence);
+        assert_eq!(convergence["iterations"], iterations);
+        assert_eq!(convergence["converged"], false);
     }
 
     #[wasm_bindgen_test]
@@ -469,11 +1706,16 @@ pub mod tests {
         let graph_str = "0-1,1-2,3-4,2-3,2-4,4-5,5-6,6-7,7-8,8-9,9-10,10-11,11-12,12-13,13-14,14-15";
         let iterations = 20;
 
-        let result = process_stress_majorization(graph_str, iterations);
+        let run = process_stress_majorization(graph_str, "", "", &LayoutOptions::default(), iterations, 0, 1e-4, 1).unwrap();
+        let result = run.layout;
 
         let start = result.find("edges: ").unwrap_or(0);
         let expected_result = "edges: ".to_owned() + graph_str + ","; 
         assert_eq!(&result[start..], expected_result);
+        let convergence: Value = serde_json::from_str(&run.result).unwrap();
+        assert!(convergence["stress"].as_f64().unwrap() > 0.0, "{}", convergence);
+        assert!(convergence["iterations"].as_u64().unwrap() <= iterations as u64, "{}", convergence);
+        assert!(convergence["converged"].is_boolean(), "{}", convergence);
 
     }
 
@@ -481,7 +1723,7 @@ pub mod tests {
     #[wasm_bindgen_test]
     fn random_check_if_output_is_correct() {
         let graph_str = "0-1,1-2,3-4,2-3,2-4,5-9,1-5,2-6,7-8,8-1,10-11,9-11"; 
-        let result = process_random(graph_str);
+        let result = process_random(graph_str, "", &LayoutOptions::default(), 1).unwrap();
         let start = result.find("edges: ").unwrap_or(0);
         let expected_result = "edges: ".to_owned() + graph_str + ","; 
         assert_eq!(&result[start..], expected_result);
@@ -492,13 +1734,13 @@ pub mod tests {
     fn new_graph_check_if_positions_of_added_graph_are_correct() {
         let num_nodes = 5;
         let edges = vec![
-            Edge { source: 0, target: 1 },
-            Edge { source: 1, target: 2 },
-            Edge { source: 2, target: 3 },
-            Edge { source: 3, target: 4 },
+            Edge::new(0, 1),
+            Edge::new(1, 2),
+            Edge::new(2, 3),
+            Edge::new(3, 4),
         ];
 
-        let graph = new_graph(num_nodes, edges.clone());
+        let graph = new_graph(num_nodes, edges.clone(), &LayoutOptions::default(), &mut Rng::new(1));
 
         assert_eq!(graph.nodes.len(), num_nodes);
         assert_eq!(graph.edges, edges);
@@ -509,19 +1751,141 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn layouts_check_if_same_seed_gives_same_result() {
+        let graph_str = "0-1,1-2,2-3,3-0,2-4,4-5";
+
+        let first = process_multidimensional_scaling(graph_str, "", "", &LayoutOptions::default(), 20, 0, 7).unwrap();
+        let second = process_multidimensional_scaling(graph_str, "", "", &LayoutOptions::default(), 20, 0, 7).unwrap();
+        let other_seed = process_multidimensional_scaling(graph_str, "", "", &LayoutOptions::default(), 20, 0, 8).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other_seed);
+        // Stress majorization starts from the classical MDS layout, which does not depend on the seed
+        assert_eq!(
+            process_stress_majorization(graph_str, "", "", &LayoutOptions::default(), 20, 0, 1e-4, 7).unwrap().layout,
+            process_stress_majorization(graph_str, "", "", &LayoutOptions::default(), 20, 0, 1e-4, 8).unwrap().layout
+        );
+        assert_eq!(
+            process_force_atlas2(graph_str, "", "", &LayoutOptions::default(), &ForceAtlas2Settings { gravity: 1.0, scaling_ratio: 2.0, ..ForceAtlas2Settings::default() }, "", 50, "adaptive", 1e-4, 3).unwrap().layout,
+            process_force_atlas2(graph_str, "", "", &LayoutOptions::default(), &ForceAtlas2Settings { gravity: 1.0, scaling_ratio: 2.0, ..ForceAtlas2Settings::default() }, "", 50, "adaptive", 1e-4, 3).unwrap().layout
+        );
+    }
+
+    #[test]
+    fn process_multilevel_check_if_pins_and_seed_are_respected() {
+        let graph_str: String = (0..30).map(|i| format!("{}-{},", i, (i + 1) % 30)).collect();
+
+        let clamped = LayoutOptions { clamp: true, ..LayoutOptions::default() };
+        let first = process_multilevel(&graph_str, "7:20,30", "", &clamped, 10, 100, 20, 0.1, 0.5, "linear", 1e-4, 4).unwrap().layout;
+        let second = process_multilevel(&graph_str, "7:20,30", "", &clamped, 10, 100, 20, 0.1, 0.5, "linear", 1e-4, 4).unwrap().layout;
+
+        assert_eq!(first, second);
+        assert!(first.contains(";7:20,30;"), "{}", first);
+        let coordinates = first["nodes: ".len()..first.find("edges: ").unwrap()]
+            .split(';')
+            .filter(|node| !node.is_empty())
+            .flat_map(|node| node.rsplit_once(':').unwrap().1.split(',').map(|value| value.parse::<f64>().unwrap()).collect::<Vec<_>>());
+        assert!(coordinates.into_iter().all(|value| (0.0..=100.0).contains(&value)));
+    }
+
+    #[test]
+    fn multilevel_layout_check_if_it_untangles_more_than_a_single_level() {
+        // A single level layout of a long cycle or a grid from random positions keeps twists the coarse levels undo
+        let cycle: String = (0..100).map(|i| format!("{}-{},", i, (i + 1) % 100)).collect();
+        let grid: String = (0..100)
+            .flat_map(|i| {
+                let right = if i % 10 < 9 { format!("{}-{},", i, i + 1) } else { String::new() };
+                let down = if i < 90 { format!("{}-{},", i, i + 10) } else { String::new() };
+                vec![right, down]
+            })
+            .collect();
+        let options = LayoutOptions::default();
+
+        let metrics = |graph_str: &str| {
+            let mut single = from_string(graph_str, "", &options, &mut Rng::new(3)).unwrap();
+            let mut multilevel = from_string(graph_str, "", &options, &mut Rng::new(3)).unwrap();
+            fruchterman_reingold(&mut single, 300, &options, 0.1, 0.5, &mut Cooling::new(CoolingSchedule::Linear, 300, 0.0));
+            multilevel_layout(&mut multilevel, 10, 100, 30, 0.1, 0.5, CoolingSchedule::Linear, 0.0, &options, &mut Rng::new(3));
+            (layout_metrics(&single, 0.1), layout_metrics(&multilevel, 0.1))
+        };
+        let (single_cycle, multilevel_cycle) = metrics(&cycle);
+        let (single_grid, multilevel_grid) = metrics(&grid);
+
+        assert!(multilevel_cycle.normalized_stress < 0.5 * single_cycle.normalized_stress, "{} >= {}", multilevel_cycle.normalized_stress, single_cycle.normalized_stress);
+        assert!(multilevel_cycle.edge_crossings <= single_cycle.edge_crossings);
+        assert_eq!(multilevel_grid.edge_crossings, 0);
+        assert!(single_grid.edge_crossings > 0);
+    }
+
+    #[test]
+    fn compute_metrics_check_quality_of_stress_majorization() {
+        let grid: String = (0..36)
+            .flat_map(|i| {
+                let right = if i % 6 < 5 { format!("{}-{},", i, i + 1) } else { String::new() };
+                let down = if i < 30 { format!("{}-{},", i, i + 6) } else { String::new() };
+                vec![right, down]
+            })
+            .collect();
+
+        let layout = process_stress_majorization(&grid, "", "", &LayoutOptions::default(), 200, 0, 1e-6, 1).unwrap().layout;
+        let metrics: Value = serde_json::from_str(&compute_metrics(&layout, 0.1).unwrap()).unwrap();
+        let random: Value = serde_json::from_str(&compute_metrics(&process_random(&grid, "", &LayoutOptions::default(), 1).unwrap(), 0.1).unwrap()).unwrap();
+
+        assert_eq!(metrics["edge_crossings"], 0);
+        assert_eq!(metrics["node_overlaps"], 0);
+        assert!(metrics["normalized_stress"].as_f64().unwrap() < 0.05, "{}", metrics);
+        assert!(metrics["neighborhood_preservation"].as_f64().unwrap() > 0.9, "{}", metrics);
+        assert!(metrics["angular_resolution"].as_f64().unwrap() > 60.0, "{}", metrics);
+        assert!(random["edge_crossings"].as_u64().unwrap() > 10, "{}", random);
+        assert!(random["normalized_stress"].as_f64().unwrap() > 0.1, "{}", random);
+    }
+
+    #[test]
+    fn layout_session_check_if_steps_match_single_run() {
+        let graph_str = "0-1,1-2,2-3,3-0,2-4,4-5";
+        let mut session = LayoutSession::new(graph_str, "", "force_atlas2", &LayoutOptions::default(), 5).unwrap();
+        session.set_gravity(1.0);
+        session.set_scaling_ratio(2.0);
+        session.set_theta(0.0);
+
+        session.step(4);
+        session.step(6);
+
+        // Unlike process_force_atlas2 the session does not fit its positions into the drawing area
+        let mut stepped = from_layout_string(&session.to_graph_string()).unwrap();
+        fit_to_area(&mut stepped, &LayoutOptions::default());
+        assert_eq!(session.iteration(), 10);
+        assert_eq!(graph_to_string(&stepped), process_force_atlas2(graph_str, "", "", &LayoutOptions::default(), &ForceAtlas2Settings { gravity: 1.0, scaling_ratio: 2.0, theta: 0.0, ..ForceAtlas2Settings::default() }, "", 10, "adaptive", 1e-4, 5).unwrap().layout);
+    }
+
+    #[test]
+    fn layout_session_check_if_reset_restores_initial_positions() {
+        let mut session = LayoutSession::new("0-1,1-2,2-0", "", "fruchterman_reingold", &LayoutOptions::default(), 9).unwrap();
+        let initial = session.positions();
+
+        session.step(20);
+        assert_ne!(session.positions(), initial);
+        session.reset();
+
+        assert_eq!(session.positions(), initial);
+        assert_eq!(session.iteration(), 0);
+        assert_eq!(ForceAlgorithm::from_name("circular"), None);
+    }
+
     #[wasm_bindgen_test]
     fn process_circular_check_if_coordinates_are_correct() {
         let graph_str = "0-1,1-2,2-3,3-4,4-0,1-5,5-6,6-7,6-8,6-9,6-10";
     
 
-        let result = process_circular(graph_str);
+        let result = process_circular(graph_str, "", &LayoutOptions::default(), "input", 1).unwrap();
 
         // Parse the result and check the coordinates
         let items: Vec<&str> = result.split(';').collect();
         for item in items {
-            if item.starts_with("nodes:") {
-                let node_str = &item[6..];
-                let parts: Vec<&str> = node_str.split(',').collect();
+            if let Some(node_str) = item.strip_prefix("nodes:") {
+                let (_, coordinates) = node_str.rsplit_once(':').unwrap();
+                let parts: Vec<&str> = coordinates.split(',').collect();
                 assert_eq!(parts.len(), 2, "Unexpected parts length: {:?}, node_str: {}", parts, node_str);
                 let x: f64 = parts[0].trim().parse().unwrap();
                 assert!(x > 0.0 && x <= 100.0, "x coordinate is not in the expected range: {}", x);
@@ -531,6 +1895,43 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn process_circular_check_if_hub_is_on_inner_ring() {
+        let graph_str = "a-b,a-c,a-d,a-e,b-c";
+
+        let result = process_circular(graph_str, "", &LayoutOptions::default(), "crossing_reduction", 2).unwrap();
+
+        // The hub is alone on the inner ring of radius 25, the other nodes are on the outer ring of radius 50
+        assert!(result.starts_with("nodes: a:75,50;"), "{}", result);
+        let graph = from_layout_string(&result).unwrap();
+        for node in &graph.nodes[1..] {
+            let radius = (node.position.x - 50.0).hypot(node.position.y - 50.0);
+            assert!((radius - 50.0).abs() < 1e-9, "{}", result);
+        }
+        assert_eq!(result, process_circular(graph_str, "", &LayoutOptions::default(), "crossing_reduction", 2).unwrap());
+    }
+
+    #[test]
+    fn process_remove_overlaps_check_if_boxes_are_separated() {
+        let layout_str = "nodes: a:0,0;b:1,0;c:50,50;edges: a-b,b-c,";
+        let graph_json = r#"{"version": 1, "nodes": [{"id": 0, "width": 80, "height": 80}, {"id": 1, "width": 80, "height": 80}, {"id": 2, "width": 80, "height": 80}],
+            "edges": [{"source": 0, "target": 1}, {"source": 1, "target": 2}]}"#;
+
+        let result = process_remove_overlaps(layout_str, "a:10,4;b:10,4", "a:0,0").unwrap();
+        let document: Value = serde_json::from_str(&process_json(graph_json, "circular", r#"{"remove_overlaps": true}"#).unwrap()).unwrap();
+
+        let graph = from_layout_string(&result).unwrap();
+        let b = graph.nodes[1].position;
+        assert!(result.starts_with("nodes: a:0,0;") && result.ends_with("edges: a-b,b-c,"), "{}", result);
+        assert!(b.x.abs() >= 10.0 - 1e-4 || b.y.abs() >= 4.0 - 1e-4, "{}", result);
+        assert_eq!(document["metadata"]["result"]["overlaps"], 0);
+        assert_eq!(document["metadata"]["parameters"]["remove_overlaps"], true);
+        assert_eq!(document["nodes"][0]["width"], 80.0);
+        let (x0, y0) = (document["nodes"][0]["x"].as_f64().unwrap(), document["nodes"][0]["y"].as_f64().unwrap());
+        let (x1, y1) = (document["nodes"][1]["x"].as_f64().unwrap(), document["nodes"][1]["y"].as_f64().unwrap());
+        assert!((x0 - x1).abs() >= 80.0 - 1e-4 || (y0 - y1).abs() >= 80.0 - 1e-4, "{}", document);
+    }
+
     #[wasm_bindgen_test]
     fn force_atlas2_check_if_coordinates_are_correct() {
         let graph_str = "0-1,1-2,2-3,3-4,4-0";
@@ -538,16 +1939,15 @@ pub mod tests {
         let gravity = 1.0;
         let scaling_ratio = 1.0;
 
-        let result = process_force_atlas2(graph_str, iterations, gravity, scaling_ratio);
+        let result = process_force_atlas2(graph_str, "", "", &LayoutOptions::default(), &ForceAtlas2Settings { gravity, scaling_ratio, ..ForceAtlas2Settings::default() }, "", iterations, "adaptive", 1e-4, 1).unwrap().layout;
 
         // Parse the result and check the coordinates
         let items: Vec<&str> = result.split(';').collect();
         for item in items {
-            if item.starts_with("nodes:") {
-                let node_str = &item[6..];
-                let parts: Vec<&str> = node_str.split(',').collect();
+            if let Some(node_str) = item.strip_prefix("nodes:") {
+                let (_, coordinates) = node_str.rsplit_once(':').unwrap();
+                let parts: Vec<&str> = coordinates.split(',').collect();
                 assert_eq!(parts.len(), 2, "Unexpected parts length: {:?}, node_str: {}", parts, node_str);
-                println!("parts[0]: {}", parts[0]); // print the value of parts[0]
                 let x: f64 = parts[0].trim().parse().unwrap();
                 assert!(x > 0.0 && x <= 100.0, "x coordinate is not in the expected range: {}", x);
                 let y: f64 = parts[1].trim().parse().unwrap();
@@ -559,35 +1959,521 @@ pub mod tests {
     #[test]
     fn graph_to_string_check_if_parsed_correctly() {
         let nodes = vec![
-            Node { position: Point { x: 1.0, y: 2.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 3.0, y: 4.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 5.0, y: 6.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 7.0, y: 8.0 }, disp: Point { x: 0.0, y: 0.0 } },
+            Node::new(Point { x: 1.0, y: 2.0 }),
+            Node::new(Point { x: 3.0, y: 4.0 }),
+            Node::new(Point { x: 5.0, y: 6.0 }),
+            Node::new(Point { x: 7.0, y: 8.0 }),
         ];
         let edges = vec![
-            Edge { source: 0, target: 1 },
-            Edge { source: 2, target: 3 },
+            Edge::new(0, 1),
+            Edge::new(2, 3),
         ];
         let graph = Graph { nodes, edges };
 
         let graph_str = graph_to_string(&graph);
 
-        assert_eq!(graph_str, "nodes: 1,2;3,4;5,6;7,8;edges: 0-1,2-3,");
+        assert_eq!(graph_str, "nodes: 0:1,2;1:3,4;2:5,6;3:7,8;edges: 0-1,2-3,");
+    }
+
+    #[test]
+    fn from_string_check_if_labels_are_kept() {
+        let graph_str = "alice-bob,bob->\"New York\":2,7-alice";
+
+        let graph = from_string(graph_str, "bob:10,20", &LayoutOptions::default(), &mut Rng::new(1)).unwrap();
+        let result = graph_to_string(&graph);
+
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.nodes[2].label, "New York");
+        assert_eq!((graph.nodes[1].position.x, graph.nodes[1].position.y), (10.0, 20.0));
+        assert!(result.starts_with("nodes: alice:"), "{}", result);
+        assert!(result.contains(";bob:10,20;\"New York\":"), "{}", result);
+        assert!(result.ends_with("edges: alice-bob,bob->\"New York\":2,7-alice,"), "{}", result);
+    }
+
+    #[test]
+    fn apply_repulsion_barnes_hut_is_close_to_exact() {
+        let make_graph = || Graph {
+            nodes: (0..64)
+                .map(|i| Node::new(Point { x: (i % 8) as f64 * 10.0 + (i / 8) as f64 * 0.3, y: (i / 8) as f64 * 10.0 }))
+                .collect(),
+            edges: vec![],
+        };
+        let mut exact = make_graph();
+        let mut approximate = make_graph();
+
+        apply_repulsion(&mut exact, 0.0, |distance| 100.0 / distance);
+        apply_repulsion(&mut approximate, 0.5, |distance| 100.0 / distance);
+
+        for (e, a) in exact.nodes.iter().zip(approximate.nodes.iter()) {
+            let error = e.disp.distance(a.disp);
+            let magnitude = (e.disp.x.powi(2) + e.disp.y.powi(2)).sqrt();
+            assert!(error <= 0.1 * magnitude + 1e-6, "error {} for force {}", error, magnitude);
+        }
+    }
+
+    #[test]
+    fn fruchterman_reingold_check_if_heavy_edges_are_shorter() {
+        let mut graph = from_string("0-1:10,1-2:0.1", "", &LayoutOptions::default(), &mut Rng::new(4)).unwrap();
+
+        fruchterman_reingold(&mut graph, 200, &LayoutOptions::default(), 0.0, 0.0, &mut Cooling::new(CoolingSchedule::Linear, 200, 1e-4));
+
+        let length = |a: usize, b: usize| {
+            let dx = graph.nodes[a].position.x - graph.nodes[b].position.x;
+            let dy = graph.nodes[a].position.y - graph.nodes[b].position.y;
+            (dx * dx + dy * dy).sqrt()
+        };
+        assert!(length(0, 1) < length(1, 2), "{} >= {}", length(0, 1), length(1, 2));
+    }
+
+    #[test]
+    fn graph_to_string_check_if_direction_is_kept() {
+        let graph_str = "0->1,1-2:3,2->0:0.5";
+
+        let result = process_circular(graph_str, "", &LayoutOptions::default(), "input", 1).unwrap();
+
+        assert!(result.ends_with("edges: 0->1,1-2:3,2->0:0.5,"), "{}", result);
+    }
+
+    #[test]
+    fn graph_to_string_check_if_weights_are_kept() {
+        let graph_str = "0-1:2.5,1-2,2-0:0.5";
+
+        let result = process_random(graph_str, "", &LayoutOptions::default(), 1).unwrap();
+
+        assert!(result.ends_with("edges: 0-1:2.5,1-2,2-0:0.5,"), "{}", result);
+    }
+
+    #[test]
+    fn layouts_check_if_disconnected_graph_is_packed() {
+        let graph_str = "0-1,1-2,2-0,3-4,4-5,5-3,6-7";
+        let results = [
+            process_force_atlas2(graph_str, "", "", &LayoutOptions::default(), &ForceAtlas2Settings { gravity: 1.0, scaling_ratio: 2.0, ..ForceAtlas2Settings::default() }, "", 50, "adaptive", 1e-4, 1).unwrap().layout,
+            process_stress_majorization(graph_str, "", "", &LayoutOptions::default(), 20, 0, 1e-4, 1).unwrap().layout,
+            process_kamada_kawai(graph_str, "", "", &LayoutOptions::default(), 50, 1.0, 1).unwrap().layout,
+        ];
+
+        for result in &results {
+            let positions: Vec<(f64, f64)> = result["nodes: ".len()..result.find("edges: ").unwrap()]
+                .split(';')
+                .filter(|node| !node.is_empty())
+                .map(|node| {
+                    let (_, coordinates) = node.rsplit_once(':').unwrap();
+                    let (x, y) = coordinates.split_once(',').unwrap();
+                    (x.parse().unwrap(), y.parse().unwrap())
+                })
+                .collect();
+            for &(x, y) in &positions {
+                assert!((0.0..=100.0).contains(&x) && (0.0..=100.0).contains(&y), "{}", result);
+            }
+            let bounds = |nodes: &[usize]| {
+                nodes.iter().map(|&node| positions[node]).fold(
+                    (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+                    |(x0, y0, x1, y1), (x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                )
+            };
+            let boxes = [bounds(&[0, 1, 2]), bounds(&[3, 4, 5]), bounds(&[6, 7])];
+            for (i, a) in boxes.iter().enumerate() {
+                for b in &boxes[i + 1..] {
+                    assert!(a.2 < b.0 || b.2 < a.0 || a.3 < b.1 || b.3 < a.1, "components overlap in {}", result);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stress_majorization_check_pivot_mode_on_grid() {
+        let side = 12;
+        let mut edges = Vec::new();
+        for i in 0..side * side {
+            if i % side + 1 < side {
+                edges.push(Edge::new(i, i + 1));
+            }
+            if i + side < side * side {
+                edges.push(Edge::new(i, i + side));
+            }
+        }
+        let mut graph = new_graph(side * side, edges, &LayoutOptions::default(), &mut Rng::new(1));
+
+        stress_majorization(&mut graph, 100, 10, 1e-4, Initialization::ClassicalMds, &LayoutOptions::default(), &mut Rng::new(1));
+
+        let distance = |a: usize, b: usize| {
+            graph.nodes[a].position.distance(graph.nodes[b].position)
+        };
+        assert!(graph.nodes.iter().all(|node| node.position.x.is_finite() && node.position.y.is_finite()));
+        assert!(distance(0, 1) < distance(0, side * side - 1) / 5.0);
+        assert!(distance(side - 1, side * (side - 1)) > 10.0);
+    }
+
+    #[test]
+    fn stress_majorization_check_isolated_nodes() {
+        let mut graph = new_graph(3, vec![Edge::new(0, 1)], &LayoutOptions::default(), &mut Rng::new(1));
+
+        stress_majorization(&mut graph, 10, 0, 1e-4, Initialization::ClassicalMds, &LayoutOptions::default(), &mut Rng::new(1));
+
+        assert!(graph.nodes.iter().all(|node| node.position.x.is_finite() && node.position.y.is_finite()));
+    }
+
+    #[test]
+    fn process_layered_check_if_edges_point_down() {
+        let graph_str = "parse->check,check->emit,parse->emit,emit->parse,lint->check";
+
+        let result = process_layered(graph_str, "", &LayoutOptions::default(), "top_down", 10.0, 20.0).unwrap();
+        let graph = from_string(graph_str, "", &LayoutOptions::default(), &mut Rng::new(0)).unwrap();
+
+        let y: Vec<f64> = result["nodes: ".len()..result.find("edges: ").unwrap()]
+            .split(';')
+            .filter(|node| !node.is_empty())
+            .map(|node| node.rsplit_once(',').unwrap().1.parse().unwrap())
+            .collect();
+        // Every edge points down except emit->parse, which closes a cycle and is reversed
+        for edge in graph.edges.iter().filter(|edge| (edge.source, edge.target) != (2, 0)) {
+            assert!(y[edge.source] < y[edge.target], "{}", result);
+        }
+        assert_eq!(LayeredDirection::from_name("bottom_up"), None);
+    }
+
+    #[test]
+    fn process_layered_check_if_layout_is_moved_onto_pin() {
+        let graph_str = "parse->check,check->emit,lint->check";
+
+        let result = process_layered(graph_str, "check:50,60", &LayoutOptions::default(), "top_down", 10.0, 20.0).unwrap();
+
+        // The layers stay 20 apart around the pinned node instead of it leaving its edges behind
+        assert!(result.contains(";check:50,60;"), "{}", result);
+        assert!(result.contains("parse:45,40;") && result.contains(";lint:55,40;") && result.contains(";emit:50,80;"), "{}", result);
+    }
+
+    #[test]
+    fn eigenvector_layouts_check_if_free_nodes_are_placed_among_pins() {
+        // A path with one end pinned near the top left corner, the rest of it reaches across the area
+        let graph_str = "0-1,1-2,2-3,3-4,4-5,5-6";
+        let layouts = [
+            process_classical_mds(graph_str, "0:10,10", &LayoutOptions::default(), 0).unwrap(),
+            process_spectral(graph_str, "0:10,10", &LayoutOptions::default(), false).unwrap(),
+            process_spectral(graph_str, "0:10,10", &LayoutOptions::default(), true).unwrap(),
+        ];
+
+        for layout in &layouts {
+            let graph = from_layout_string(layout).unwrap();
+            assert_eq!((graph.nodes[0].position.x, graph.nodes[0].position.y), (10.0, 10.0));
+            let inside = |value: f64| (0.0..=100.0).contains(&value);
+            assert!(graph.nodes.iter().all(|node| inside(node.position.x) && inside(node.position.y)), "{}", layout);
+            assert!(graph.nodes[6].position.distance(graph.nodes[0].position) > 50.0, "{}", layout);
+        }
+    }
+
+    #[test]
+    fn process_spectral_check_if_layout_is_deterministic() {
+        let graph_str = "0-1,1-2,2-3,3-4,4-0,0-2,5-6";
+
+        let first = process_spectral(graph_str, "", &LayoutOptions::default(), false).unwrap();
+        let second = process_spectral(graph_str, "", &LayoutOptions::default(), false).unwrap();
+        let normalized = process_spectral(graph_str, "", &LayoutOptions::default(), true).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, normalized);
+        assert!(!first.contains("NaN"), "{}", first);
+    }
+
+    #[test]
+    fn process_tree_check_if_root_is_on_top() {
+        let graph_str = "ceo-cto,ceo-cfo,cto-dev,cto-ops";
+
+        let centered = process_tree(graph_str, "", &LayoutOptions::default(), "", 10.0, 20.0).unwrap();
+        let rooted = process_tree(graph_str, "", &LayoutOptions::default(), "dev", 10.0, 20.0).unwrap();
+
+        // The center of the tree is cto, the longest path runs from cfo to dev
+        assert!(centered.contains(";cto:10,0;"), "{}", centered);
+        assert!(rooted.contains(";dev:5,0;"), "{}", rooted);
+        assert!(rooted.contains(";cfo:0,60;"), "{}", rooted);
+        assert_eq!(tree_root(&from_string(graph_str, "", &LayoutOptions::default(), &mut Rng::new(0)).unwrap(), "intern"), None);
+    }
+
+    #[test]
+    fn process_tree_check_if_tree_is_moved_onto_pin() {
+        let graph_str = "ceo-cto,ceo-cfo,cto-dev,cto-ops";
+
+        let tidy = process_tree(graph_str, "cfo:30,70", &LayoutOptions::default(), "ceo", 10.0, 20.0).unwrap();
+        let radial = process_radial_tree(graph_str, "ceo:50,50", &LayoutOptions::default(), "ceo", 10.0, 20.0).unwrap();
+
+        // The pinned node keeps its place in the tree, its parent stays a level above it
+        assert!(tidy.contains(";cfo:30,70;"), "{}", tidy);
+        assert!(tidy.contains("ceo:25,50;") && tidy.contains(";dev:15,90;"), "{}", tidy);
+        let graph = from_layout_string(&radial).unwrap();
+        assert_eq!((graph.nodes[0].position.x, graph.nodes[0].position.y), (50.0, 50.0));
+        assert!((graph.nodes[1].position.distance(graph.nodes[0].position) - 20.0).abs() < 1e-9, "{}", radial);
+    }
+
+    #[test]
+    fn layouts_check_if_pinned_nodes_stay_in_place() {
+        let graph_str = "0-1,1-2,2-3,3-0,2-4,4-5";
+        let pinned_str = "1:12.5,80;4:70,5";
+        let results = [
+            process_random(graph_str, pinned_str, &LayoutOptions::default(), 1).unwrap(),
+            process_circular(graph_str, pinned_str, &LayoutOptions::default(), "input", 1).unwrap(),
+            process_force_atlas2(graph_str, pinned_str, "", &LayoutOptions::default(), &ForceAtlas2Settings { gravity: 1.0, scaling_ratio: 2.0, ..ForceAtlas2Settings::default() }, "", 50, "adaptive", 1e-4, 1).unwrap().layout,
+            process_fruchterman_reingold(graph_str, pinned_str, "", &LayoutOptions::default(), 50, 1.0, 0.5, "linear", 1e-4, 1).unwrap().layout,
+            process_stress_majorization(graph_str, pinned_str, "", &LayoutOptions::default(), 20, 0, 1e-4, 1).unwrap().layout,
+            process_multidimensional_scaling(graph_str, pinned_str, "", &LayoutOptions::default(), 20, 0, 1).unwrap(),
+            process_kamada_kawai(graph_str, pinned_str, "", &LayoutOptions::default(), 50, 1.0, 1).unwrap().layout,
+        ];
+
+        for result in &results {
+            let nodes: Vec<&str> = result["nodes: ".len()..result.find("edges: ").unwrap()].split(';').collect();
+            assert_eq!(nodes[1], "1:12.5,80", "pinned node moved in {}", result);
+            assert_eq!(nodes[4], "4:70,5", "pinned node moved in {}", result);
+            // The free nodes relax around the pins over the area instead of bunching up next to one of them
+            let graph = from_layout_string(result).unwrap();
+            let free: Vec<Point> = [0, 2, 3, 5].iter().map(|&i| graph.nodes[i].position).collect();
+            let extent = |coordinate: fn(&Point) -> f64| {
+                let values = free.iter().map(coordinate);
+                values.clone().fold(f64::NEG_INFINITY, f64::max) - values.fold(f64::INFINITY, f64::min)
+            };
+            assert!(extent(|p| p.x).max(extent(|p| p.y)) > 50.0, "free nodes bunch up in {}", result);
+        }
+    }
+
+    #[test]
+    fn layouts_check_if_free_nodes_fit_the_area_around_a_pin() {
+        // Without clamping the free nodes leave the area while the force-directed layouts run
+        let graph_str: String = (0..20).map(|i| format!("{}-{},", i, (i + 1) % 20)).collect();
+        let options = LayoutOptions::default();
+        let layouts = [
+            process_fruchterman_reingold(&graph_str, "0:10,10", "", &options, 100, 0.1, 0.5, "linear", 1e-4, 1).unwrap().layout,
+            process_force_atlas2(&graph_str, "0:10,10", "", &options, &ForceAtlas2Settings::default(), "", 100, "adaptive", 1e-4, 1).unwrap().layout,
+        ];
+
+        for layout in &layouts {
+            let graph = from_layout_string(layout).unwrap();
+            assert_eq!((graph.nodes[0].position.x, graph.nodes[0].position.y), (10.0, 10.0));
+            let inside = |value: f64| (0.0..=100.0).contains(&value);
+            assert!(graph.nodes.iter().all(|node| inside(node.position.x) && inside(node.position.y)), "{}", layout);
+        }
+    }
+
+    #[test]
+    fn layout_session_check_if_pinned_node_stays_in_place() {
+        let mut session = LayoutSession::new("0-1,1-2,2-0", "", "force_atlas2", &LayoutOptions::default(), 2).unwrap();
+
+        session.pin(2, 40.0, 60.0).unwrap();
+        session.step(10);
+        assert_eq!(&session.positions()[4..], &[40.0, 60.0]);
+        session.unpin(2);
+        session.step(10);
+
+        assert_ne!(&session.positions()[4..], &[40.0, 60.0]);
+    }
+
+    #[test]
+    fn kamada_kawai_check_if_edges_get_uniform_length() {
+        let positions = [(10.0, 10.0), (12.0, 60.0), (80.0, 15.0), (30.0, 90.0), (70.0, 70.0)];
+        let mut graph = Graph {
+            nodes: positions
+                .iter()
+                .map(|&(x, y)| Node::new(Point { x, y }))
+                .collect(),
+            edges: vec![
+                Edge::new(0, 1),
+                Edge::new(1, 2),
+                Edge::new(2, 3),
+                Edge::new(3, 4),
+                Edge::new(4, 0),
+            ],
+        };
+
+        let convergence = kamada_kawai(&mut graph, 500, 1.0, &LayoutOptions::default());
+
+        // A 5-cycle should become a regular pentagon with all sides equal
+        let lengths: Vec<f64> = graph.edges.iter().map(|edge| {
+            let dx = graph.nodes[edge.source].position.x - graph.nodes[edge.target].position.x;
+            let dy = graph.nodes[edge.source].position.y - graph.nodes[edge.target].position.y;
+            (dx * dx + dy * dy).sqrt()
+        }).collect();
+        for length in &lengths {
+            assert!((length - lengths[0]).abs() < 1e-2 * lengths[0], "unequal edge lengths: {:?}", lengths);
+        }
+        // Twice as stiff springs settle in the same shape with twice the energy
+        let mut stiff = graph_at(&positions, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+        let stiff_convergence = kamada_kawai(&mut stiff, 500, 2.0, &LayoutOptions::default());
+        assert!(convergence.converged && stiff_convergence.converged);
+        assert!((stiff_convergence.value - 2.0 * convergence.value).abs() < 1e-6 * convergence.value, "{:?} {:?}", stiff_convergence, convergence);
+    }
+
+    #[test]
+    fn run_layout_check_reported_parameters() {
+        let mut graph = graph(3, &[(0, 1), (1, 2)]);
+        let parameters = LayoutParameters { iterations: 7, spring_constant: 2.0, ..LayoutParameters::default() };
+
+        let (used, result) = run_layout(&mut graph, "kamada_kawai", &parameters, &mut Rng::new(1)).unwrap();
+        let (_, stress_result) = run_layout(&mut graph, "stress_majorization", &parameters, &mut Rng::new(1)).unwrap();
+
+        assert_eq!(Value::Object(used), json!({ "iterations": 7, "spring_constant": 2.0 }));
+        assert!(result["energy"].as_f64().unwrap() >= 0.0);
+        assert!(result["iterations"].as_u64().unwrap() <= 7);
+        assert_eq!(stress_result["converged"], json!(true));
+        assert!(stress_result["stress"].as_f64().unwrap() < 1e-6);
+        assert!(stress_result["iterations"].as_u64().unwrap() <= 7);
+        assert_eq!(
+            run_layout(&mut graph, "spring", &parameters, &mut Rng::new(1)),
+            Err(JsonError::UnknownAlgorithm("spring".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_parameters_check_if_unknown_keys_are_rejected() {
+        let parameters = parse_parameters(r#"{"gravity": 2.0, "width": 300, "clamp": true}"#).unwrap();
+
+        assert_eq!(parameters.gravity, 2.0);
+        assert_eq!(parameters.options(), LayoutOptions { width: 300.0, clamp: true, ..LayoutOptions::default() });
+        assert_eq!(parse_parameters(" ").unwrap(), LayoutParameters::default());
+        match parse_parameters(r#"{"graivty": 2.0}"#) {
+            Err(JsonError::InvalidJson(message)) => assert!(message.contains("graivty"), "{}", message),
+            other => panic!("misspelled parameter accepted: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fruchterman_reingold_check_if_cooling_stops_layout_early() {
+        let mut grid_str = String::new();
+        for i in 0..36 {
+            if i % 6 < 5 {
+                grid_str += &format!("{}-{},", i, i + 1);
+            }
+            if i < 30 {
+                grid_str += &format!("{}-{},", i, i + 6);
+            }
+        }
+        let mut graph = from_string(&grid_str, "", &LayoutOptions::default(), &mut Rng::new(1)).unwrap();
+        let linear = LayoutParameters { iterations: 100, cooling: CoolingSchedule::Linear, tolerance: 0.0, ..LayoutParameters::default() };
+
+        let (used, adaptive_result) = run_layout(&mut graph, "fruchterman_reingold", &LayoutParameters::default(), &mut Rng::new(1)).unwrap();
+        let (_, linear_result) = run_layout(&mut graph, "fruchterman_reingold", &linear, &mut Rng::new(1)).unwrap();
+
+        assert_eq!((&used["cooling"], &used["tolerance"]), (&json!("adaptive"), &json!(1e-4)));
+        assert_eq!(adaptive_result["converged"], json!(true));
+        assert!(adaptive_result["iterations"].as_u64().unwrap() < 1000, "{:?}", adaptive_result);
+        assert_eq!((&linear_result["iterations"], &linear_result["converged"]), (&json!(100), &json!(false)));
+
+        // A converged session stays where it is and the viewer stops animating it
+        let mut session = LayoutSession::new(&grid_str, "", "fruchterman_reingold", &LayoutOptions::default(), 1).unwrap();
+        session.set_cooling("exponential", 300, 1e-4).unwrap();
+        while !session.converged() && session.iteration() < 300 {
+            session.step(25);
+        }
+        let positions = session.positions();
+        session.step(25);
+        assert!(session.converged() && session.iteration() < 300, "{}", session.iteration());
+        assert_eq!(session.positions(), positions);
+    }
+
+    #[test]
+    fn layouts_check_if_warm_start_refines_previous_drawing() {
+        let graph_str = "0-1,1-2,2-3,3-4,4-5,5-0,0-3,6-0,7-3";
+        let options = LayoutOptions::default();
+        let previous = process_fruchterman_reingold(graph_str, "", "", &options, 300, 0.1, 0.0, "adaptive", 1e-4, 1).unwrap().layout;
+        let mean_shift = |layout: &str| {
+            let (a, b) = (from_layout_string(&previous).unwrap(), from_layout_string(layout).unwrap());
+            let shifts = a.nodes.iter().zip(&b.nodes).map(|(p, q)| p.position.distance(q.position));
+            shifts.sum::<f64>() / a.nodes.len() as f64
+        };
+
+        // A little more gravity and another seed, once continuing from the previous drawing and once from scratch
+        let warm = process_fruchterman_reingold(graph_str, "", &previous, &options, 50, 0.2, 0.0, "adaptive", 1e-4, 2).unwrap().layout;
+        let cold = process_fruchterman_reingold(graph_str, "", "", &options, 50, 0.2, 0.0, "adaptive", 1e-4, 2).unwrap().layout;
+        // Only node 0 is placed, the others keep the classical MDS layout moved onto it
+        let mut refined = from_string_with_initial(graph_str, "", "0:50,50;unknown:1,1", &options, &mut Rng::new(1)).unwrap();
+        stress_majorization(&mut refined, 0, 0, 1e-4, Initialization::ClassicalMds, &options, &mut Rng::new(1));
+        let mds = classical_mds(&refined, 0).unwrap();
+
+        assert!(mean_shift(&warm) < 2.0 && mean_shift(&cold) > 20.0 * mean_shift(&warm), "{} {}", mean_shift(&warm), mean_shift(&cold));
+        assert_eq!((refined.nodes[0].position.x, refined.nodes[0].position.y), (50.0, 50.0));
+        for (node, position) in refined.nodes.iter().zip(&mds) {
+            let shift = Point { x: node.position.x - position.x, y: node.position.y - position.y };
+            assert!(shift.distance(Point { x: 50.0 - mds[0].x, y: 50.0 - mds[0].y }) < 1e-9, "{:?}", refined.nodes);
+        }
+        // A session starts at the given positions and returns to them on reset
+        let mut session = LayoutSession::new(graph_str, &previous, "kamada_kawai", &options, 3).unwrap();
+        let start = session.positions();
+        session.step(20);
+        session.reset();
+        assert_eq!(session.positions(), start);
+        assert_eq!(graph_to_string(&from_layout_string(&session.to_graph_string()).unwrap()), previous);
+    }
+
+    #[test]
+    fn layout_session_check_if_kamada_kawai_steps_continue_one_run() {
+        let graph_str = "0-1,1-2,2-3,3-0,2-4,5-6";
+        let options = LayoutOptions::default();
+
+        let mut session = LayoutSession::new(graph_str, "", "kamada_kawai", &options, 2).unwrap();
+        session.step(5);
+        session.step(15);
+        let mut graph = from_string(graph_str, "", &options, &mut Rng::new(2)).unwrap();
+        kamada_kawai(&mut graph, 20, 1.0, &options);
+
+        // The distances kept by the session are those a single run computes, the gradients only differ by rounding
+        assert_eq!(session.distances, kamada_kawai_distances(&graph));
+        let stepped = from_layout_string(&session.to_graph_string()).unwrap();
+        for (a, b) in stepped.nodes.iter().zip(&graph.nodes) {
+            assert!(a.position.distance(b.position) < 1e-9, "{:?} {:?}", a.position, b.position);
+        }
+    }
+
+    #[test]
+    fn process_json_check_if_layout_fits_drawing_area() {
+        let graph_json = r#"{"version": 1, "nodes": [{"id": 0}, {"id": 1}, {"id": 2}, {"id": 3}], "edges": [{"source": 0, "target": 1}, {"source": 1, "target": 2}, {"source": 2, "target": 3}]}"#;
+        let parameters = r#"{"iterations": 200, "width": 400, "height": 200, "margin": 20, "keep_aspect_ratio": false}"#;
+
+        let document: Value = serde_json::from_str(&process_json(graph_json, "fruchterman_reingold", parameters).unwrap()).unwrap();
+        let circle: Value = serde_json::from_str(&process_json(graph_json, "circular", parameters).unwrap()).unwrap();
+
+        let coordinates = |document: &Value, axis: &str| -> Vec<f64> {
+            document["nodes"].as_array().unwrap().iter().map(|node| node[axis].as_f64().unwrap()).collect()
+        };
+        for (axis, low, high) in [("x", 20.0, 380.0), ("y", 20.0, 180.0)] {
+            let values = coordinates(&document, axis);
+            // Without keeping the aspect ratio the layout touches all four borders of the inner area
+            assert!(values.iter().all(|&value| value >= low - 1e-9 && value <= high + 1e-9), "{}", document);
+            assert!(values.iter().any(|&value| (value - low).abs() < 1e-9) && values.iter().any(|&value| (value - high).abs() < 1e-9));
+        }
+        // Four nodes on an ellipse filling the inner area
+        let rounded = |axis| coordinates(&circle, axis).into_iter().map(f64::round).collect::<Vec<_>>();
+        assert_eq!(rounded("x"), vec![380.0, 200.0, 20.0, 200.0]);
+        assert_eq!(rounded("y"), vec![100.0, 180.0, 100.0, 20.0]);
+        assert_eq!(document["metadata"]["parameters"]["width"], json!(400.0));
+        assert_eq!(document["metadata"]["parameters"]["clamp"], json!(false));
+    }
+
+    #[test]
+    fn process_json_check_warm_start_from_document_positions() {
+        let graph_json = r#"{"version": 1, "nodes": [{"id": "a", "x": 10, "y": 10}, {"id": "b", "x": 90, "y": 90}, {"id": "c", "x": 50, "y": 10}],
+            "edges": [{"source": "a", "target": "b"}, {"source": "b", "target": "c"}]}"#;
+
+        let warm: Value = serde_json::from_str(&process_json(graph_json, "kamada_kawai", r#"{"iterations": 0, "warm_start": true}"#).unwrap()).unwrap();
+        let cold: Value = serde_json::from_str(&process_json(graph_json, "kamada_kawai", r#"{"iterations": 0}"#).unwrap()).unwrap();
+
+        let positions = |document: &Value| -> Vec<(f64, f64)> {
+            document["nodes"].as_array().unwrap().iter().map(|node| (node["x"].as_f64().unwrap(), node["y"].as_f64().unwrap())).collect()
+        };
+        assert_eq!(positions(&warm), vec![(0.0, 0.0), (100.0, 100.0), (50.0, 0.0)]);
+        assert_ne!(positions(&cold), positions(&warm));
+        assert_eq!(warm["metadata"]["parameters"]["warm_start"], json!(true));
+        assert!(cold["metadata"]["parameters"].get("warm_start").is_none());
     }
 
     #[wasm_bindgen_test]
     fn multidimensional_scaling_check_for_correct_nodes_positions() {
         let num_nodes = 5;
         let edges = vec![
-            Edge { source: 0, target: 1 },
-            Edge { source: 1, target: 2 },
-            Edge { source: 2, target: 3 },
-            Edge { source: 3, target: 4 },
-            Edge { source: 4, target: 0 },
+            Edge::new(0, 1),
+            Edge::new(1, 2),
+            Edge::new(2, 3),
+            Edge::new(3, 4),
+            Edge::new(4, 0),
         ];
-        let mut graph = new_graph(num_nodes, edges.clone());
+        let mut graph = new_graph(num_nodes, edges.clone(), &LayoutOptions::default(), &mut Rng::new(1));
 
-        multidimensional_scaling(&mut graph, 10);
+        multidimensional_scaling(&mut graph, 10, 0, &LayoutOptions::default(), &mut Rng::new(1));
 
         // Check that the graph has the correct number of nodes and edges
         assert_eq!(graph.nodes.len(), num_nodes);
@@ -604,17 +2490,17 @@ pub mod tests {
     #[test]
     fn circular_layout_check_if_coordinates_are_correct() {
         let nodes = vec![
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
+            Node::new(Point { x: 0.0, y: 0.0 }),
+            Node::new(Point { x: 0.0, y: 0.0 }),
+            Node::new(Point { x: 0.0, y: 0.0 }),
+            Node::new(Point { x: 0.0, y: 0.0 }),
         ];
         let edges = vec![];
         let mut graph = Graph { nodes, edges };
 
-        circular_layout(&mut graph);
+        circular_layout(&mut graph, CircularOrdering::Input, 1, &LayoutOptions::default());
 
-        let expected_positions = vec![
+        let expected_positions = [
             Point { x: 100.0, y: 50.0 },
             Point { x: 50.0, y: 100.0 },
             Point { x: 0.0, y: 50.0 },
@@ -635,16 +2521,16 @@ pub mod tests {
         // Create a graph with some nodes
     let mut graph = Graph {
         nodes: vec![
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
-            Node { position: Point { x: 0.0, y: 0.0 }, disp: Point { x: 0.0, y: 0.0 } },
+            Node::new(Point { x: 0.0, y: 0.0 }),
+            Node::new(Point { x: 0.0, y: 0.0 }),
+            Node::new(Point { x: 0.0, y: 0.0 }),
+            Node::new(Point { x: 0.0, y: 0.0 }),
         ],
         edges: vec![],
     };
 
         // Apply the random layout
-        random_layout(&mut graph);
+        random_layout(&mut graph, &LayoutOptions::default(), &mut Rng::new(1));
 
         // Check that all nodes have a position with x and y between 0 and 100
         for node in &graph.nodes {
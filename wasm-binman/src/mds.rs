@@ -0,0 +1,299 @@
+use crate::distance::{pivot_distances, shortest_path_distances, Adjacency};
+use crate::{Graph, Point, Rng};
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR CLASSICAL (TORGERSON) MULTIDIMENSIONAL SCALING
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Classical MDS places the nodes so that their Euclidean distances approximate the graph-theoretic distances:
+the matrix of squared distances is double centered, B = -1/2 * J * D^2 * J with J = I - 1/n,
+and the coordinates are the eigenvectors of the two largest eigenvalues of B scaled by the square roots of those.
+The eigenvectors are found by power iteration, the second one is kept orthogonal to the first. Unless the graph
+distances are Euclidean B also has negative eigenvalues, which can be larger in absolute value than the positive ones
+(e.g. for bipartite graphs), so the iteration runs on B + r * I, where r is the largest absolute eigenvalue of B.
+That matrix has the same eigenvectors and no negative eigenvalues, so its dominant ones are the largest ones of B.
+The pivot variant (PivotMDS by Brandes and Pich) only uses the distances from k pivots to all nodes,
+the k x n matrix C is double centered and the coordinates are C^T * u for the dominant eigenvectors u of C * C^T,
+which needs O(k * n) time and memory.
+The result is deterministic and is also used as the initial layout of stress majorization.
+When some nodes already have positions, the layout is moved, rotated, mirrored and scaled onto them as a whole
+(Procrustes analysis), so the other nodes start in the frame of the given drawing.
+*/
+
+// Maximum number of power iterations per eigenvector
+const POWER_ITERATIONS: usize = 1000;
+
+// Power iteration stops once an eigenvector changes less than this between two iterations
+const POWER_TOLERANCE: f64 = 1e-10;
+
+// Replace infinite distances (pairs in different components) by the largest finite distance
+fn bound_distances(rows: &mut [Vec<f64>]) {
+    let max_distance = rows
+        .iter()
+        .flat_map(|row| row.iter())
+        .copied()
+        .filter(|distance| distance.is_finite())
+        .fold(0.0, f64::max);
+    for distance in rows.iter_mut().flat_map(|row| row.iter_mut()) {
+        if !distance.is_finite() {
+            *distance = max_distance;
+        }
+    }
+}
+
+// Double center the matrix of squared distances, the result is -1/2 * J_rows * D^2 * J_columns
+fn double_center(rows: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let num_rows = rows.len();
+    let num_columns = rows.first().map_or(0, |row| row.len());
+    let squared: Vec<Vec<f64>> = rows.iter().map(|row| row.iter().map(|d| d * d).collect()).collect();
+    let row_means: Vec<f64> = squared.iter().map(|row| row.iter().sum::<f64>() / num_columns as f64).collect();
+    let column_means: Vec<f64> = (0..num_columns)
+        .map(|j| squared.iter().map(|row| row[j]).sum::<f64>() / num_rows as f64)
+        .collect();
+    let total_mean = row_means.iter().sum::<f64>() / num_rows as f64;
+    squared
+        .iter()
+        .zip(&row_means)
+        .map(|(row, row_mean)| {
+            row.iter()
+                .zip(&column_means)
+                .map(|(value, column_mean)| -0.5 * (value - row_mean - column_mean + total_mean))
+                .collect()
+        })
+        .collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(vector: &mut [f64]) -> f64 {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        for value in vector.iter_mut() {
+            *value /= norm;
+        }
+    }
+    norm
+}
+
+/*
+Dominant eigenpairs of a symmetric matrix given by its product with a vector.
+Each eigenvector is kept orthogonal to the ones found before (deflation).
+Returns the eigenvalues (Rayleigh quotients) and the unit eigenvectors.
+*/
+pub(crate) fn dominant_eigenvectors<F: Fn(&[f64]) -> Vec<f64>>(size: usize, count: usize, multiply: F) -> Vec<(f64, Vec<f64>)> {
+    let mut rng = Rng::new(size as u64);
+    let mut found: Vec<(f64, Vec<f64>)> = Vec::new();
+    for _ in 0..count {
+        let orthogonalize = |vector: &mut Vec<f64>, found: &[(f64, Vec<f64>)]| {
+            for (_, previous) in found {
+                let projection = dot(vector, previous);
+                for (value, p) in vector.iter_mut().zip(previous) {
+                    *value -= projection * p;
+                }
+            }
+        };
+
+        let mut vector: Vec<f64> = (0..size).map(|_| rng.next_f64() - 0.5).collect();
+        orthogonalize(&mut vector, &found);
+        normalize(&mut vector);
+        for _ in 0..POWER_ITERATIONS {
+            let mut next = multiply(&vector);
+            orthogonalize(&mut next, &found);
+            if normalize(&mut next) == 0.0 {
+                break;
+            }
+            let change = vector.iter().zip(&next).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+            vector = next;
+            if change < POWER_TOLERANCE {
+                break;
+            }
+        }
+        let eigenvalue = dot(&vector, &multiply(&vector));
+        found.push((eigenvalue, vector));
+    }
+    found
+}
+
+/*
+Classical MDS coordinates of all nodes, edge directions are ignored.
+With `pivots` > 0 (and fewer pivots than nodes) the pivot variant is used.
+Returns None when the graph has fewer than two nodes or all nodes end up at the same point.
+*/
+pub(crate) fn classical_mds(graph: &Graph, pivots: usize) -> Option<Vec<Point>> {
+    let num_nodes = graph.nodes.len();
+    if num_nodes < 2 {
+        return None;
+    }
+
+    let positions = if pivots == 0 || pivots >= num_nodes {
+        let mut distances = shortest_path_distances(graph, false);
+        bound_distances(&mut distances);
+        let b = double_center(&distances);
+        let radius = dominant_eigenvectors(num_nodes, 1, |vector| b.iter().map(|row| dot(row, vector)).collect())[0].0.abs();
+        let eigen = dominant_eigenvectors(num_nodes, 2, |vector| {
+            b.iter().zip(vector).map(|(row, value)| dot(row, vector) + radius * value).collect()
+        });
+        let scales: Vec<f64> = eigen.iter().map(|(eigenvalue, _)| (eigenvalue - radius).max(0.0).sqrt()).collect();
+        (0..num_nodes)
+            .map(|i| Point { x: eigen[0].1[i] * scales[0], y: eigen[1].1[i] * scales[1] })
+            .collect::<Vec<Point>>()
+    } else {
+        let (pivots, mut distances) = pivot_distances(&Adjacency::new(graph, false), pivots);
+        bound_distances(&mut distances);
+        let c = double_center(&distances);
+        // Eigenvectors of the small k x k matrix C * C^T, mapped back to the nodes with C^T
+        let eigen = dominant_eigenvectors(pivots.len(), 2, |vector| {
+            let projected: Vec<f64> = (0..num_nodes).map(|j| c.iter().zip(vector).map(|(row, v)| row[j] * v).sum()).collect();
+            c.iter().map(|row| dot(row, &projected)).collect()
+        });
+        let axis = |u: &[f64]| -> Vec<f64> { (0..num_nodes).map(|j| c.iter().zip(u).map(|(row, v)| row[j] * v).sum()).collect() };
+        let (x, y) = (axis(&eigen[0].1), axis(&eigen[1].1));
+        let mut positions: Vec<Point> = x.into_iter().zip(y).map(|(x, y)| Point { x, y }).collect();
+
+        // The axes are only known up to scale, fit the scale to the distances from the pivots (least squares)
+        let (mut numerator, mut denominator) = (0.0, 0.0);
+        for (&pivot, row) in pivots.iter().zip(&distances) {
+            for (position, &distance) in positions.iter().zip(row) {
+                let length = position.distance(positions[pivot]);
+                numerator += length * distance;
+                denominator += length * length;
+            }
+        }
+        let scale = if denominator > 0.0 { numerator / denominator } else { 0.0 };
+        for position in &mut positions {
+            position.x *= scale;
+            position.y *= scale;
+        }
+        positions
+    };
+
+    let spread = positions.iter().any(|p| (p.x - positions[0].x).abs() > 1e-12 || (p.y - positions[0].y).abs() > 1e-12);
+    if spread && positions.iter().all(|p| p.x.is_finite() && p.y.is_finite()) {
+        Some(positions)
+    } else {
+        None
+    }
+}
+
+/*
+Move, rotate, possibly mirror and scale `positions` as a whole so that the nodes in `anchors` come as close as possible
+to their given points (least squares). A single anchor, or anchors all at the same position, only moves the layout.
+*/
+pub(crate) fn align_to_anchors(positions: &mut [Point], anchors: &[(usize, Point)]) {
+    if anchors.is_empty() {
+        return;
+    }
+    let count = anchors.len() as f64;
+    let (mut from, mut to) = (Point { x: 0.0, y: 0.0 }, Point { x: 0.0, y: 0.0 });
+    for &(node, anchor) in anchors {
+        from = Point { x: from.x + positions[node].x / count, y: from.y + positions[node].y / count };
+        to = Point { x: to.x + anchor.x / count, y: to.y + anchor.y / count };
+    }
+
+    // Cross covariance of the centered positions and their anchors
+    let (mut xx, mut xy, mut yx, mut yy, mut spread) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for &(node, anchor) in anchors {
+        let (a, b) = (Point { x: positions[node].x - from.x, y: positions[node].y - from.y }, Point { x: anchor.x - to.x, y: anchor.y - to.y });
+        xx += a.x * b.x;
+        xy += a.x * b.y;
+        yx += a.y * b.x;
+        yy += a.y * b.y;
+        spread += a.x * a.x + a.y * a.y;
+    }
+    // Best rotation of the layout as it is and of the layout mirrored at the x axis, the better one is used
+    let (rotated, mirrored) = ((xx + yy).hypot(xy - yx), (xx - yy).hypot(xy + yx));
+    let (mirror, angle) = if mirrored > rotated { (-1.0, (xy + yx).atan2(xx - yy)) } else { (1.0, (xy - yx).atan2(xx + yy)) };
+    let scale = if spread > 0.0 { rotated.max(mirrored) / spread } else { 1.0 };
+
+    let (sin, cos) = angle.sin_cos();
+    for position in positions.iter_mut() {
+        let (x, y) = (position.x - from.x, mirror * (position.y - from.y));
+        *position = Point { x: to.x + scale * (cos * x - sin * y), y: to.y + scale * (sin * x + cos * y) };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+
+    #[test]
+    fn dominant_eigenvectors_check_diagonal_matrix() {
+        let diagonal = [1.0, 5.0, 3.0];
+
+        let eigen = dominant_eigenvectors(3, 2, |vector| vector.iter().zip(&diagonal).map(|(v, d)| v * d).collect());
+
+        assert!((eigen[0].0 - 5.0).abs() < 1e-6 && (eigen[1].0 - 3.0).abs() < 1e-6);
+        assert!(eigen[0].1[1].abs() > 0.999 && eigen[1].1[2].abs() > 0.999);
+    }
+
+    #[test]
+    fn classical_mds_check_if_cycle_distances_are_reproduced() {
+        let cycle = graph(4, &[(0, 1), (1, 2), (2, 3), (3, 0)]);
+
+        let positions = classical_mds(&cycle, 0).unwrap();
+
+        // A 4-cycle becomes a square whose diagonals have their graph distance 2, the sides are stretched to sqrt(2)
+        for i in 0..4 {
+            let side = positions[i].distance(positions[(i + 1) % 4]);
+            assert!((side - 2.0_f64.sqrt()).abs() < 1e-6, "side {}", side);
+        }
+        assert!((positions[0].distance(positions[2]) - 2.0).abs() < 1e-6);
+        assert!((positions[1].distance(positions[3]) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn classical_mds_check_if_path_is_a_line() {
+        let path = graph(5, &(0..4).map(|i| (i, i + 1)).collect::<Vec<_>>());
+
+        let positions = classical_mds(&path, 0).unwrap();
+        let pivoted = classical_mds(&path, 3).unwrap();
+
+        for (i, j) in [(0, 4), (1, 3), (0, 2)] {
+            assert!((positions[i].distance(positions[j]) - (j - i) as f64).abs() < 1e-6);
+            assert!((pivoted[i].distance(pivoted[j]) - (j - i) as f64).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn classical_mds_check_if_bipartite_graph_spans_both_axes() {
+        // K3,3 has the eigenvalue -2.5 besides 2.0, the axes of the negative eigenvalues must not be used
+        let edges: Vec<(usize, usize)> = (0..3).flat_map(|i| (3..6).map(move |j| (i, j))).collect();
+        let complete_bipartite = graph(6, &edges);
+
+        let positions = classical_mds(&complete_bipartite, 0).unwrap();
+
+        let extent = |coordinate: fn(&Point) -> f64| {
+            let values = positions.iter().map(coordinate);
+            values.clone().fold(f64::NEG_INFINITY, f64::max) - values.fold(f64::INFINITY, f64::min)
+        };
+        assert!(extent(|p| p.x) > 0.5 && extent(|p| p.y) > 0.5, "{:?}", positions);
+    }
+
+    #[test]
+    fn classical_mds_check_degenerate_graphs() {
+        assert!(classical_mds(&graph(1, &[]), 0).is_none());
+        assert!(classical_mds(&graph(3, &[]), 0).is_none());
+    }
+
+    #[test]
+    fn align_to_anchors_check_if_similar_layout_is_recovered() {
+        // An L shape, and the same shape mirrored, turned by 90 degrees, doubled and moved to (10, 20)
+        let shape = [(0.0, 0.0), (2.0, 0.0), (0.0, 1.0), (1.0, 1.0)];
+        let target: Vec<Point> = shape.iter().map(|&(x, y)| Point { x: 10.0 + 2.0 * y, y: 20.0 + 2.0 * x }).collect();
+        let points = || shape.iter().map(|&(x, y)| Point { x, y }).collect::<Vec<Point>>();
+        let (mut aligned, mut moved) = (points(), points());
+
+        align_to_anchors(&mut aligned, &[(0, target[0]), (1, target[1]), (2, target[2])]);
+        align_to_anchors(&mut moved, &[(1, Point { x: 5.0, y: 5.0 })]);
+
+        for (position, expected) in aligned.iter().zip(&target) {
+            assert!(position.distance(*expected) < 1e-9, "{:?}", aligned);
+        }
+        assert_eq!((moved[0].x, moved[0].y, moved[1].x, moved[1].y), (3.0, 5.0, 5.0, 5.0));
+    }
+}
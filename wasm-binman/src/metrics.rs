@@ -0,0 +1,216 @@
+use crate::stress::StressModel;
+use crate::{Graph, Point};
+use serde::Serialize;
+use std::collections::HashSet;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR MEASURING THE QUALITY OF A LAYOUT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Quality measures of a drawing, so layouts can be compared by numbers instead of by eye:
+- edge crossings: pairs of edges without a common node whose segments cross (lower is better)
+- normalized stress: stress after the best uniform scaling divided by the number of node pairs, see stress.rs (lower is better)
+- edge length variance: variance of the edge lengths divided by their squared mean, independent of the scale (lower is better)
+- angular resolution: smallest angle in degrees between two edges at the same node (higher is better)
+- node overlaps: pairs of nodes closer than twice the node radius (lower is better)
+- neighborhood preservation: how many of the graph neighbors of a node are also its nearest nodes in the drawing,
+  the Jaccard index averaged over the nodes (1 is best)
+Edge directions are ignored and self loops do not count. The crossings, the overlaps and the neighborhoods
+compare every pair of edges or nodes (crossings and overlaps skip pairs whose x ranges do not meet),
+so they are meant for graphs of up to some thousands of nodes.
+*/
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct LayoutMetrics {
+    pub(crate) edge_crossings: usize,
+    pub(crate) normalized_stress: f64,
+    pub(crate) edge_length_variance: f64,
+    // None when no node has two edges
+    pub(crate) angular_resolution: Option<f64>,
+    pub(crate) node_overlaps: usize,
+    pub(crate) neighborhood_preservation: f64,
+}
+
+// Cross product of b - a and c - a, positive when a, b, c turn counterclockwise
+fn orientation(a: Point, b: Point, c: Point) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+// Whether the segments p1-p2 and q1-q2 cross in a single point inside both of them
+fn segments_cross(p1: Point, p2: Point, q1: Point, q2: Point) -> bool {
+    let d1 = orientation(q1, q2, p1);
+    let d2 = orientation(q1, q2, p2);
+    let d3 = orientation(p1, p2, q1);
+    let d4 = orientation(p1, p2, q2);
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+// Distinct neighbors of every node, edge directions and self loops are ignored
+fn neighbor_sets(graph: &Graph) -> Vec<HashSet<usize>> {
+    let mut neighbors = vec![HashSet::new(); graph.nodes.len()];
+    for edge in graph.edges.iter().filter(|edge| edge.source != edge.target) {
+        neighbors[edge.source].insert(edge.target);
+        neighbors[edge.target].insert(edge.source);
+    }
+    neighbors
+}
+
+fn edge_crossings(graph: &Graph, positions: &[Point]) -> usize {
+    let mut segments: Vec<(usize, usize, f64, f64)> = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.source != edge.target)
+        .map(|edge| {
+            let (a, b) = (positions[edge.source].x, positions[edge.target].x);
+            (edge.source, edge.target, a.min(b), a.max(b))
+        })
+        .collect();
+    segments.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let mut crossings = 0;
+    for (k, &(s1, t1, _, max_x)) in segments.iter().enumerate() {
+        for &(s2, t2, min_x, _) in &segments[k + 1..] {
+            if min_x > max_x {
+                break;
+            }
+            let shares_node = s1 == s2 || s1 == t2 || t1 == s2 || t1 == t2;
+            if !shares_node && segments_cross(positions[s1], positions[t1], positions[s2], positions[t2]) {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+fn edge_length_variance(graph: &Graph, positions: &[Point]) -> f64 {
+    let lengths: Vec<f64> = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.source != edge.target)
+        .map(|edge| positions[edge.source].distance(positions[edge.target]))
+        .collect();
+    let mean = lengths.iter().sum::<f64>() / lengths.len() as f64;
+    if lengths.is_empty() || mean == 0.0 {
+        return 0.0;
+    }
+    let variance = lengths.iter().map(|length| (length - mean).powi(2)).sum::<f64>() / lengths.len() as f64;
+    variance / (mean * mean)
+}
+
+fn angular_resolution(neighbors: &[HashSet<usize>], positions: &[Point]) -> Option<f64> {
+    let mut resolution: Option<f64> = None;
+    for (node, node_neighbors) in neighbors.iter().enumerate() {
+        if node_neighbors.len() < 2 {
+            continue;
+        }
+        let center = positions[node];
+        let mut angles: Vec<f64> = node_neighbors
+            .iter()
+            .map(|&neighbor| (positions[neighbor].y - center.y).atan2(positions[neighbor].x - center.x).to_degrees())
+            .collect();
+        angles.sort_by(f64::total_cmp);
+        let wrap_around = angles[0] + 360.0 - angles[angles.len() - 1];
+        let smallest = angles.windows(2).map(|pair| pair[1] - pair[0]).fold(wrap_around, f64::min);
+        resolution = Some(resolution.map_or(smallest, |resolution| resolution.min(smallest)));
+    }
+    resolution
+}
+
+fn node_overlaps(positions: &[Point], node_radius: f64) -> usize {
+    let mut sorted: Vec<Point> = positions.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x));
+    let min_distance = 2.0 * node_radius;
+    let mut overlaps = 0;
+    for (k, &a) in sorted.iter().enumerate() {
+        for &b in &sorted[k + 1..] {
+            if b.x - a.x >= min_distance {
+                break;
+            }
+            if a.distance(b) < min_distance {
+                overlaps += 1;
+            }
+        }
+    }
+    overlaps
+}
+
+fn neighborhood_preservation(neighbors: &[HashSet<usize>], positions: &[Point]) -> f64 {
+    let (mut total, mut counted) = (0.0, 0);
+    for (node, node_neighbors) in neighbors.iter().enumerate() {
+        let degree = node_neighbors.len();
+        if degree == 0 {
+            continue;
+        }
+        let mut others: Vec<(f64, usize)> = (0..positions.len())
+            .filter(|&other| other != node)
+            .map(|other| (positions[node].distance(positions[other]), other))
+            .collect();
+        others.select_nth_unstable_by(degree - 1, |a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let shared = others[..degree].iter().filter(|(_, other)| node_neighbors.contains(other)).count();
+        total += shared as f64 / (2 * degree - shared) as f64;
+        counted += 1;
+    }
+    if counted == 0 {
+        1.0
+    } else {
+        total / counted as f64
+    }
+}
+
+
+// Measure the drawing given by the current node positions, `node_radius` is the radius of a drawn node
+pub(crate) fn layout_metrics(graph: &Graph, node_radius: f64) -> LayoutMetrics {
+    let positions: Vec<Point> = graph.nodes.iter().map(|node| node.position).collect();
+    let neighbors = neighbor_sets(graph);
+    LayoutMetrics {
+        edge_crossings: edge_crossings(graph, &positions),
+        normalized_stress: StressModel::new(graph, 0).normalized_stress(&positions),
+        edge_length_variance: edge_length_variance(graph, &positions),
+        angular_resolution: angular_resolution(&neighbors, &positions),
+        node_overlaps: node_overlaps(&positions, node_radius),
+        neighborhood_preservation: neighborhood_preservation(&neighbors, &positions),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph_at;
+
+    #[test]
+    fn layout_metrics_check_square_and_crossed_square() {
+        let square = graph_at(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)], &[(0, 1), (1, 2), (2, 3), (3, 0)]);
+        let crossed = graph_at(&[(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)], &[(0, 1), (1, 2), (2, 3), (3, 0)]);
+
+        let good = layout_metrics(&square, 0.1);
+        let bad = layout_metrics(&crossed, 0.1);
+
+        assert_eq!(good.edge_crossings, 0);
+        assert_eq!(bad.edge_crossings, 1);
+        assert!(good.edge_length_variance.abs() < 1e-12);
+        assert!(bad.edge_length_variance > 0.01);
+        assert!((good.angular_resolution.unwrap() - 90.0).abs() < 1e-9);
+        assert!((bad.angular_resolution.unwrap() - 45.0).abs() < 1e-9);
+        assert!(good.normalized_stress < bad.normalized_stress);
+        assert_eq!(good.neighborhood_preservation, 1.0);
+        assert!(bad.neighborhood_preservation < 1.0);
+        assert_eq!((good.node_overlaps, layout_metrics(&square, 0.6).node_overlaps), (0, 4));
+    }
+
+    #[test]
+    fn layout_metrics_check_degenerate_graphs() {
+        let single = graph_at(&[(3.0, 4.0)], &[(0, 0)]);
+        let path = graph_at(&[(0.0, 0.0), (1.0, 0.0), (5.0, 0.0)], &[(0, 1), (1, 2), (1, 0)]);
+
+        let metrics = layout_metrics(&single, 1.0);
+        let path_metrics = layout_metrics(&path, 1.0);
+
+        assert_eq!(metrics.angular_resolution, None);
+        assert_eq!((metrics.edge_crossings, metrics.node_overlaps), (0, 0));
+        assert_eq!((metrics.normalized_stress, metrics.neighborhood_preservation), (0.0, 1.0));
+        assert!((path_metrics.angular_resolution.unwrap() - 180.0).abs() < 1e-9);
+        assert_eq!(path_metrics.node_overlaps, 1);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::distance::Adjacency;
+use crate::{Edge, Graph, Node, Point, Rng};
+use std::collections::HashMap;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR COARSENING AND PROLONGATION OF THE MULTILEVEL LAYOUT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+A force-directed layout of a large graph needs many iterations to untangle it, since every iteration only moves
+nodes a short distance. The multilevel scheme (Walshaw) builds a hierarchy of smaller and smaller graphs
+by merging the endpoints of a maximal matching, lays out the coarsest graph from scratch and then goes back
+level by level: every node starts at the position of the node it was merged into and the layout is refined
+with a few iterations. The global shape is found on the small graphs where it is cheap,
+the large graphs only need local corrections.
+Matching prefers heavy edges, so strongly attracting nodes are merged first. Pinned nodes are never merged.
+A coarse node weighs as much as its members together and repels the other nodes as strongly as they would,
+so the coarse layouts take the space the finer graphs need. The finer levels start at REFINEMENT_TEMPERATURE,
+a full temperature would let the first iterations shake up the layout found on the coarser levels.
+*/
+
+// Graphs with at most this many nodes are not coarsened any further
+const COARSEST_SIZE: usize = 8;
+
+// Coarsening stops when a level keeps more than this fraction of the nodes (e.g. stars, where a matching has one edge)
+const MIN_REDUCTION: f64 = 0.8;
+
+// Starting temperature of the finer levels, a fraction of the largest step of the coarsest level
+pub(crate) const REFINEMENT_TEMPERATURE: f64 = 0.3;
+
+/*
+Merge the endpoints of a maximal matching into single nodes. Nodes are visited in random order and
+matched with their unmatched neighbor of largest edge weight. Returns the coarse graph and for every node
+of `graph` the index of the coarse node it was merged into, or None when coarsening would not pay off.
+Coarse nodes start at the mean position of their members and add up their masses,
+parallel edges are merged by adding their weights.
+*/
+pub(crate) fn coarsen(graph: &Graph, rng: &mut Rng) -> Option<(Graph, Vec<usize>)> {
+    let num_nodes = graph.nodes.len();
+    if num_nodes <= COARSEST_SIZE {
+        return None;
+    }
+    let adjacency = Adjacency::new(graph, false);
+
+    let mut order: Vec<usize> = (0..num_nodes).collect();
+    for i in (1..num_nodes).rev() {
+        let j = (rng.next_f64() * (i + 1) as f64) as usize;
+        order.swap(i, j);
+    }
+
+    let mut parent = vec![usize::MAX; num_nodes];
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    for node in order {
+        if parent[node] != usize::MAX {
+            continue;
+        }
+        parent[node] = members.len();
+        let mut group = vec![node];
+        if !graph.nodes[node].is_pinned() {
+            let partner = adjacency
+                .neighbors(node)
+                .filter(|&(other, _)| parent[other] == usize::MAX && !graph.nodes[other].is_pinned())
+                .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)));
+            if let Some((partner, _)) = partner {
+                parent[partner] = members.len();
+                group.push(partner);
+            }
+        }
+        members.push(group);
+    }
+    if members.len() as f64 > MIN_REDUCTION * num_nodes as f64 {
+        return None;
+    }
+
+    let nodes = members
+        .iter()
+        .map(|group| {
+            let sum = group.iter().fold(Point { x: 0.0, y: 0.0 }, |sum, &member| {
+                let position = graph.nodes[member].position;
+                Point { x: sum.x + position.x, y: sum.y + position.y }
+            });
+            let mut node = Node::new(Point { x: sum.x / group.len() as f64, y: sum.y / group.len() as f64 });
+            node.mass = group.iter().map(|&member| graph.nodes[member].mass).sum();
+            // Pinned nodes are never merged, so a pinned coarse node has a single member
+            node.pinned = graph.nodes[group[0]].pinned;
+            node
+        })
+        .collect();
+
+    let mut weights: HashMap<(usize, usize), f64> = HashMap::new();
+    for edge in &graph.edges {
+        let (source, target) = (parent[edge.source], parent[edge.target]);
+        if source != target {
+            *weights.entry((source.min(target), source.max(target))).or_insert(0.0) += edge.weight;
+        }
+    }
+    let mut edges: Vec<Edge> = weights
+        .into_iter()
+        .map(|((source, target), weight)| {
+            let mut edge = Edge::new(source, target);
+            edge.weight = weight;
+            edge
+        })
+        .collect();
+    edges.sort_by_key(|edge| (edge.source, edge.target));
+
+    Some((Graph { nodes, edges }, parent))
+}
+
+/*
+Move every node of the finer graph to the position of its coarse node, shifted by a random offset of at most
+`jitter` in each direction so that merged nodes do not coincide (forces between coinciding nodes vanish).
+Pinned nodes stay at their fixed coordinates and nodes with an initial position start there.
+*/
+pub(crate) fn prolong(fine: &mut Graph, coarse: &Graph, parent: &[usize], jitter: f64, rng: &mut Rng) {
+    for (node, &coarse_node) in fine.nodes.iter_mut().zip(parent) {
+        let center = coarse.nodes[coarse_node].position;
+        let position = Point {
+            x: center.x + (rng.next_f64() * 2.0 - 1.0) * jitter,
+            y: center.y + (rng.next_f64() * 2.0 - 1.0) * jitter,
+        };
+        node.position = node.pinned.or(node.initial).unwrap_or(position);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+
+    #[test]
+    fn coarsen_check_if_matching_halves_a_path() {
+        let edges: Vec<(usize, usize)> = (0..15).map(|i| (i, i + 1)).collect();
+        let mut path = graph(16, &edges);
+        path.nodes[5].pinned = Some(Point { x: 5.0, y: 0.0 });
+
+        let (coarse, parent) = coarsen(&path, &mut Rng::new(3)).unwrap();
+
+        assert!(coarse.nodes.len() >= 8 && coarse.nodes.len() <= 11, "{}", coarse.nodes.len());
+        // Every coarse node has at most two members, which are neighbors on the path
+        for group in 0..coarse.nodes.len() {
+            let members: Vec<usize> = (0..16).filter(|&node| parent[node] == group).collect();
+            assert!(members.len() == 1 || (members.len() == 2 && members[1] - members[0] == 1), "{:?}", members);
+            let mean = members.iter().map(|&node| node as f64).sum::<f64>() / members.len() as f64;
+            assert_eq!(coarse.nodes[group].position.x, mean);
+            assert_eq!(coarse.nodes[group].mass, members.len() as f64);
+        }
+        assert!((0..16).all(|node| node == 5 || parent[node] != parent[5]));
+        assert!(coarse.nodes[parent[5]].is_pinned());
+        assert_eq!(coarse.edges.len(), coarse.nodes.len() - 1);
+    }
+
+    #[test]
+    fn coarsen_check_if_parallel_edges_are_merged_and_stars_stop() {
+        // The heavy edges 0-1 and 2-3 are always matched, the light edges 0-2 and 1-3 then become one coarse edge
+        let mut ladder = graph(10, &[(0, 1), (2, 3), (0, 2), (1, 3), (4, 5), (6, 7), (8, 9), (4, 6), (5, 7), (8, 4)]);
+        for i in [0, 1, 4, 5, 6] {
+            ladder.edges[i].weight = 10.0;
+        }
+        let star = graph(12, &(1..12).map(|i| (0, i)).collect::<Vec<_>>());
+
+        let (coarse, parent) = coarsen(&ladder, &mut Rng::new(1)).unwrap();
+
+        assert_eq!(parent[0], parent[1]);
+        assert_eq!(parent[2], parent[3]);
+        let pair = (parent[0].min(parent[2]), parent[0].max(parent[2]));
+        let edge = coarse.edges.iter().find(|edge| (edge.source, edge.target) == pair).unwrap();
+        assert_eq!(edge.weight, 2.0);
+        assert!(coarsen(&star, &mut Rng::new(1)).is_none());
+    }
+
+    #[test]
+    fn prolong_check_if_nodes_follow_their_coarse_node() {
+        let mut fine = graph(4, &[(0, 1), (1, 2), (2, 3)]);
+        fine.nodes[2].pinned = Some(Point { x: 50.0, y: 50.0 });
+        fine.nodes[3].initial = Some(Point { x: 20.0, y: 10.0 });
+        let coarse = graph(2, &[(0, 1)]);
+
+        prolong(&mut fine, &coarse, &[1, 1, 0, 0], 0.5, &mut Rng::new(9));
+
+        assert!((fine.nodes[0].position.x - 1.0).abs() <= 0.5 && fine.nodes[0].position.y.abs() <= 0.5);
+        assert!((fine.nodes[1].position.x - 1.0).abs() <= 0.5);
+        assert_ne!(fine.nodes[0].position.x, fine.nodes[1].position.x);
+        assert_eq!((fine.nodes[2].position.x, fine.nodes[2].position.y), (50.0, 50.0));
+        assert_eq!((fine.nodes[3].position.x, fine.nodes[3].position.y), (20.0, 10.0));
+    }
+}
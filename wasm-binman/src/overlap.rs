@@ -0,0 +1,217 @@
+use crate::stress::StressModel;
+use crate::{Graph, Point, Size};
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR REMOVING NODE OVERLAPS AFTER A LAYOUT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+Overlap removal by PRISM (Gansner and Hu), which works after any layout. Every node is a box of its size
+centered at its position. Each round builds a proximity graph and a stress function on its edges:
+a pair of overlapping boxes should move apart along the line through their centers by the factor that just
+separates them (at most MAX_EXPANSION per round), every other pair should keep its current distance.
+Minimizing that stress (see stress.rs) removes overlaps while keeping the relative positions of nearby nodes,
+so the drawing keeps its shape. Rounds continue until no two boxes overlap.
+PRISM uses the Delaunay triangulation as proximity graph, here it is approximated by the nearest neighbors of every
+node, and all overlapping pairs found by a scan line are added to it.
+Pinned nodes keep their positions (two pinned nodes may overlap) and nodes of size 0 never overlap.
+Finding the nearest neighbors compares every pair of nodes, so this is meant for graphs of up to some thousands of nodes.
+*/
+
+// Number of nearest neighbors of every node in the proximity graph
+const NEAREST_NEIGHBORS: usize = 6;
+
+// Largest factor by which the distance of an overlapping pair is stretched in one round
+const MAX_EXPANSION: f64 = 1.5;
+
+// Rounds stop after this many even if some boxes still overlap
+const MAX_ROUNDS: usize = 100;
+
+// Stress majorization per round, the next round starts from a fresh proximity graph anyway
+const SMACOF_ITERATIONS: usize = 10;
+const SMACOF_TOLERANCE: f64 = 1e-4;
+
+// Boxes overlapping by less than this fraction of their size do not count, so rounding errors do not cause endless rounds
+const OVERLAP_TOLERANCE: f64 = 1e-6;
+
+/*
+Factor by which the distance between the centers has to grow for the boxes to just touch, along the axis
+where that takes the least. Values above 1 mean the boxes overlap, boxes with the same center give infinity.
+*/
+fn overlap_factor(a: Point, a_size: Size, b: Point, b_size: Size) -> f64 {
+    let half_width = (a_size.width + b_size.width) / 2.0;
+    let half_height = (a_size.height + b_size.height) / 2.0;
+    if half_width <= 0.0 || half_height <= 0.0 {
+        return 0.0;
+    }
+    let factor = |gap: f64, half: f64| if gap > 0.0 { half / gap } else { f64::INFINITY };
+    factor((a.x - b.x).abs(), half_width).min(factor((a.y - b.y).abs(), half_height))
+}
+
+/*
+Pairs of overlapping boxes, pairs of two fixed nodes are left out as they cannot be separated.
+A scan line over the left edges of the boxes only compares boxes whose x ranges meet.
+*/
+fn overlapping_pairs(positions: &[Point], sizes: &[Size], fixed: &[bool]) -> Vec<(usize, usize)> {
+    let mut by_left: Vec<usize> = (0..positions.len()).collect();
+    let left = |node: usize| positions[node].x - sizes[node].width / 2.0;
+    by_left.sort_by(|&a, &b| left(a).total_cmp(&left(b)));
+
+    let mut pairs = Vec::new();
+    for (k, &a) in by_left.iter().enumerate() {
+        let right = positions[a].x + sizes[a].width / 2.0;
+        for &b in &by_left[k + 1..] {
+            if left(b) >= right {
+                break;
+            }
+            if !(fixed[a] && fixed[b]) && overlap_factor(positions[a], sizes[a], positions[b], sizes[b]) > 1.0 + OVERLAP_TOLERANCE {
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+    pairs
+}
+
+// Pairs of every node with its nearest neighbors, each pair listed once with the smaller index first
+fn nearest_pairs(positions: &[Point]) -> Vec<(usize, usize)> {
+    let num_nodes = positions.len();
+    let count = NEAREST_NEIGHBORS.min(num_nodes.saturating_sub(1));
+    let mut pairs = Vec::new();
+    if count == 0 {
+        return pairs;
+    }
+    for (i, &position) in positions.iter().enumerate() {
+        let mut others: Vec<(f64, usize)> = (0..num_nodes)
+            .filter(|&j| j != i)
+            .map(|j| ((positions[j].x - position.x).powi(2) + (positions[j].y - position.y).powi(2), j))
+            .collect();
+        others.select_nth_unstable_by(count - 1, |a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        pairs.extend(others[..count].iter().map(|&(_, j)| (i.min(j), i.max(j))));
+    }
+    pairs
+}
+
+/*
+Nodes of positive size at exactly the same position as an earlier node are moved by a tiny offset
+in a different direction each, otherwise the stress function could not tell in which direction to separate them.
+*/
+fn separate_coincident(positions: &mut [Point], sizes: &[Size], fixed: &[bool]) {
+    let mut order: Vec<usize> = (0..positions.len()).collect();
+    order.sort_by(|&a, &b| positions[a].x.total_cmp(&positions[b].x).then(positions[a].y.total_cmp(&positions[b].y)));
+    let mut duplicates = 0;
+    for k in 1..order.len() {
+        let (previous, node) = (positions[order[k - 1]], order[k]);
+        if positions[node].x != previous.x || positions[node].y != previous.y {
+            duplicates = 0;
+            continue;
+        }
+        duplicates += 1;
+        if !fixed[node] && sizes[node].width + sizes[node].height > 0.0 {
+            // Golden angle steps spread the offsets of many coinciding nodes evenly
+            let angle = duplicates as f64 * 2.399_963_229_728_653;
+            let offset = 1e-3 * (sizes[node].width + sizes[node].height);
+            positions[node].x += offset * angle.cos();
+            positions[node].y += offset * angle.sin();
+        }
+    }
+}
+
+/*
+Move the nodes apart until no two of their boxes overlap, see the comment at the top of the file.
+Returns the number of overlapping pairs left, 0 unless only pinned nodes overlap or MAX_ROUNDS was not enough.
+*/
+pub(crate) fn remove_overlaps(graph: &mut Graph) -> usize {
+    let num_nodes = graph.nodes.len();
+    let sizes: Vec<Size> = graph.nodes.iter().map(|node| node.size).collect();
+    let fixed: Vec<bool> = graph.nodes.iter().map(|node| node.is_pinned()).collect();
+    let mut positions: Vec<Point> = graph.nodes.iter().map(|node| node.position).collect();
+    separate_coincident(&mut positions, &sizes, &fixed);
+
+    for _ in 0..MAX_ROUNDS {
+        let overlapping = overlapping_pairs(&positions, &sizes, &fixed);
+        if overlapping.is_empty() {
+            break;
+        }
+        let mut pairs = nearest_pairs(&positions);
+        pairs.extend(overlapping);
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        let targets: Vec<(usize, usize, f64)> = pairs
+            .into_iter()
+            .map(|(i, j)| {
+                let (p, q) = (positions[i], positions[j]);
+                let expansion = overlap_factor(p, sizes[i], q, sizes[j]).clamp(1.0, MAX_EXPANSION);
+                (i, j, expansion * p.distance(q))
+            })
+            .collect();
+        StressModel::from_pairs(num_nodes, &targets).smacof(&mut positions, &fixed, SMACOF_ITERATIONS, SMACOF_TOLERANCE);
+    }
+
+    for (node, position) in graph.nodes.iter_mut().zip(&positions) {
+        node.position = node.pinned.unwrap_or(*position);
+    }
+    overlapping_pairs(&positions, &sizes, &fixed).len()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph_at;
+
+    // Square boxes of the given side at the given positions
+    fn boxes(positions: &[(f64, f64)], side: f64) -> Graph {
+        let mut graph = graph_at(positions, &[]);
+        for node in &mut graph.nodes {
+            node.size = Size { width: side, height: side };
+        }
+        graph
+    }
+
+    #[test]
+    fn overlap_factor_check_boxes() {
+        let size = Size { width: 2.0, height: 2.0 };
+        let wide = Size { width: 6.0, height: 2.0 };
+
+        assert_eq!(overlap_factor(Point { x: 0.0, y: 0.0 }, size, Point { x: 1.0, y: 0.0 }, size), 2.0);
+        assert_eq!(overlap_factor(Point { x: 0.0, y: 0.0 }, size, Point { x: 1.0, y: 0.5 }, wide), 4.0);
+        assert_eq!(overlap_factor(Point { x: 0.0, y: 0.0 }, size, Point { x: 3.0, y: 3.0 }, size), 2.0 / 3.0);
+        assert_eq!(overlap_factor(Point { x: 1.0, y: 1.0 }, size, Point { x: 1.0, y: 1.0 }, size), f64::INFINITY);
+        assert_eq!(overlap_factor(Point { x: 1.0, y: 1.0 }, Size { width: 0.0, height: 0.0 }, Point { x: 1.0, y: 1.0 }, Size { width: 0.0, height: 0.0 }), 0.0);
+    }
+
+    #[test]
+    fn remove_overlaps_check_if_grid_spreads_and_keeps_its_shape() {
+        // A 5 x 5 grid with spacing 1 of boxes of side 2, a pinned node in the middle and two nodes on top of each other
+        let mut positions: Vec<(f64, f64)> = (0..25).map(|i| ((i % 5) as f64, (i / 5) as f64)).collect();
+        positions.push((0.0, 0.0));
+        let mut grid = boxes(&positions, 2.0);
+        grid.nodes[12].pinned = Some(Point { x: 2.0, y: 2.0 });
+
+        let remaining = remove_overlaps(&mut grid);
+
+        let positions: Vec<Point> = grid.nodes.iter().map(|node| node.position).collect();
+        let sizes: Vec<Size> = grid.nodes.iter().map(|node| node.size).collect();
+        assert_eq!(remaining, 0);
+        assert!(overlapping_pairs(&positions, &sizes, &[false; 26]).is_empty());
+        assert_eq!((positions[12].x, positions[12].y), (2.0, 2.0));
+        // Left stays left of right and top above bottom
+        for row in 0..5 {
+            assert!(positions[row * 5].x < positions[row * 5 + 4].x);
+            assert!(positions[row].y < positions[20 + row].y);
+        }
+    }
+
+    #[test]
+    fn remove_overlaps_check_if_separate_nodes_do_not_move() {
+        let mut apart = boxes(&[(0.0, 0.0), (5.0, 0.0), (0.0, 5.0)], 2.0);
+        let mut points = boxes(&[(1.0, 1.0), (1.0, 1.0)], 0.0);
+
+        assert_eq!(remove_overlaps(&mut apart), 0);
+        assert_eq!(remove_overlaps(&mut points), 0);
+
+        assert_eq!((apart.nodes[1].position.x, apart.nodes[1].position.y), (5.0, 0.0));
+        assert_eq!((points.nodes[1].position.x, points.nodes[1].position.y), (1.0, 1.0));
+    }
+}
@@ -0,0 +1,568 @@
+use crate::{Edge, Point, Size};
+use std::collections::HashMap;
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR PARSING GRAPHS GIVEN IN THE TEXT FORMAT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+The text format is a comma separated list of edges, e.g. "alice-bob,bob->carol:2.5".
+"-" separates the nodes of an undirected edge and "->" those of a directed one, ":weight" is optional.
+Node ids are arbitrary strings. Ids containing whitespace or one of the characters , ; : - > " have to be quoted,
+e.g. "\"New York\"-Boston", inside quotes \" and \\ stand for a quote and a backslash.
+*/
+
+// Reasons why a graph string can be rejected
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ParseErrorReason {
+    EmptyGraph,
+    EmptyEdge,
+    MissingSeparator,
+    TooManySeparators,
+    UnexpectedText,
+    InvalidNodeId,
+    InvalidWeight,
+    InvalidPin,
+    InvalidPosition,
+    InvalidCoordinate,
+    UnknownNode,
+    InvalidLayout,
+    InvalidSize,
+    InvalidDimension,
+}
+
+// Define ParseError structure (position is the byte offset of the offending token in the input)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParseError {
+    pub(crate) position: usize,
+    pub(crate) token: String,
+    pub(crate) reason: ParseErrorReason,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self.reason {
+            ParseErrorReason::EmptyGraph => "graph does not contain any edges",
+            ParseErrorReason::EmptyEdge => "empty edge between two commas",
+            ParseErrorReason::MissingSeparator => "edge must have the form source-target or source->target",
+            ParseErrorReason::TooManySeparators => "edge must connect exactly two nodes",
+            ParseErrorReason::UnexpectedText => "unexpected text after the edge",
+            ParseErrorReason::InvalidNodeId => "node id is missing or its quotes are not closed",
+            ParseErrorReason::InvalidWeight => "edge weight must be a positive number",
+            ParseErrorReason::InvalidPin => "pinned node must have the form id:x,y",
+            ParseErrorReason::InvalidPosition => "initial position must have the form id:x,y",
+            ParseErrorReason::InvalidCoordinate => "coordinate must be a finite number",
+            ParseErrorReason::UnknownNode => "node does not exist in the graph",
+            ParseErrorReason::InvalidLayout => "laid out graph must have the form nodes: id:x,y;...edges: ...",
+            ParseErrorReason::InvalidSize => "node size must have the form id:width,height",
+            ParseErrorReason::InvalidDimension => "width and height must be non-negative numbers",
+        };
+        write!(f, "invalid graph at position {} ('{}'): {}", self.position, self.token, description)
+    }
+}
+
+// Parse errors are thrown as JavaScript Error objects
+impl From<ParseError> for JsValue {
+    fn from(error: ParseError) -> JsValue {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+/*
+Node id interner, maps the ids used in the input to dense node indices in order of first appearance.
+Only ids that actually occur get a node, so "1000000-1000001" is a graph with two nodes.
+*/
+#[derive(Debug, Default)]
+pub(crate) struct NodeInterner {
+    indices: HashMap<String, usize>,
+    labels: Vec<String>,
+}
+
+impl NodeInterner {
+    // Index of the node with the given id, a new node is created for an id seen for the first time
+    pub(crate) fn intern(&mut self, label: &str) -> usize {
+        if let Some(&index) = self.indices.get(label) {
+            return index;
+        }
+        let index = self.labels.len();
+        self.indices.insert(label.to_string(), index);
+        self.labels.push(label.to_string());
+        index
+    }
+
+    pub(crate) fn get(&self, label: &str) -> Option<usize> {
+        self.indices.get(label).copied()
+    }
+
+    pub(crate) fn label(&self, index: usize) -> &str {
+        &self.labels[index]
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    // Original ids of the nodes, by node index
+    pub(crate) fn labels(&self) -> &[String] {
+        &self.labels
+    }
+}
+
+// Characters allowed in a node id written without quotes
+fn is_bare_id_char(c: char) -> bool {
+    !(c.is_whitespace() || matches!(c, ',' | ';' | ':' | '-' | '>' | '"'))
+}
+
+// Write a node id so that it can be read back by the parser, quoting it only when needed
+pub(crate) fn quote_label(label: &str) -> String {
+    if !label.is_empty() && label.chars().all(is_bare_id_char) {
+        return label.to_string();
+    }
+    format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/*
+Split `text` at every `separator` that is not inside quotes.
+Every part comes with its byte offset in `text`.
+*/
+fn split_unquoted(text: &str, separator: char) -> Vec<(usize, &str)> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if in_quotes && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == separator && !in_quotes {
+            parts.push((start, &text[start..i]));
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push((start, &text[start..]));
+    parts
+}
+
+/*
+Read a node id from the beginning of `text`, leading whitespace is skipped.
+Returns the id and the number of bytes consumed, `offset` is the position of `text` in the whole input.
+*/
+fn read_node_id(text: &str, offset: usize) -> Result<(String, usize), ParseError> {
+    let start = text.len() - text.trim_start().len();
+    let rest = &text[start..];
+    let error = |token: &str| ParseError {
+        position: offset + start,
+        token: token.trim().to_string(),
+        reason: ParseErrorReason::InvalidNodeId,
+    };
+
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut label = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((label, start + 1 + i + 1)),
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => label.push(escaped),
+                    None => break,
+                },
+                _ => label.push(c),
+            }
+        }
+        return Err(error(rest));
+    }
+
+    let end = rest.find(|c: char| !is_bare_id_char(c)).unwrap_or(rest.len());
+    if end == 0 {
+        return Err(error(rest));
+    }
+    Ok((rest[..end].to_string(), start + end))
+}
+
+// Parse an edge weight, `offset` is the position of `token` in the whole graph string
+fn parse_weight(token: &str, offset: usize) -> Result<f64, ParseError> {
+    let trimmed = token.trim();
+    let position = offset + token.find(trimmed).unwrap_or(0);
+    match trimmed.parse::<f64>() {
+        Ok(weight) if weight.is_finite() && weight > 0.0 => Ok(weight),
+        _ => Err(ParseError { position, token: trimmed.to_string(), reason: ParseErrorReason::InvalidWeight }),
+    }
+}
+
+/*
+Parse a single edge of the form "source-target" (undirected) or "source->target" (directed),
+optionally followed by a weight, e.g. "source->target:weight".
+`offset` is the position of `token` in the whole graph string
+*/
+fn parse_edge(token: &str, offset: usize, interner: &mut NodeInterner) -> Result<Edge, ParseError> {
+    let trimmed = token.trim();
+    let position = offset + token.find(trimmed).unwrap_or(0);
+    let error = |reason| ParseError { position, token: trimmed.to_string(), reason };
+
+    if trimmed.is_empty() {
+        return Err(error(ParseErrorReason::EmptyEdge));
+    }
+    let (source, consumed) = read_node_id(trimmed, position)?;
+    let rest = trimmed[consumed..].trim_start();
+    let (directed, separator) = if rest.starts_with("->") {
+        (true, "->")
+    } else if rest.starts_with('-') {
+        (false, "-")
+    } else {
+        return Err(error(ParseErrorReason::MissingSeparator));
+    };
+    let target_start = trimmed.len() - rest.len() + separator.len();
+    let (target, consumed) = read_node_id(&trimmed[target_start..], position + target_start)?;
+    let rest_start = target_start + consumed;
+    let rest = trimmed[rest_start..].trim_start();
+
+    let mut edge = Edge::new(interner.intern(&source), interner.intern(&target));
+    edge.directed = directed;
+    if let Some(weight) = rest.strip_prefix(':') {
+        edge.weight = parse_weight(weight, position + trimmed.len() - weight.len())?;
+    } else if rest.starts_with('-') {
+        return Err(error(ParseErrorReason::TooManySeparators));
+    } else if !rest.is_empty() {
+        return Err(error(ParseErrorReason::UnexpectedText));
+    }
+    Ok(edge)
+}
+
+/*
+Parse a string of comma separated edges, e.g. "0-1,1-2,2-0", with weights "0-1:2.5,1-2:0.5,2-0"
+or with directed edges "0->1,1->2,2-0".
+Node ids are interned, `interner` maps them to node indices afterwards.
+Whitespace around edges and node ids and a single trailing comma are allowed.
+Malformed input is reported with the position of the offending token instead of panicking.
+*/
+pub(crate) fn parse_edges(graph_str: &str, interner: &mut NodeInterner) -> Result<Vec<Edge>, ParseError> {
+    if graph_str.trim().is_empty() {
+        return Err(ParseError { position: 0, token: String::new(), reason: ParseErrorReason::EmptyGraph });
+    }
+
+    let tokens = split_unquoted(graph_str, ',');
+    let mut edges = Vec::new();
+    for (i, &(offset, token)) in tokens.iter().enumerate() {
+        let is_trailing = i == tokens.len() - 1 && i > 0 && token.trim().is_empty();
+        if !is_trailing {
+            edges.push(parse_edge(token, offset, interner)?);
+        }
+    }
+    Ok(edges)
+}
+
+/*
+Parse semicolon separated entries "id:a,b" into the node index and both numbers.
+`entry` is the reason given for malformed entries and `number` the one for values rejected by `is_valid`.
+*/
+fn parse_node_values(
+    text: &str,
+    interner: &NodeInterner,
+    entry: ParseErrorReason,
+    number: ParseErrorReason,
+    is_valid: fn(f64) -> bool,
+) -> Result<Vec<(usize, f64, f64)>, ParseError> {
+    let mut values = Vec::new();
+    for (offset, token) in split_unquoted(text, ';') {
+        let trimmed = token.trim();
+        let position = offset + token.find(trimmed).unwrap_or(0);
+        if trimmed.is_empty() {
+            continue;
+        }
+        let error = |position, token: &str, reason| ParseError { position, token: token.trim().to_string(), reason };
+
+        let (id, consumed) = read_node_id(trimmed, position)?;
+        let pair = trimmed[consumed..]
+            .strip_prefix(':')
+            .ok_or_else(|| error(position, trimmed, entry.clone()))?;
+        let (a, b) = pair
+            .split_once(',')
+            .ok_or_else(|| error(position, trimmed, entry.clone()))?;
+        let index = interner
+            .get(&id)
+            .ok_or_else(|| error(position, &trimmed[..consumed], ParseErrorReason::UnknownNode))?;
+        let a_position = position + consumed + 1;
+        let b_position = a_position + a.len() + 1;
+        let value = |value: &str, position| match value.trim().parse::<f64>() {
+            Ok(parsed) if is_valid(parsed) => Ok(parsed),
+            _ => Err(error(position, value, number.clone())),
+        };
+        values.push((index, value(a, a_position)?, value(b, b_position)?));
+    }
+    Ok(values)
+}
+
+/*
+Parse pinned nodes given as semicolon separated entries "id:x,y", e.g. "0:10,20;\"New York\":55.5,40".
+An empty string means that no node is pinned. Ids must refer to nodes known to the interner.
+*/
+pub(crate) fn parse_pins(pinned_str: &str, interner: &NodeInterner) -> Result<Vec<(usize, Point)>, ParseError> {
+    let pins = parse_node_values(pinned_str, interner, ParseErrorReason::InvalidPin, ParseErrorReason::InvalidCoordinate, f64::is_finite)?;
+    Ok(pins.into_iter().map(|(index, x, y)| (index, Point { x, y })).collect())
+}
+
+/*
+Parse initial positions, either as semicolon separated entries "id:x,y" like pins or as a whole laid out graph
+returned by the process_* functions, so the output of a previous run can be passed back as it is.
+Unlike pins, ids of nodes that are not in the graph are skipped, the graph may have changed since that run.
+*/
+pub(crate) fn parse_positions(positions_str: &str, interner: &NodeInterner) -> Result<Vec<(usize, Point)>, ParseError> {
+    let mut listed = NodeInterner::default();
+    let positions: Vec<(usize, Point)> = if positions_str.trim_start().starts_with("nodes:") {
+        parse_layout(positions_str, &mut listed)?.0.into_iter().enumerate().collect()
+    } else {
+        for (offset, token) in split_unquoted(positions_str, ';') {
+            if !token.trim().is_empty() {
+                let (id, _) = read_node_id(token, offset)?;
+                listed.intern(&id);
+            }
+        }
+        let values = parse_node_values(positions_str, &listed, ParseErrorReason::InvalidPosition, ParseErrorReason::InvalidCoordinate, f64::is_finite)?;
+        values.into_iter().map(|(index, x, y)| (index, Point { x, y })).collect()
+    };
+    Ok(positions
+        .into_iter()
+        .filter_map(|(index, position)| interner.get(listed.label(index)).map(|node| (node, position)))
+        .collect())
+}
+
+/*
+Parse node sizes given as semicolon separated entries "id:width,height", e.g. "a:30,10;b:10,10".
+Nodes without an entry keep their size. Ids must refer to nodes known to the interner.
+*/
+pub(crate) fn parse_sizes(sizes_str: &str, interner: &NodeInterner) -> Result<Vec<(usize, Size)>, ParseError> {
+    let is_size = |value: f64| value.is_finite() && value >= 0.0;
+    let sizes = parse_node_values(sizes_str, interner, ParseErrorReason::InvalidSize, ParseErrorReason::InvalidDimension, is_size)?;
+    Ok(sizes.into_iter().map(|(index, width, height)| (index, Size { width, height })).collect())
+}
+
+/*
+Parse a laid out graph as written by the process_* functions, e.g. "nodes: a:0,1;b:2,3;edges: a-b,".
+Nodes are numbered in the order they are listed and edges may only refer to listed nodes.
+Returns the position of every node and the edges.
+*/
+pub(crate) fn parse_layout(layout_str: &str, interner: &mut NodeInterner) -> Result<(Vec<Point>, Vec<Edge>), ParseError> {
+    let shift = |offset: usize| move |mut error: ParseError| {
+        error.position += offset;
+        error
+    };
+    let start = layout_str.len() - layout_str.trim_start().len();
+    let body = layout_str[start..].strip_prefix("nodes:").ok_or_else(|| ParseError {
+        position: start,
+        token: layout_str.trim().chars().take(6).collect(),
+        reason: ParseErrorReason::InvalidLayout,
+    })?;
+    let body_offset = start + "nodes:".len();
+
+    // The edges follow the last semicolon outside of quotes
+    let (edges_offset, edges_token) = *split_unquoted(body, ';').last().expect("split always returns a part");
+    let edges_start = edges_token.len() - edges_token.trim_start().len();
+    let edges_str = edges_token[edges_start..].strip_prefix("edges:").ok_or_else(|| ParseError {
+        position: body_offset + edges_offset + edges_start,
+        token: edges_token.trim().to_string(),
+        reason: ParseErrorReason::InvalidLayout,
+    })?;
+    let edges_position = body_offset + edges_offset + edges_start + "edges:".len();
+
+    let nodes_str = &body[..edges_offset];
+    for (offset, token) in split_unquoted(nodes_str, ';') {
+        if !token.trim().is_empty() {
+            let (id, _) = read_node_id(token, body_offset + offset)?;
+            interner.intern(&id);
+        }
+    }
+    let num_nodes = interner.len();
+    let mut positions = vec![Point { x: 0.0, y: 0.0 }; num_nodes];
+    for (index, position) in parse_pins(nodes_str, interner).map_err(shift(body_offset))? {
+        positions[index] = position;
+    }
+
+    let edges = if edges_str.trim().is_empty() {
+        Vec::new()
+    } else {
+        parse_edges(edges_str, interner).map_err(shift(edges_position))?
+    };
+    if interner.len() > num_nodes {
+        return Err(ParseError {
+            position: edges_position,
+            token: interner.label(num_nodes).to_string(),
+            reason: ParseErrorReason::UnknownNode,
+        });
+    }
+    Ok((positions, edges))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(graph_str: &str) -> Result<Vec<Edge>, ParseError> {
+        parse_edges(graph_str, &mut NodeInterner::default())
+    }
+
+    #[test]
+    fn parse_edge_check_if_whitespace_is_ignored() {
+        let mut interner = NodeInterner::default();
+
+        let edge = parse_edge(" 3 -\n 12 ", 0, &mut interner).unwrap();
+
+        assert_eq!(edge, Edge::new(0, 1));
+        assert_eq!(interner.labels(), ["3", "12"]);
+    }
+
+    #[test]
+    fn parse_edges_check_if_errors_are_reported() {
+        let cases = [
+            ("", 0, "", ParseErrorReason::EmptyGraph),
+            ("0-1,3", 4, "3", ParseErrorReason::MissingSeparator),
+            ("0-1, 1-\"x", 7, "\"x", ParseErrorReason::InvalidNodeId),
+            ("0-1,,1-2", 4, "", ParseErrorReason::EmptyEdge),
+            ("0-1-2", 0, "0-1-2", ParseErrorReason::TooManySeparators),
+            ("-1-2", 0, "-1-2", ParseErrorReason::InvalidNodeId),
+            ("0-1,,", 4, "", ParseErrorReason::EmptyEdge),
+            ("0-1 2", 0, "0-1 2", ParseErrorReason::UnexpectedText),
+        ];
+
+        for (graph_str, position, token, reason) in cases {
+            let error = parse(graph_str).unwrap_err();
+            assert_eq!(error, ParseError { position, token: token.to_string(), reason }, "input: {:?}", graph_str);
+        }
+    }
+
+    #[test]
+    fn parse_error_check_message() {
+        let error = parse("0-1,a-").unwrap_err();
+
+        assert_eq!(error.to_string(), "invalid graph at position 6 (''): node id is missing or its quotes are not closed");
+    }
+
+    #[test]
+    fn parse_edges_check_weights() {
+        let edges = parse("0-1:2.5, 1-2 ,2-0: 0.25").unwrap();
+
+        assert_eq!(edges.iter().map(|edge| edge.weight).collect::<Vec<_>>(), vec![2.5, 1.0, 0.25]);
+        assert_eq!(
+            parse("0-1:2,1-2:-3").unwrap_err(),
+            ParseError { position: 10, token: "-3".to_string(), reason: ParseErrorReason::InvalidWeight }
+        );
+        assert_eq!(parse("0-1:").unwrap_err().reason, ParseErrorReason::InvalidWeight);
+    }
+
+    #[test]
+    fn parse_edges_check_directed_edges() {
+        let edges = parse("0->1, 1-2, 12 -> 3:2.5").unwrap();
+
+        assert_eq!(edges.iter().map(|edge| edge.directed).collect::<Vec<_>>(), vec![true, false, true]);
+        assert_eq!((edges[2].source, edges[2].target, edges[2].weight), (3, 4, 2.5));
+        assert_eq!(
+            parse("0->1,1->").unwrap_err(),
+            ParseError { position: 8, token: "".to_string(), reason: ParseErrorReason::InvalidNodeId }
+        );
+        assert_eq!(parse("0->1->2").unwrap_err().reason, ParseErrorReason::TooManySeparators);
+    }
+
+    #[test]
+    fn parse_edges_check_string_ids() {
+        let mut interner = NodeInterner::default();
+
+        let edges = parse_edges(r#"alice-bob, bob->"carol, the \"boss\"":2, 1000000-alice"#, &mut interner).unwrap();
+
+        assert_eq!(edges[1].target, 2);
+        assert_eq!(edges[2], Edge::new(3, 0));
+        assert_eq!(interner.labels(), ["alice", "bob", "carol, the \"boss\"", "1000000"]);
+    }
+
+    #[test]
+    fn quote_label_check_round_trip() {
+        for label in ["alice", "42", "New York", "a-b", "say \"hi\"", "back\\slash", ""] {
+            let mut interner = NodeInterner::default();
+            let graph_str = format!("{}-other", quote_label(label));
+
+            parse_edges(&graph_str, &mut interner).unwrap();
+
+            assert_eq!(interner.labels()[0], label, "quoted: {}", graph_str);
+        }
+        assert_eq!(quote_label("alice"), "alice");
+        assert_eq!(quote_label("New York"), "\"New York\"");
+    }
+
+    #[test]
+    fn parse_pins_check_valid_and_invalid_input() {
+        let mut interner = NodeInterner::default();
+        parse_edges("0-1,2-3,\"x y\"-0", &mut interner).unwrap();
+
+        let pins = parse_pins(" 0:10,20; 3:-5.5,40 ;\"x y\":1,2", &interner).unwrap();
+
+        assert_eq!(pins.len(), 3);
+        assert_eq!((pins[1].0, pins[1].1.x, pins[1].1.y), (3, -5.5, 40.0));
+        assert_eq!(pins[2].0, 4);
+        assert!(parse_pins("", &interner).unwrap().is_empty());
+        assert_eq!(parse_pins("0:1,2;7:1,2", &interner).unwrap_err().reason, ParseErrorReason::UnknownNode);
+        assert_eq!(parse_pins("0:1", &interner).unwrap_err().reason, ParseErrorReason::InvalidPin);
+        assert_eq!(
+            parse_pins("0:1,2;1:3,y", &interner).unwrap_err(),
+            ParseError { position: 10, token: "y".to_string(), reason: ParseErrorReason::InvalidCoordinate }
+        );
+    }
+
+    #[test]
+    fn parse_positions_check_entries_and_previous_layouts() {
+        let mut interner = NodeInterner::default();
+        parse_edges("a-b,b-\"x y\"", &mut interner).unwrap();
+
+        let entries = parse_positions("b:1,2; gone:5,5;\"x y\":-3,4", &interner).unwrap();
+        let layout = parse_positions(" nodes: \"x y\":7,8;a:0,1;edges: \"x y\"-a,", &interner).unwrap();
+
+        assert_eq!(entries.iter().map(|&(index, p)| (index, p.x, p.y)).collect::<Vec<_>>(), vec![(1, 1.0, 2.0), (2, -3.0, 4.0)]);
+        assert_eq!(layout.iter().map(|&(index, p)| (index, p.x, p.y)).collect::<Vec<_>>(), vec![(2, 7.0, 8.0), (0, 0.0, 1.0)]);
+        assert!(parse_positions("", &interner).unwrap().is_empty());
+        assert_eq!(parse_positions("a:1", &interner).unwrap_err().reason, ParseErrorReason::InvalidPosition);
+        assert_eq!(
+            parse_positions("a:1,2;b:inf,3", &interner).unwrap_err(),
+            ParseError { position: 8, token: "inf".to_string(), reason: ParseErrorReason::InvalidCoordinate }
+        );
+    }
+
+    #[test]
+    fn parse_sizes_check_valid_and_invalid_input() {
+        let mut interner = NodeInterner::default();
+        parse_edges("a-b", &mut interner).unwrap();
+
+        let sizes = parse_sizes("b:30,10; a:0,2.5", &interner).unwrap();
+
+        assert_eq!((sizes[0].0, sizes[0].1.width, sizes[0].1.height), (1, 30.0, 10.0));
+        assert_eq!((sizes[1].0, sizes[1].1.width, sizes[1].1.height), (0, 0.0, 2.5));
+        assert_eq!(parse_sizes("a:1", &interner).unwrap_err().reason, ParseErrorReason::InvalidSize);
+        assert_eq!(
+            parse_sizes("a:1,-2", &interner).unwrap_err(),
+            ParseError { position: 4, token: "-2".to_string(), reason: ParseErrorReason::InvalidDimension }
+        );
+    }
+
+    #[test]
+    fn parse_layout_check_valid_and_invalid_input() {
+        let mut interner = NodeInterner::default();
+
+        let (positions, edges) = parse_layout("nodes: b:1,2;\"x;y\":-3,4.5;lonely:0,0;edges: \"x;y\"->b:2,", &mut interner).unwrap();
+
+        assert_eq!(interner.len(), 3);
+        assert_eq!((positions[1].x, positions[1].y), (-3.0, 4.5));
+        assert_eq!((edges[0].source, edges[0].target, edges[0].weight), (1, 0, 2.0));
+        let (positions, edges) = parse_layout("nodes: edges: ", &mut NodeInterner::default()).unwrap();
+        assert!(positions.is_empty() && edges.is_empty());
+        let error = |layout_str: &str| parse_layout(layout_str, &mut NodeInterner::default()).unwrap_err();
+        assert_eq!(error("a-b").reason, ParseErrorReason::InvalidLayout);
+        assert_eq!(error("nodes: a:1,2;b:3,4").reason, ParseErrorReason::InvalidLayout);
+        assert_eq!(error("nodes: a:1,2;edges: a-c").reason, ParseErrorReason::UnknownNode);
+        assert_eq!(
+            error("nodes: a:1,2;b:x,4;edges: a-b"),
+            ParseError { position: 15, token: "x".to_string(), reason: ParseErrorReason::InvalidCoordinate }
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::Point;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE BARNES-HUT QUADTREE USED TO APPROXIMATE REPULSIVE FORCES
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+
+// Cells deeper than this are not subdivided any more, so nodes sharing a position end up in one leaf
+const MAX_DEPTH: usize = 32;
+
+// Define Cell structure (a square region of the plane)
+#[derive(Debug)]
+struct Cell {
+    center: Point,
+    half_size: f64,
+    mass: f64,
+    mass_center: Point,
+    children: Option<[usize; 4]>,
+    bodies: Vec<usize>,
+}
+
+impl Cell {
+    fn new(center: Point, half_size: f64) -> Cell {
+        Cell {
+            center,
+            half_size,
+            mass: 0.0,
+            mass_center: Point { x: 0.0, y: 0.0 },
+            children: None,
+            bodies: Vec::new(),
+        }
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        (point.x - self.center.x).abs() <= self.half_size && (point.y - self.center.y).abs() <= self.half_size
+    }
+
+    fn quadrant(&self, point: Point) -> usize {
+        let east = if point.x >= self.center.x { 1 } else { 0 };
+        let south = if point.y >= self.center.y { 2 } else { 0 };
+        east + south
+    }
+}
+
+/*
+Barnes-Hut quadtree. Every cell stores the total mass and the center of mass of the bodies inside it,
+so a group of distant nodes can be treated as a single heavy node when computing repulsion.
+This reduces a layout iteration from O(n^2) to O(n log n).
+*/
+#[derive(Debug)]
+pub(crate) struct QuadTree {
+    cells: Vec<Cell>,
+    positions: Vec<Point>,
+    masses: Vec<f64>,
+}
+
+impl QuadTree {
+    // Build a quadtree over the given positions, each body having the given mass
+    pub(crate) fn new(positions: Vec<Point>, masses: Vec<f64>) -> QuadTree {
+        let mut min = Point { x: f64::INFINITY, y: f64::INFINITY };
+        let mut max = Point { x: f64::NEG_INFINITY, y: f64::NEG_INFINITY };
+        for position in &positions {
+            min.x = min.x.min(position.x);
+            min.y = min.y.min(position.y);
+            max.x = max.x.max(position.x);
+            max.y = max.y.max(position.y);
+        }
+        let (center, half_size) = if positions.is_empty() {
+            (Point { x: 0.0, y: 0.0 }, 1.0)
+        } else {
+            let center = Point { x: (min.x + max.x) / 2.0, y: (min.y + max.y) / 2.0 };
+            let half_size = ((max.x - min.x).max(max.y - min.y) / 2.0).max(1e-9);
+            (center, half_size)
+        };
+
+        let mut tree = QuadTree {
+            cells: vec![Cell::new(center, half_size)],
+            positions,
+            masses,
+        };
+        for body in 0..tree.positions.len() {
+            tree.insert(body);
+        }
+        for cell in &mut tree.cells {
+            if cell.mass > 0.0 {
+                cell.mass_center.x /= cell.mass;
+                cell.mass_center.y /= cell.mass;
+            }
+        }
+        tree
+    }
+
+    // Insert a body, accumulating its (weighted) position into every cell on the way down
+    fn insert(&mut self, body: usize) {
+        let position = self.positions[body];
+        let mass = self.masses[body];
+        let mut cell = 0;
+        let mut depth = 0;
+        loop {
+            self.cells[cell].mass += mass;
+            self.cells[cell].mass_center.x += position.x * mass;
+            self.cells[cell].mass_center.y += position.y * mass;
+
+            if let Some(children) = self.cells[cell].children {
+                cell = children[self.cells[cell].quadrant(position)];
+                depth += 1;
+                continue;
+            }
+            if self.cells[cell].bodies.is_empty() || depth >= MAX_DEPTH {
+                self.cells[cell].bodies.push(body);
+                return;
+            }
+
+            // Leaf already occupied - split it and push the previous bodies one level down
+            let children = self.subdivide(cell);
+            for previous in std::mem::take(&mut self.cells[cell].bodies) {
+                let previous_position = self.positions[previous];
+                let child = children[self.cells[cell].quadrant(previous_position)];
+                let previous_mass = self.masses[previous];
+                self.cells[child].mass += previous_mass;
+                self.cells[child].mass_center.x += previous_position.x * previous_mass;
+                self.cells[child].mass_center.y += previous_position.y * previous_mass;
+                self.cells[child].bodies.push(previous);
+            }
+            cell = children[self.cells[cell].quadrant(position)];
+            depth += 1;
+        }
+    }
+
+    fn subdivide(&mut self, cell: usize) -> [usize; 4] {
+        let center = self.cells[cell].center;
+        let quarter = self.cells[cell].half_size / 2.0;
+        let first = self.cells.len();
+        for quadrant in 0..4 {
+            let dx = if quadrant & 1 == 1 { quarter } else { -quarter };
+            let dy = if quadrant & 2 == 2 { quarter } else { -quarter };
+            self.cells.push(Cell::new(Point { x: center.x + dx, y: center.y + dy }, quarter));
+        }
+        let children = [first, first + 1, first + 2, first + 3];
+        self.cells[cell].children = Some(children);
+        children
+    }
+
+    /*
+    Visit everything that acts on `body`: single bodies nearby and aggregated cells far away.
+    A cell is approximated when its size divided by its distance from the body is below theta
+    and the body itself lies outside of it.
+    The callback receives the position and the mass of the interacting body or cell,
+    and the index of the body (None for an aggregated cell).
+    */
+    pub(crate) fn for_each_interaction<F: FnMut(Point, f64, Option<usize>)>(&self, body: usize, theta: f64, mut interact: F) {
+        let position = self.positions[body];
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let cell = &self.cells[index];
+            if cell.mass <= 0.0 {
+                continue;
+            }
+            match cell.children {
+                None => {
+                    for &other in &cell.bodies {
+                        if other != body {
+                            interact(self.positions[other], self.masses[other], Some(other));
+                        }
+                    }
+                }
+                Some(children) => {
+                    let dx = position.x - cell.mass_center.x;
+                    let dy = position.y - cell.mass_center.y;
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    if !cell.contains(position) && 2.0 * cell.half_size < theta * distance {
+                        interact(cell.mass_center, cell.mass, None);
+                    } else {
+                        stack.extend_from_slice(&children);
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(side: usize) -> Vec<Point> {
+        (0..side * side)
+            .map(|i| Point { x: (i % side) as f64, y: (i / side) as f64 })
+            .collect()
+    }
+
+    #[test]
+    fn quadtree_root_holds_total_mass_and_center() {
+        let tree = QuadTree::new(grid(4), vec![1.0; 16]);
+
+        assert_eq!(tree.cells[0].mass, 16.0);
+        assert!((tree.cells[0].mass_center.x - 1.5).abs() < 1e-9);
+        assert!((tree.cells[0].mass_center.y - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quadtree_with_zero_theta_visits_every_other_body() {
+        let tree = QuadTree::new(grid(5), vec![1.0; 25]);
+
+        let mut visited = 0;
+        let mut mass = 0.0;
+        tree.for_each_interaction(12, 0.0, |_, m, body| {
+            assert!(body.is_some());
+            visited += 1;
+            mass += m;
+        });
+
+        assert_eq!(visited, 24);
+        assert_eq!(mass, 24.0);
+    }
+
+    #[test]
+    fn quadtree_approximation_preserves_total_mass() {
+        let tree = QuadTree::new(grid(10), vec![2.0; 100]);
+
+        let mut visited = 0;
+        let mut mass = 0.0;
+        tree.for_each_interaction(0, 1.0, |_, m, _| {
+            visited += 1;
+            mass += m;
+        });
+
+        assert!(visited < 99);
+        assert!((mass - 198.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quadtree_handles_coincident_positions() {
+        let positions = vec![Point { x: 3.0, y: 3.0 }; 5];
+        let tree = QuadTree::new(positions, vec![1.0; 5]);
+
+        let mut visited = 0;
+        tree.for_each_interaction(0, 0.5, |_, _, _| visited += 1);
+
+        assert_eq!(visited, 4);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::distance::Adjacency;
+use crate::mds::dominant_eigenvectors;
+use crate::stress::conjugate_gradients;
+use crate::{Graph, Point};
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE SPECTRAL LAYOUT
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+The spectral layout (Hall) uses the eigenvectors of the Laplacian L = D - A for the second and third smallest
+eigenvalues as x and y coordinates: among centered, orthogonal axes of unit length they minimize the sum of
+w_ij * |p_i - p_j|^2 over the edges. The smallest eigenvalue 0 belongs to the constant vector, which would put
+every node at the same point, so it is projected out.
+The normalized variant uses L_sym = I - D^-1/2 A D^-1/2 and rescales its eigenvectors by D^-1/2, which gives the
+solutions of L x = lambda D x (degree-normalized eigenvectors, Koren) and usually spreads the nodes more evenly.
+The smallest eigenvectors are found by inverse iteration, i.e. power iteration on the inverse of the Laplacian,
+where every product is a conjugate gradient solve that only needs the edge list.
+Edge weights are read as the strength of the connection. The layout needs no random numbers.
+*/
+
+// Added to the diagonal of the Laplacian so that graphs with several components still give solvable systems
+const LAPLACIAN_SHIFT: f64 = 1e-9;
+
+// Laplacian of a graph with edge directions ignored, self loops do not count
+#[derive(Debug)]
+struct Laplacian {
+    adjacency: Adjacency,
+    degrees: Vec<f64>,
+    normalized: bool,
+}
+
+impl Laplacian {
+    fn new(graph: &Graph, normalized: bool) -> Laplacian {
+        let adjacency = Adjacency::new(graph, false);
+        let degrees = (0..adjacency.len())
+            .map(|i| adjacency.neighbors(i).filter(|&(j, _)| j != i).map(|(_, weight)| weight).sum())
+            .collect();
+        Laplacian { adjacency, degrees, normalized }
+    }
+
+    // D^-1/2, isolated nodes get 0
+    fn inverse_sqrt_degree(&self, i: usize) -> f64 {
+        if self.degrees[i] > 0.0 {
+            1.0 / self.degrees[i].sqrt()
+        } else {
+            0.0
+        }
+    }
+
+    fn product(&self, vector: &[f64]) -> Vec<f64> {
+        (0..vector.len())
+            .map(|i| {
+                let neighbors = self.adjacency.neighbors(i).filter(|&(j, _)| j != i);
+                let value = if self.normalized {
+                    let scale = self.inverse_sqrt_degree(i);
+                    vector[i] - neighbors.map(|(j, weight)| scale * weight * self.inverse_sqrt_degree(j) * vector[j]).sum::<f64>()
+                } else {
+                    self.degrees[i] * vector[i] - neighbors.map(|(j, weight)| weight * vector[j]).sum::<f64>()
+                };
+                value + LAPLACIAN_SHIFT * vector[i]
+            })
+            .collect()
+    }
+
+    // Unit eigenvector of the eigenvalue 0: constant, or the square roots of the degrees when normalized
+    fn kernel(&self) -> Vec<f64> {
+        let mut kernel: Vec<f64> = if self.normalized {
+            self.degrees.iter().map(|degree| degree.sqrt()).collect()
+        } else {
+            vec![1.0; self.degrees.len()]
+        };
+        let norm = kernel.iter().map(|value| value * value).sum::<f64>().sqrt();
+        for value in &mut kernel {
+            *value /= norm;
+        }
+        kernel
+    }
+}
+
+// Remove the component along a unit vector
+fn project_out(vector: &mut [f64], unit: &[f64]) {
+    let projection: f64 = vector.iter().zip(unit).map(|(v, u)| v * u).sum();
+    for (value, u) in vector.iter_mut().zip(unit) {
+        *value -= projection * u;
+    }
+}
+
+/*
+Spectral coordinates of all nodes, scaled by the square root of the number of nodes so that the drawing
+does not shrink as the graph grows. Returns None for graphs with fewer than two nodes or without edges.
+*/
+pub(crate) fn spectral_positions(graph: &Graph, normalized: bool) -> Option<Vec<Point>> {
+    let num_nodes = graph.nodes.len();
+    let laplacian = Laplacian::new(graph, normalized);
+    if num_nodes < 2 || laplacian.degrees.iter().all(|&degree| degree == 0.0) {
+        return None;
+    }
+
+    let kernel = laplacian.kernel();
+    let eigen = dominant_eigenvectors(num_nodes, 2, |vector| {
+        let mut rhs = vector.to_vec();
+        project_out(&mut rhs, &kernel);
+        let mut solution = vec![0.0; num_nodes];
+        conjugate_gradients(|v| laplacian.product(v), &rhs, &mut solution);
+        project_out(&mut solution, &kernel);
+        solution
+    });
+
+    let scale = (num_nodes as f64).sqrt();
+    let positions: Vec<Point> = (0..num_nodes)
+        .map(|i| {
+            let degree_scale = if normalized { laplacian.inverse_sqrt_degree(i) } else { 1.0 };
+            Point { x: eigen[0].1[i] * degree_scale * scale, y: eigen[1].1[i] * degree_scale * scale }
+        })
+        .collect();
+    if positions.iter().all(|p| p.x.is_finite() && p.y.is_finite()) {
+        Some(positions)
+    } else {
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+
+    #[test]
+    fn laplacian_check_if_kernel_is_annihilated() {
+        let mut graph = graph(4, &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 3)]);
+        graph.edges[3].weight = 2.5;
+
+        for normalized in [false, true] {
+            let laplacian = Laplacian::new(&graph, normalized);
+            let product = laplacian.product(&laplacian.kernel());
+            assert!(product.iter().all(|value| value.abs() < 1e-8), "{:?}", product);
+        }
+        assert_eq!(Laplacian::new(&graph, false).degrees, vec![2.0, 2.0, 4.5, 2.5]);
+    }
+
+    #[test]
+    fn spectral_positions_check_if_path_is_ordered() {
+        let path = graph(12, &(0..11).map(|i| (i, i + 1)).collect::<Vec<_>>());
+
+        for normalized in [false, true] {
+            let positions = spectral_positions(&path, normalized).unwrap();
+            let x: Vec<f64> = positions.iter().map(|p| p.x).collect();
+            let increasing = x.windows(2).all(|pair| pair[0] < pair[1]);
+            let decreasing = x.windows(2).all(|pair| pair[0] > pair[1]);
+            assert!(increasing || decreasing, "{:?}", x);
+        }
+    }
+
+    #[test]
+    fn spectral_positions_check_if_cycle_is_a_circle() {
+        let cycle = graph(10, &(0..10).map(|i| (i, (i + 1) % 10)).collect::<Vec<_>>());
+
+        let positions = spectral_positions(&cycle, false).unwrap();
+
+        let radii: Vec<f64> = positions.iter().map(|p| p.x.hypot(p.y)).collect();
+        assert!(radii.iter().all(|r| (r - radii[0]).abs() < 1e-4 * radii[0]), "{:?}", radii);
+        assert!(spectral_positions(&graph(3, &[]), false).is_none());
+    }
+}
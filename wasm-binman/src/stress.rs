@@ -0,0 +1,308 @@
+use crate::distance::distance_terms;
+use crate::{Convergence, Graph, Point};
+use std::collections::HashMap;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE STRESS FUNCTION AND ITS MINIMIZATION BY MAJORIZATION (SMACOF)
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+The stress of a layout is sum over pairs of w_ij * (|x_i - x_j| - d_ij)^2 with the graph-theoretic distance d_ij
+and the weight w_ij = d_ij^-2, so that errors on short distances count as much as errors on long ones.
+SMACOF (Scaling by MAjorizing a COmplicated Function) replaces the stress by a quadratic function that touches it
+at the current layout and lies above it everywhere else. Minimizing that function means solving L_w X' = L_Z(X) X,
+where L_w is the weighted Laplacian and L_Z(X) depends on the current distances (Guttman transform).
+The new layout never has a higher stress than the previous one, so the iteration stops once the stress
+does not decrease by more than a relative tolerance any more.
+The linear systems are solved by conjugate gradients, which only needs products with the sparse Laplacian.
+*/
+
+// Maximum number of conjugate gradient iterations per linear system
+const CG_ITERATIONS: usize = 200;
+
+// Conjugate gradients stop once the residual is this small relative to the right-hand side
+const CG_TOLERANCE: f64 = 1e-10;
+
+// Define Term structure (a pair of nodes whose distance in the drawing should match their distance in the graph)
+#[derive(Debug, Clone, Copy)]
+struct Term {
+    i: usize,
+    j: usize,
+    distance: f64,
+    weight: f64,
+}
+
+// The stress function of a graph, see the comment at the top of the file
+#[derive(Debug)]
+pub(crate) struct StressModel {
+    terms: Vec<Term>,
+    num_nodes: usize,
+}
+
+impl StressModel {
+    /*
+    Collect the pairs of nodes entering the stress, with `pivots` > 0 only the pairs of nodes
+    with a pivot or a neighbor (see distance.rs). Every pair is counted once, unreachable pairs are left out.
+    */
+    pub(crate) fn new(graph: &Graph, pivots: usize) -> StressModel {
+        let mut pairs: HashMap<(usize, usize), f64> = HashMap::new();
+        for (i, node_terms) in distance_terms(graph, pivots).into_iter().enumerate() {
+            for (j, distance) in node_terms {
+                if distance > 0.0 {
+                    pairs.entry((i.min(j), i.max(j))).or_insert(distance);
+                }
+            }
+        }
+        let mut terms: Vec<Term> = pairs
+            .into_iter()
+            .map(|((i, j), distance)| Term { i, j, distance, weight: 1.0 / (distance * distance) })
+            .collect();
+        terms.sort_by_key(|term| (term.i, term.j));
+        StressModel { terms, num_nodes: graph.nodes.len() }
+    }
+
+    // Stress function of the given pairs of nodes (i, j, target distance), pairs with a target distance of 0 are left out
+    pub(crate) fn from_pairs(num_nodes: usize, pairs: &[(usize, usize, f64)]) -> StressModel {
+        let terms = pairs
+            .iter()
+            .filter(|&&(_, _, distance)| distance > 0.0)
+            .map(|&(i, j, distance)| Term { i, j, distance, weight: 1.0 / (distance * distance) })
+            .collect();
+        StressModel { terms, num_nodes }
+    }
+
+    // Longest target distance, 0 without any pairs
+    pub(crate) fn max_distance(&self) -> f64 {
+        self.terms.iter().map(|term| term.distance).fold(0.0, f64::max)
+    }
+
+    // The same stress function with every target distance multiplied by `scale`, its minima are scaled alike
+    pub(crate) fn scaled(self, scale: f64) -> StressModel {
+        let terms = self
+            .terms
+            .into_iter()
+            .map(|term| Term { distance: term.distance * scale, weight: 1.0 / (term.distance * scale).powi(2), ..term })
+            .collect();
+        StressModel { terms, num_nodes: self.num_nodes }
+    }
+
+    // Stress of the given node positions
+    pub(crate) fn stress(&self, positions: &[Point]) -> f64 {
+        self.terms
+            .iter()
+            .map(|term| {
+                let length = positions[term.i].distance(positions[term.j]);
+                term.weight * (length - term.distance).powi(2)
+            })
+            .sum()
+    }
+
+    /*
+    Stress of the positions scaled by the factor that minimizes it, divided by the number of pairs
+    (the sum of w_ij * d_ij^2). It does not depend on the size of the drawing: 0 is a perfect drawing
+    and 1 is as bad as putting every node at the same point.
+    */
+    pub(crate) fn normalized_stress(&self, positions: &[Point]) -> f64 {
+        if self.terms.is_empty() {
+            return 0.0;
+        }
+        let lengths: Vec<f64> = self.terms.iter().map(|term| positions[term.i].distance(positions[term.j])).collect();
+        let (numerator, denominator) = self.terms.iter().zip(&lengths).fold((0.0, 0.0), |(numerator, denominator), (term, length)| {
+            (numerator + term.weight * term.distance * length, denominator + term.weight * length * length)
+        });
+        let scale = if denominator > 0.0 { numerator / denominator } else { 0.0 };
+        let stress: f64 = self.terms.iter().zip(&lengths).map(|(term, length)| term.weight * (scale * length - term.distance).powi(2)).sum();
+        stress / self.terms.len() as f64
+    }
+
+    // Product of the weighted Laplacian restricted to the free nodes with a vector (entries of fixed nodes are ignored)
+    fn laplacian_product(&self, vector: &[f64], fixed: &[bool]) -> Vec<f64> {
+        let mut product = vec![0.0; self.num_nodes];
+        for term in &self.terms {
+            let (i, j) = (term.i, term.j);
+            if !fixed[i] {
+                product[i] += term.weight * (vector[i] - if fixed[j] { 0.0 } else { vector[j] });
+            }
+            if !fixed[j] {
+                product[j] += term.weight * (vector[j] - if fixed[i] { 0.0 } else { vector[i] });
+            }
+        }
+        product
+    }
+
+    /*
+    Solve L_w x = rhs for the free nodes by conjugate gradients, starting from `x`.
+    Fixed nodes keep their value in `x`, their contribution has to be included in `rhs` already.
+    */
+    fn solve(&self, rhs: &[f64], x: &mut [f64], fixed: &[bool]) {
+        let free = |mut values: Vec<f64>| {
+            for (value, &is_fixed) in values.iter_mut().zip(fixed) {
+                if is_fixed {
+                    *value = 0.0;
+                }
+            }
+            values
+        };
+        conjugate_gradients(|vector| free(self.laplacian_product(vector, fixed)), &free(rhs.to_vec()), x);
+    }
+
+    // One Guttman transform: the layout minimizing the quadratic majorant of the stress at `positions`
+    fn guttman_transform(&self, positions: &mut [Point], fixed: &[bool]) {
+        let mut rhs_x = vec![0.0; self.num_nodes];
+        let mut rhs_y = vec![0.0; self.num_nodes];
+        for term in &self.terms {
+            let (p, q) = (positions[term.i], positions[term.j]);
+            let length = p.distance(q);
+            // Right-hand side L_Z(X) X, the entries of L_Z are w_ij * d_ij / |x_i - x_j| off the diagonal
+            if length > 0.0 {
+                let factor = term.weight * term.distance / length;
+                rhs_x[term.i] += factor * (p.x - q.x);
+                rhs_y[term.i] += factor * (p.y - q.y);
+                rhs_x[term.j] += factor * (q.x - p.x);
+                rhs_y[term.j] += factor * (q.y - p.y);
+            }
+            // Fixed nodes move to the right-hand side of the system
+            if fixed[term.j] {
+                rhs_x[term.i] += term.weight * q.x;
+                rhs_y[term.i] += term.weight * q.y;
+            }
+            if fixed[term.i] {
+                rhs_x[term.j] += term.weight * p.x;
+                rhs_y[term.j] += term.weight * p.y;
+            }
+        }
+
+        let mut x: Vec<f64> = positions.iter().map(|p| p.x).collect();
+        let mut y: Vec<f64> = positions.iter().map(|p| p.y).collect();
+        self.solve(&rhs_x, &mut x, fixed);
+        self.solve(&rhs_y, &mut y, fixed);
+        for (position, (x, y)) in positions.iter_mut().zip(x.into_iter().zip(y)) {
+            *position = Point { x, y };
+        }
+    }
+
+    /*
+    Run SMACOF from the given positions for at most `max_iterations` iterations.
+    Nodes marked as fixed keep their positions. The loop stops early once the stress decreases
+    by less than `tolerance` relative to its previous value.
+    */
+    pub(crate) fn smacof(&self, positions: &mut [Point], fixed: &[bool], max_iterations: usize, tolerance: f64) -> Convergence {
+        let mut stress = self.stress(positions);
+        if self.terms.is_empty() {
+            return Convergence { value: stress, iterations: 0, converged: true };
+        }
+        for iteration in 1..=max_iterations {
+            self.guttman_transform(positions, fixed);
+            let new_stress = self.stress(positions);
+            let change = (stress - new_stress) / stress.max(f64::MIN_POSITIVE);
+            stress = new_stress;
+            if change < tolerance {
+                return Convergence { value: stress, iterations: iteration, converged: true };
+            }
+        }
+        Convergence { value: stress, iterations: max_iterations, converged: false }
+    }
+}
+
+/*
+Solve A x = rhs by conjugate gradients, starting from `x`. A is symmetric and positive semi-definite
+and given by its product with a vector, so sparse matrices never have to be stored.
+Entries where both `rhs` and the products are 0 keep their value in `x`.
+*/
+pub(crate) fn conjugate_gradients<F: Fn(&[f64]) -> Vec<f64>>(multiply: F, rhs: &[f64], x: &mut [f64]) {
+    let dot = |a: &[f64], b: &[f64]| -> f64 { a.iter().zip(b).map(|(a, b)| a * b).sum() };
+
+    let product = multiply(x);
+    let mut residual: Vec<f64> = rhs.iter().zip(&product).map(|(b, p)| b - p).collect();
+    let mut direction = residual.clone();
+    let mut residual_norm = dot(&residual, &residual);
+    let threshold = CG_TOLERANCE * CG_TOLERANCE * dot(rhs, rhs).max(1e-300);
+
+    for _ in 0..CG_ITERATIONS {
+        if residual_norm <= threshold {
+            break;
+        }
+        let product = multiply(&direction);
+        let curvature = dot(&direction, &product);
+        if curvature <= 0.0 {
+            break;
+        }
+        let step = residual_norm / curvature;
+        for k in 0..x.len() {
+            x[k] += step * direction[k];
+            residual[k] -= step * product[k];
+        }
+        let new_norm = dot(&residual, &residual);
+        for (d, r) in direction.iter_mut().zip(&residual) {
+            *d = r + new_norm / residual_norm * *d;
+        }
+        residual_norm = new_norm;
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+    use crate::Rng;
+
+    fn random_positions(num_nodes: usize, seed: u64) -> Vec<Point> {
+        let mut rng = Rng::new(seed);
+        (0..num_nodes).map(|_| Point { x: rng.next_f64() * 10.0, y: rng.next_f64() * 10.0 }).collect()
+    }
+
+    #[test]
+    fn stress_check_value_of_known_layout() {
+        let path = graph(3, &[(0, 1), (1, 2)]);
+        let model = StressModel::new(&path, 0);
+        let line = [Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }, Point { x: 2.0, y: 0.0 }];
+        let squeezed = [Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }];
+
+        assert_eq!(model.stress(&line), 0.0);
+        // Pairs (1, 2) and (0, 2) are off by 1 with weights 1 and 1/4
+        assert!((model.stress(&squeezed) - 1.25).abs() < 1e-12);
+        let stretched: Vec<Point> = line.iter().map(|p| Point { x: p.x * 7.0, y: p.y }).collect();
+        assert!(model.normalized_stress(&stretched) < 1e-12);
+        assert!((model.normalized_stress(&[line[0]; 3]) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn smacof_check_if_stress_never_increases_and_converges() {
+        let cycle = graph(6, &(0..6).map(|i| (i, (i + 1) % 6)).collect::<Vec<_>>());
+        let model = StressModel::new(&cycle, 0);
+        let mut positions = random_positions(6, 3);
+        let fixed = vec![false; 6];
+
+        let mut previous = model.stress(&positions);
+        for _ in 0..10 {
+            model.smacof(&mut positions, &fixed, 1, 0.0);
+            let stress = model.stress(&positions);
+            assert!(stress <= previous + 1e-9, "{} > {}", stress, previous);
+            previous = stress;
+        }
+        let result = model.smacof(&mut positions, &fixed, 1000, 1e-6);
+
+        assert!(result.converged && result.iterations < 1000);
+        assert!((result.value - model.stress(&positions)).abs() < 1e-12);
+        // The regular hexagon with side 1.115 is optimal, its stress is 0.284
+        assert!((result.value - 0.2841).abs() < 1e-3, "stress {}", result.value);
+    }
+
+    #[test]
+    fn smacof_check_if_fixed_nodes_stay_in_place() {
+        let path = graph(4, &(0..3).map(|i| (i, i + 1)).collect::<Vec<_>>());
+        let model = StressModel::new(&path, 0);
+        let mut positions = random_positions(4, 5);
+        positions[0] = Point { x: 0.0, y: 0.0 };
+        positions[3] = Point { x: 3.0, y: 0.0 };
+        let fixed = [true, false, false, true];
+
+        let result = model.smacof(&mut positions, &fixed, 500, 1e-9);
+
+        assert_eq!((positions[0].x, positions[3].x), (0.0, 3.0));
+        assert!((positions[1].x - 1.0).abs() < 1e-3 && (positions[2].x - 2.0).abs() < 1e-3);
+        assert!(result.value < 1e-5);
+    }
+}
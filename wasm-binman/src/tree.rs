@@ -0,0 +1,444 @@
+use crate::distance::Adjacency;
+use crate::{Graph, Point};
+use std::f64::consts::PI;
+
+
+// CODE IN THIS FILE IS RESPONSIBLE FOR THE TIDY TREE AND RADIAL TREE LAYOUTS
+// TESTS ARE ADDED AT THE END OF THE FILE
+
+/*
+The tidy tree layout (Reingold-Tilford, as generalized by Walker and made linear by Buchheim, Juenger and Leipert)
+draws a rooted tree with the root on top and every node above the middle of its children.
+Subtrees are built bottom-up and pushed apart along their contours until they keep `node_spacing` on every level,
+small subtrees between two large ones are spread evenly. The radial variant maps the horizontal coordinate
+to an angle and the depth to the radius, so the root sits in the center and every level is a ring.
+Graphs that are not trees are drawn along a breadth-first spanning tree. It follows directed edges from their source
+to their target only, so the children of a node are among its successors and undirected neighbors, and a component
+with directed edges is rooted at its first node without incoming ones. Every connected component gets its own tree,
+as does every node that cannot be reached from the roots, the trees of a forest are placed next to each other.
+*/
+
+// Shape of the tree layout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TreeStyle {
+    Tidy,
+    Radial,
+}
+
+/*
+Spanning forest of a graph below an extra virtual root, which has the last index.
+The roots of the components are the children of the virtual root, children are ordered as they were discovered.
+`number[v]` is the index of v among the children of its parent.
+*/
+#[derive(Debug)]
+struct Tree {
+    children: Vec<Vec<usize>>,
+    parent: Vec<usize>,
+    number: Vec<usize>,
+    depth: Vec<usize>,
+}
+
+// Breadth-first search from `start` over the nodes whose parent is still unset, returns the nodes in the order they are reached
+fn breadth_first(adjacency: &Adjacency, start: usize, parent: &mut [usize]) -> Vec<usize> {
+    let mut order = vec![start];
+    parent[start] = start;
+    let mut next = 0;
+    while next < order.len() {
+        let node = order[next];
+        next += 1;
+        for (neighbor, _) in adjacency.neighbors(node) {
+            if parent[neighbor] == usize::MAX {
+                parent[neighbor] = node;
+                order.push(neighbor);
+            }
+        }
+    }
+    order
+}
+
+/*
+Center of the component containing `start`: the middle of a longest path found by two breadth-first searches,
+the first from `start` and the second from the farthest node found. This is the exact center of a tree
+and a node of small eccentricity otherwise.
+*/
+fn component_center(adjacency: &Adjacency, start: usize, scratch: &mut [usize]) -> usize {
+    let order = breadth_first(adjacency, start, scratch);
+    let end = order[order.len() - 1];
+    for &node in &order {
+        scratch[node] = usize::MAX;
+    }
+
+    let order = breadth_first(adjacency, end, scratch);
+    let mut path = vec![order[order.len() - 1]];
+    while path[path.len() - 1] != end {
+        path.push(scratch[path[path.len() - 1]]);
+    }
+    for &node in &order {
+        scratch[node] = usize::MAX;
+    }
+    path[path.len() / 2]
+}
+
+impl Tree {
+    /*
+    Breadth-first spanning forest along the edge directions, the tree of `root` is rooted there, every other tree
+    at a node with outgoing but without incoming directed edges or, if there is none, at the center of its component
+    */
+    fn spanning(graph: &Graph, root: Option<usize>) -> Tree {
+        let num_nodes = graph.nodes.len();
+        let adjacency = Adjacency::new(graph, true);
+        let (mut incoming, mut outgoing) = (vec![false; num_nodes], vec![false; num_nodes]);
+        for edge in graph.edges.iter().filter(|edge| edge.directed && edge.source != edge.target) {
+            outgoing[edge.source] = true;
+            incoming[edge.target] = true;
+        }
+        let is_source = |node: usize| outgoing[node] && !incoming[node];
+        let virtual_root = num_nodes;
+        let mut children = vec![Vec::new(); num_nodes + 1];
+        let mut parent = vec![usize::MAX; num_nodes + 1];
+        let mut depth = vec![0; num_nodes + 1];
+        let mut scratch = vec![usize::MAX; num_nodes];
+
+        parent[virtual_root] = virtual_root;
+        let starts = root.into_iter().chain((0..num_nodes).filter(|&node| is_source(node))).chain(0..num_nodes);
+        for start in starts {
+            if parent[start] != usize::MAX {
+                continue;
+            }
+            let component_root = if Some(start) == root || is_source(start) { start } else { component_center(&adjacency, start, &mut scratch) };
+            let order = breadth_first(&adjacency, component_root, &mut parent);
+            parent[component_root] = virtual_root;
+            children[virtual_root].push(component_root);
+            for &node in &order {
+                if node != component_root {
+                    children[parent[node]].push(node);
+                }
+                depth[node] = depth[parent[node]] + 1;
+            }
+        }
+
+        let mut number = vec![0; num_nodes + 1];
+        for siblings in &children {
+            for (index, &child) in siblings.iter().enumerate() {
+                number[child] = index;
+            }
+        }
+        Tree { children, parent, number, depth }
+    }
+
+    fn root(&self) -> usize {
+        self.children.len() - 1
+    }
+
+    fn left_sibling(&self, node: usize) -> Option<usize> {
+        if node == self.root() || self.number[node] == 0 {
+            None
+        } else {
+            Some(self.children[self.parent[node]][self.number[node] - 1])
+        }
+    }
+
+    fn leftmost_sibling(&self, node: usize) -> usize {
+        self.children[self.parent[node]][0]
+    }
+
+    // Nodes ordered so that every node comes after all of its descendants
+    fn post_order(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.children.len());
+        let mut stack = vec![self.root()];
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            stack.extend(&self.children[node]);
+        }
+        order.reverse();
+        order
+    }
+}
+
+/*
+State of Walker's algorithm in the linear time version of Buchheim et al.
+`prelim` is the preliminary coordinate of a node relative to its siblings and `modifier` the shift of its whole subtree.
+Threads link the end of a contour to the next node on the same contour in a deeper subtree,
+`shift` and `change` record the shifts of subtrees that are spread over the siblings between them.
+*/
+#[derive(Debug)]
+struct Walker<'a> {
+    tree: &'a Tree,
+    spacing: f64,
+    prelim: Vec<f64>,
+    modifier: Vec<f64>,
+    midpoint: Vec<f64>,
+    shift: Vec<f64>,
+    change: Vec<f64>,
+    thread: Vec<Option<usize>>,
+    ancestor: Vec<usize>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(tree: &'a Tree, spacing: f64) -> Walker<'a> {
+        let size = tree.children.len();
+        Walker {
+            tree,
+            spacing,
+            prelim: vec![0.0; size],
+            modifier: vec![0.0; size],
+            midpoint: vec![0.0; size],
+            shift: vec![0.0; size],
+            change: vec![0.0; size],
+            thread: vec![None; size],
+            ancestor: (0..size).collect(),
+        }
+    }
+
+    // Next node on the left (resp. right) contour of the subtree below `node`
+    fn next_left(&self, node: usize) -> Option<usize> {
+        self.tree.children[node].first().copied().or(self.thread[node])
+    }
+
+    fn next_right(&self, node: usize) -> Option<usize> {
+        self.tree.children[node].last().copied().or(self.thread[node])
+    }
+
+    // Preliminary coordinate of a node right after its left sibling (whose subtree is already placed)
+    fn place(&mut self, node: usize) {
+        let left = self.tree.left_sibling(node).map(|sibling| self.prelim[sibling] + self.spacing);
+        if self.tree.children[node].is_empty() {
+            self.prelim[node] = left.unwrap_or(0.0);
+        } else if let Some(left) = left {
+            self.prelim[node] = left;
+            self.modifier[node] = left - self.midpoint[node];
+        } else {
+            self.prelim[node] = self.midpoint[node];
+        }
+    }
+
+    // Move the subtree of `right` by `shift` and spread the shift over the subtrees between `left` and `right`
+    fn move_subtree(&mut self, left: usize, right: usize, shift: f64) {
+        let subtrees = (self.tree.number[right] - self.tree.number[left]) as f64;
+        self.change[right] -= shift / subtrees;
+        self.shift[right] += shift;
+        self.change[left] += shift / subtrees;
+        self.prelim[right] += shift;
+        self.modifier[right] += shift;
+    }
+
+    /*
+    Push the subtree of `node` to the right until it keeps the spacing from the subtrees of its left siblings
+    on every level, walking down the facing contours. Returns the new default ancestor.
+    */
+    fn apportion(&mut self, node: usize, default_ancestor: usize) -> usize {
+        let sibling = match self.tree.left_sibling(node) {
+            Some(sibling) => sibling,
+            None => return default_ancestor,
+        };
+        let mut default_ancestor = default_ancestor;
+        // Inside and outside contours of the right (p) and the left (m) part, with the sums of their modifiers
+        let (mut inner_right, mut outer_right) = (node, node);
+        let (mut inner_left, mut outer_left) = (sibling, self.tree.leftmost_sibling(node));
+        let mut sum_inner_right = self.modifier[inner_right];
+        let mut sum_outer_right = self.modifier[outer_right];
+        let mut sum_inner_left = self.modifier[inner_left];
+        let mut sum_outer_left = self.modifier[outer_left];
+
+        while let (Some(next_inner_left), Some(next_inner_right)) = (self.next_right(inner_left), self.next_left(inner_right)) {
+            inner_left = next_inner_left;
+            inner_right = next_inner_right;
+            // Both contours of a forest reach equally deep, so the outer ones continue as long as the inner ones do
+            outer_left = self.next_left(outer_left).expect("left contour ends early");
+            outer_right = self.next_right(outer_right).expect("right contour ends early");
+            self.ancestor[outer_right] = node;
+            let shift = (self.prelim[inner_left] + sum_inner_left) - (self.prelim[inner_right] + sum_inner_right) + self.spacing;
+            if shift > 0.0 {
+                let ancestor = self.ancestor[inner_left];
+                let left = if self.tree.parent[ancestor] == self.tree.parent[node] { ancestor } else { default_ancestor };
+                self.move_subtree(left, node, shift);
+                sum_inner_right += shift;
+                sum_outer_right += shift;
+            }
+            sum_inner_left += self.modifier[inner_left];
+            sum_inner_right += self.modifier[inner_right];
+            sum_outer_left += self.modifier[outer_left];
+            sum_outer_right += self.modifier[outer_right];
+        }
+
+        if let (Some(next), None) = (self.next_right(inner_left), self.next_right(outer_right)) {
+            self.thread[outer_right] = Some(next);
+            self.modifier[outer_right] += sum_inner_left - sum_outer_right;
+        }
+        if let (Some(next), None) = (self.next_left(inner_right), self.next_left(outer_left)) {
+            self.thread[outer_left] = Some(next);
+            self.modifier[outer_left] += sum_inner_right - sum_outer_left;
+            default_ancestor = node;
+        }
+        default_ancestor
+    }
+
+    // Apply the shifts recorded by move_subtree to the children of `node`
+    fn execute_shifts(&mut self, node: usize) {
+        let (mut shift, mut change) = (0.0, 0.0);
+        for &child in self.tree.children[node].iter().rev() {
+            self.prelim[child] += shift;
+            self.modifier[child] += shift;
+            change += self.change[child];
+            shift += self.shift[child] + change;
+        }
+    }
+
+    // Horizontal coordinate of every node of the tree, the leftmost node is at 0
+    fn coordinates(mut self) -> Vec<f64> {
+        let tree = self.tree;
+        for node in tree.post_order() {
+            if tree.children[node].is_empty() {
+                continue;
+            }
+            let mut default_ancestor = tree.children[node][0];
+            for &child in &tree.children[node] {
+                self.place(child);
+                default_ancestor = self.apportion(child, default_ancestor);
+            }
+            self.execute_shifts(node);
+            let children = &tree.children[node];
+            self.midpoint[node] = (self.prelim[children[0]] + self.prelim[children[children.len() - 1]]) / 2.0;
+        }
+        self.place(tree.root());
+
+        // Add up the modifiers on the way down from the root
+        let mut x = vec![0.0; tree.children.len()];
+        let mut stack = vec![(tree.root(), 0.0)];
+        while let Some((node, sum)) = stack.pop() {
+            x[node] = self.prelim[node] + sum;
+            for &child in &tree.children[node] {
+                stack.push((child, sum + self.modifier[node]));
+            }
+        }
+        let min = x.iter().copied().fold(f64::INFINITY, f64::min);
+        x.iter().map(|value| value - min).collect()
+    }
+}
+
+/*
+Compute the tree layout. `root` is the root of its component, without it every component is rooted at its center.
+In the tidy style `node_spacing` is the minimum distance between two nodes of a level and `layer_spacing` the distance
+between consecutive levels. In the radial style the levels are rings `layer_spacing` apart around the origin.
+Pinned nodes are laid out like the others, translate_to_area then moves the whole tree onto the pins.
+*/
+pub(crate) fn tree_layout(graph: &mut Graph, style: TreeStyle, root: Option<usize>, node_spacing: f64, layer_spacing: f64) -> &Graph {
+    let tree = Tree::spanning(graph, root);
+    let x = Walker::new(&tree, node_spacing).coordinates();
+
+    // The circumference gets one extra spacing, so the first and the last node do not meet
+    let width = x[..graph.nodes.len()].iter().copied().fold(0.0, f64::max) + node_spacing;
+    // A forest has several roots, which go on the first ring instead of sharing the center
+    let ring_offset = if tree.children[tree.root()].len() > 1 { 0 } else { 1 };
+    for (i, node) in graph.nodes.iter_mut().enumerate() {
+        let position = match style {
+            TreeStyle::Tidy => Point { x: x[i], y: (tree.depth[i] - 1) as f64 * layer_spacing },
+            TreeStyle::Radial => {
+                let angle = 2.0 * PI * x[i] / width;
+                let radius = (tree.depth[i] - ring_offset) as f64 * layer_spacing;
+                Point { x: radius * angle.cos(), y: radius * angle.sin() }
+            }
+        };
+        node.position = position;
+    }
+    graph
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::graph;
+    use crate::{Node, Rng};
+
+    #[test]
+    fn spanning_tree_check_roots_and_depths() {
+        // A path 0-1-2-3-4 with a triangle 1-2-5 and a separate edge 6-7
+        let graph = graph(8, &[(0, 1), (1, 2), (2, 3), (3, 4), (1, 5), (2, 5), (6, 7)]);
+
+        let centered = Tree::spanning(&graph, None);
+        let rooted = Tree::spanning(&graph, Some(4));
+
+        assert_eq!(centered.children[8], vec![2, 7]);
+        assert_eq!(centered.children[2], vec![1, 3, 5]);
+        assert_eq!(centered.depth[..5], [3, 2, 1, 2, 3]);
+        assert_eq!(rooted.children[8], vec![4, 7]);
+        assert_eq!(rooted.depth[0], 5);
+    }
+
+    #[test]
+    fn spanning_tree_check_if_children_follow_edge_direction() {
+        // 1 -> 0, 1 -> 2 -> 3 and 4 -> 3, node 3 is reached from 1 first, so 4 is a tree of its own
+        let mut graph = graph(5, &[(1, 0), (1, 2), (2, 3), (4, 3)]);
+        for edge in &mut graph.edges {
+            edge.directed = true;
+        }
+
+        let sourced = Tree::spanning(&graph, None);
+        let rooted = Tree::spanning(&graph, Some(3));
+
+        assert_eq!(sourced.children[5], vec![1, 4]);
+        assert_eq!(sourced.children[1], vec![0, 2]);
+        assert_eq!(sourced.depth[..5], [2, 1, 2, 3, 1]);
+        // Node 3 has no successors, so the given root is a single node and the sources root the rest
+        assert_eq!(rooted.children[5], vec![3, 1, 4]);
+        assert!(rooted.children[3].is_empty());
+    }
+
+    #[test]
+    fn tree_layout_check_if_parents_are_centered_and_levels_spaced() {
+        // Root 0 with children 1, 2, 3, node 1 has two children and node 3 a long chain that should not widen the tree
+        let mut graph = graph(9, &[(0, 1), (0, 2), (0, 3), (1, 4), (1, 5), (3, 6), (6, 7), (7, 8)]);
+
+        tree_layout(&mut graph, TreeStyle::Tidy, Some(0), 10.0, 25.0);
+
+        let x: Vec<f64> = graph.nodes.iter().map(|node| node.position.x).collect();
+        let y: Vec<f64> = graph.nodes.iter().map(|node| node.position.y).collect();
+        assert_eq!(y, vec![0.0, 25.0, 25.0, 25.0, 50.0, 50.0, 50.0, 75.0, 100.0]);
+        assert_eq!((x[4], x[5]), (0.0, 10.0));
+        assert_eq!(x[1], 5.0);
+        assert_eq!(x[0], (x[1] + x[3]) / 2.0);
+        assert_eq!((x[2] - x[1], x[3] - x[2]), (10.0, 10.0));
+        assert_eq!((x[6], x[7], x[8]), (x[3], x[3], x[3]));
+    }
+
+    #[test]
+    fn tree_layout_check_random_trees_for_overlaps() {
+        let mut rng = Rng::new(7);
+        for _ in 0..20 {
+            let edges: Vec<(usize, usize)> = (1..60).map(|i| ((rng.next_f64() * i as f64) as usize, i)).collect();
+            let mut graph = graph(60, &edges);
+
+            tree_layout(&mut graph, TreeStyle::Tidy, Some(0), 1.0, 1.0);
+
+            let mut levels: Vec<Vec<f64>> = Vec::new();
+            for node in &graph.nodes {
+                let level = node.position.y as usize;
+                levels.resize(levels.len().max(level + 1), Vec::new());
+                levels[level].push(node.position.x);
+            }
+            for level in &mut levels {
+                level.sort_by(f64::total_cmp);
+                assert!(level.windows(2).all(|pair| pair[1] - pair[0] > 1.0 - 1e-9), "{:?}", level);
+            }
+        }
+    }
+
+    #[test]
+    fn tree_layout_check_radial_rings() {
+        let mut star = graph(6, &[(0, 1), (0, 2), (0, 3), (1, 4), (2, 5)]);
+        let mut forest = graph(4, &[(0, 1), (2, 3)]);
+
+        tree_layout(&mut star, TreeStyle::Radial, None, 1.0, 10.0);
+        tree_layout(&mut forest, TreeStyle::Radial, Some(0), 1.0, 10.0);
+
+        let radius = |node: &Node| node.position.x.hypot(node.position.y);
+        let radii: Vec<f64> = star.nodes.iter().map(radius).collect();
+        assert!(radii[0] < 1e-9);
+        assert!(radii[1..4].iter().all(|r| (r - 10.0).abs() < 1e-9));
+        assert!(radii[4..].iter().all(|r| (r - 20.0).abs() < 1e-9));
+        let radii: Vec<f64> = forest.nodes.iter().map(radius).collect();
+        // The second tree is rooted at its center, which is node 3
+        assert!((radii[0] - 10.0).abs() < 1e-9 && (radii[3] - 10.0).abs() < 1e-9 && (radii[2] - 20.0).abs() < 1e-9);
+    }
+}
@@ -78,6 +78,9 @@ struct GraphSimple {
 struct RandomGraphBody {
     vertices: i32,
     edges: i32,
+    // Generate directed edges ("v1->v2") instead of undirected ones ("v1-v2").
+    #[serde(default)]
+    directed: bool,
 }
 
 // Endpoint to register a new user.
@@ -254,7 +257,8 @@ async fn random_graph(body: Json<RandomGraphBody>) -> impl Responder {
     let random_graph: RandomGraphBody = body.into_inner();
     let mut rng = rand::thread_rng();
     let mut graph = String::new();
-    
+    let separator = if random_graph.directed { "->" } else { "-" };
+
     // Generate random edges for the graph.
     for _ in 0..random_graph.edges {
         let v1 = rng.gen_range(1..random_graph.vertices + 1);
@@ -264,7 +268,7 @@ async fn random_graph(body: Json<RandomGraphBody>) -> impl Responder {
                 break v2;
             }
         };
-        graph.push_str(&format!("{}-{},", v1, v2));
+        graph.push_str(&format!("{}{}{},", v1, separator, v2));
     }
     graph.pop();
 